@@ -0,0 +1,74 @@
+/* Build the shared library with `cargo rustc --release --lib --crate-type cdylib --features ffi`. */
+
+#ifndef WIN_STREAMSHOT_H
+#define WIN_STREAMSHOT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum WsStatus {
+  WsStatus_OK = 0,
+  WsStatus_INVALID_ARGUMENT = 1,
+  WsStatus_WINDOW_NOT_FOUND = 2,
+  WsStatus_MONITOR_NOT_FOUND = 3,
+  WsStatus_WINDOW_CLOSED = 4,
+  WsStatus_WINDOW_MINIMIZED = 5,
+  WsStatus_INVALID_SIZE = 6,
+  WsStatus_BUFFER_TOO_SMALL = 7,
+  WsStatus_UNSUPPORTED = 8,
+  WsStatus_FAILED = 9,
+  WsStatus_PANICKED = 10,
+  WsStatus_TIMEOUT = 11,
+  WsStatus_CAPTURE_PROTECTED = 12,
+  WsStatus_ELEVATION_REQUIRED = 13,
+} WsStatus;
+
+typedef struct WsCapture WsCapture;
+
+typedef struct WsFrame {
+  uint32_t width;
+  uint32_t height;
+  uint32_t stride;
+  uint64_t seq;
+  uint8_t *data;
+  size_t len;
+} WsFrame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+WsStatus ws_find_window(const char *title, WsCapture **out);
+
+WsStatus ws_capture_window(intptr_t hwnd, WsCapture **out);
+
+WsStatus ws_capture_primary_monitor(WsCapture **out);
+
+WsStatus ws_capture_monitor(const char *name, WsCapture **out);
+
+void ws_free_capture(WsCapture *capture);
+
+WsStatus ws_capture_frame(WsCapture *capture, WsFrame *frame);
+
+/**
+ * Writes the pixels into `buffer`, which stays owned by the caller. The
+ * returned frame's `data` is NULL; only `len` and the geometry are filled.
+ */
+WsStatus ws_capture_into(WsCapture *capture, uint8_t *buffer, size_t len, WsFrame *frame);
+
+/**
+ * Releases the pixels of a frame filled by `ws_capture_frame`. Frames with
+ * NULL `data`, such as those from `ws_capture_into`, are left untouched.
+ */
+void ws_free_frame(WsFrame *frame);
+
+const char *ws_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WIN_STREAMSHOT_H */
@@ -0,0 +1,54 @@
+use std::{
+  collections::HashMap,
+  sync::{Mutex, OnceLock, PoisonError},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResolvedMethod {
+  Wgc,
+  PrintWindow,
+  BitBlt,
+}
+
+#[cfg(feature = "wgc")]
+pub(crate) const FALLBACK_CHAIN: &[ResolvedMethod] = &[
+  ResolvedMethod::Wgc,
+  ResolvedMethod::PrintWindow,
+  ResolvedMethod::BitBlt,
+];
+#[cfg(not(feature = "wgc"))]
+pub(crate) const FALLBACK_CHAIN: &[ResolvedMethod] =
+  &[ResolvedMethod::PrintWindow, ResolvedMethod::BitBlt];
+
+fn resolved() -> &'static Mutex<HashMap<isize, ResolvedMethod>> {
+  static RESOLVED: OnceLock<Mutex<HashMap<isize, ResolvedMethod>>> = OnceLock::new();
+  RESOLVED.get_or_init(Mutex::default)
+}
+
+pub(crate) fn cached_method(handle: isize) -> Option<ResolvedMethod> {
+  resolved()
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .get(&handle)
+    .copied()
+}
+
+pub(crate) fn remember_method(handle: isize, method: ResolvedMethod) {
+  resolved()
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .insert(handle, method);
+}
+
+pub(crate) fn forget_method(handle: isize) {
+  resolved()
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .remove(&handle);
+}
+
+pub(crate) fn is_blank(pixels: &[u8]) -> bool {
+  pixels
+    .chunks_exact(4)
+    .all(|pixel| pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0)
+}
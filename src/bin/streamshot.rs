@@ -0,0 +1,254 @@
+use {
+  std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+  },
+  win_streamshot::{
+    Capture, CaptureMethod, CaptureSession, MonitorFinder, Mp4Recorder, WindowFinder,
+    WindowScreenshotBuffer,
+  },
+};
+
+const USAGE: &str = "\
+usage:
+  streamshot list
+  streamshot grab <target> [--cursor] [--method print-window|bit-blt|auto] -o <file.png|file.bmp>
+  streamshot record <target> [--fps 30] [--duration 10] [--bitrate 8000000] -o <file.mp4>
+
+targets:
+  --title <text>      first window whose title contains <text>
+  --exact <title>     window with exactly this title
+  --class <name>      window with this class name
+  --pid <pid>         main window of a process
+  --hwnd <handle>     window handle, decimal or 0x-prefixed hex
+  --monitor <name>    monitor by device name, e.g. \\\\.\\DISPLAY1
+  --primary           the primary monitor";
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+enum Target {
+  Title(String),
+  Exact(String),
+  Class(String),
+  Pid(u32),
+  Hwnd(isize),
+  Monitor(String),
+  Primary,
+}
+
+struct Options {
+  target: Option<Target>,
+  output: Option<PathBuf>,
+  cursor: bool,
+  method: CaptureMethod,
+  fps: u32,
+  duration: Duration,
+  bitrate: Option<u32>,
+}
+
+impl Options {
+  fn parse(mut args: impl Iterator<Item = String>) -> CliResult<Self> {
+    let mut options = Self {
+      target: None,
+      output: None,
+      cursor: false,
+      method: CaptureMethod::default(),
+      fps: 30,
+      duration: Duration::from_secs(10),
+      bitrate: None,
+    };
+
+    while let Some(arg) = args.next() {
+      let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+      match arg.as_str() {
+        "--title" => options.target = Some(Target::Title(value()?)),
+        "--exact" => options.target = Some(Target::Exact(value()?)),
+        "--class" => options.target = Some(Target::Class(value()?)),
+        "--pid" => options.target = Some(Target::Pid(value()?.parse()?)),
+        "--hwnd" => options.target = Some(Target::Hwnd(parse_handle(&value()?)?)),
+        "--monitor" => options.target = Some(Target::Monitor(value()?)),
+        "--primary" => options.target = Some(Target::Primary),
+        "-o" | "--output" => options.output = Some(value()?.into()),
+        "--cursor" => options.cursor = true,
+        "--method" => {
+          options.method = match value()?.as_str() {
+            "print-window" => CaptureMethod::PrintWindow,
+            "bit-blt" => CaptureMethod::BitBlt,
+            "auto" => CaptureMethod::Auto,
+            other => return Err(format!("unknown capture method `{other}`").into()),
+          }
+        }
+        "--fps" => options.fps = value()?.parse()?,
+        "--duration" => options.duration = Duration::from_secs_f64(value()?.parse()?),
+        "--bitrate" => options.bitrate = Some(value()?.parse()?),
+        _ => return Err(format!("unexpected argument `{arg}`").into()),
+      }
+    }
+    Ok(options)
+  }
+
+  fn output(&self) -> CliResult<&Path> {
+    Ok(self.output.as_deref().ok_or("missing -o <file>")?)
+  }
+
+  fn window(
+    &self,
+    finder: impl FnOnce(&WindowFinder) -> win_streamshot::Result<WindowScreenshotBuffer>,
+  ) -> CliResult<WindowScreenshotBuffer> {
+    Ok(
+      finder(&WindowFinder::new()?)?
+        .with_cursor(self.cursor)
+        .with_capture_method(self.method),
+    )
+  }
+}
+
+fn parse_handle(value: &str) -> CliResult<isize> {
+  Ok(match value.strip_prefix("0x") {
+    Some(hex) => isize::from_str_radix(hex, 16)?,
+    None => value.parse()?,
+  })
+}
+
+fn main() -> ExitCode {
+  let mut args = env::args().skip(1);
+  let result = match args.next().as_deref() {
+    Some("list") => list(),
+    Some("grab") => Options::parse(args).and_then(|options| with_capture::<Grab>(&options)),
+    Some("record") => Options::parse(args).and_then(|options| with_capture::<Record>(&options)),
+    Some("help" | "-h" | "--help") => {
+      println!("{USAGE}");
+      Ok(())
+    }
+    _ => Err(USAGE.into()),
+  };
+
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("streamshot: {error}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn list() -> CliResult<()> {
+  println!("monitors:");
+  for monitor in MonitorFinder::new()?.monitors() {
+    let bounds = monitor.bounds();
+    println!(
+      "  {:<16} {}x{} at ({}, {}){}",
+      monitor.name(),
+      bounds.width(),
+      bounds.height(),
+      bounds.left,
+      bounds.top,
+      if monitor.is_primary() { " primary" } else { "" },
+    );
+  }
+
+  println!("windows:");
+  for window in WindowFinder::new()?.windows() {
+    let rect = window.rect();
+    println!(
+      "  {:#010x} pid {:<6} {:>5}x{:<5} {:<32} {}",
+      window.hwnd().0,
+      window.pid(),
+      rect.width(),
+      rect.height(),
+      window.class(),
+      window.title(),
+    );
+  }
+  Ok(())
+}
+
+trait Command {
+  fn run<C: Capture>(capture: C, options: &Options) -> CliResult<()>;
+}
+
+struct Grab;
+
+impl Command for Grab {
+  fn run<C: Capture>(mut capture: C, options: &Options) -> CliResult<()> {
+    let output = options.output()?;
+    let screenshot = capture.get_bgr_screenshot()?;
+    match extension(output).as_str() {
+      "png" => screenshot.save_png(output)?,
+      "bmp" => screenshot.save_bmp(output)?,
+      other => return Err(format!("cannot save `.{other}` files, use .png or .bmp").into()),
+    }
+    println!(
+      "saved {}x{} frame to {}",
+      screenshot.width(),
+      screenshot.height(),
+      output.display()
+    );
+    Ok(())
+  }
+}
+
+struct Record;
+
+impl Command for Record {
+  fn run<C: Capture>(capture: C, options: &Options) -> CliResult<()> {
+    let output = options.output()?;
+    if extension(output) != "mp4" {
+      return Err("recordings are written as .mp4".into());
+    }
+
+    let mut session = CaptureSession::new(capture).with_fps(options.fps);
+    let mut recorder = Mp4Recorder::new(output).with_fps(options.fps);
+    if let Some(bitrate) = options.bitrate {
+      recorder = recorder.with_bitrate(bitrate);
+    }
+    recorder.record_for(&mut session, options.duration)?;
+
+    let stats = session.stats();
+    println!(
+      "recorded {:.1}s to {} ({} frames, {} dropped, {:.1} fps)",
+      options.duration.as_secs_f64(),
+      output.display(),
+      stats.frames(),
+      stats.dropped_frames(),
+      stats.fps(),
+    );
+    Ok(())
+  }
+}
+
+fn extension(path: &Path) -> String {
+  path
+    .extension()
+    .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+    .unwrap_or_default()
+}
+
+fn with_capture<F: Command>(options: &Options) -> CliResult<()> {
+  match options.target.as_ref().ok_or("missing capture target")? {
+    Target::Title(title) => F::run(options.window(|finder| finder.find(title))?, options),
+    Target::Exact(title) => F::run(options.window(|finder| finder.find_exact(title))?, options),
+    Target::Class(class) => F::run(
+      options.window(|finder| finder.find_by_class(class))?,
+      options,
+    ),
+    Target::Pid(pid) => F::run(options.window(|finder| finder.find_by_pid(*pid))?, options),
+    Target::Hwnd(handle) => F::run(
+      options.window(|_| WindowScreenshotBuffer::from_raw_handle(*handle))?,
+      options,
+    ),
+    Target::Monitor(name) => F::run(
+      MonitorFinder::new()?
+        .find(name)?
+        .with_cursor(options.cursor),
+      options,
+    ),
+    Target::Primary => F::run(
+      MonitorFinder::new()?.primary()?.with_cursor(options.cursor),
+      options,
+    ),
+  }
+}
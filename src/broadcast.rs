@@ -0,0 +1,355 @@
+use {
+  crate::{FramePool, Screenshot, ScreenshotOwned},
+  std::{
+    collections::VecDeque,
+    ops::Not,
+    sync::{Arc, Condvar, Mutex, PoisonError, Weak},
+  },
+};
+
+const DEFAULT_SUBSCRIBER_CAPACITY: usize = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueuePolicy {
+  #[default]
+  DropOldest,
+  DropNewest,
+  Block,
+  LatestOnly,
+}
+
+pub struct Broadcast<Color> {
+  hub: Arc<Hub<Color>>,
+}
+
+struct Hub<Color> {
+  subscribers: Mutex<Vec<Weak<Queue<Color>>>>,
+  pool: FramePool,
+  policy: QueuePolicy,
+}
+
+struct Queue<Color> {
+  state: Mutex<QueueState<Color>>,
+  ready: Condvar,
+  space: Condvar,
+  capacity: usize,
+  policy: QueuePolicy,
+}
+
+struct QueueState<Color> {
+  frames: VecDeque<Arc<ScreenshotOwned<Color>>>,
+  lagged: u64,
+  closed: bool,
+  detached: bool,
+}
+
+impl<Color> Broadcast<Color> {
+  pub fn new() -> Self {
+    Self::with_policy(QueuePolicy::default())
+  }
+
+  pub fn with_policy(policy: QueuePolicy) -> Self {
+    Self {
+      hub: Arc::new(Hub {
+        subscribers: Mutex::new(Vec::new()),
+        pool: FramePool::default(),
+        policy,
+      }),
+    }
+  }
+
+  pub fn policy(&self) -> QueuePolicy {
+    self.hub.policy
+  }
+
+  pub fn subscribe(&self) -> Subscriber<Color> {
+    self.subscribe_with(DEFAULT_SUBSCRIBER_CAPACITY, self.hub.policy)
+  }
+
+  pub fn subscribe_with_capacity(&self, capacity: usize) -> Subscriber<Color> {
+    self.subscribe_with(capacity, self.hub.policy)
+  }
+
+  pub fn subscribe_with(&self, capacity: usize, policy: QueuePolicy) -> Subscriber<Color> {
+    let capacity = match policy {
+      QueuePolicy::LatestOnly => 1,
+      _ => capacity.max(1),
+    };
+    let queue = Arc::new(Queue {
+      state: Mutex::new(QueueState {
+        frames: VecDeque::with_capacity(capacity),
+        lagged: 0,
+        closed: false,
+        detached: false,
+      }),
+      ready: Condvar::new(),
+      space: Condvar::new(),
+      capacity,
+      policy,
+    });
+    self
+      .hub
+      .subscribers
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .push(Arc::downgrade(&queue));
+    Subscriber { queue }
+  }
+
+  pub fn subscriber_count(&self) -> usize {
+    let mut subscribers = self
+      .hub
+      .subscribers
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    subscribers.retain(|queue| queue.strong_count() > 0);
+    subscribers.len()
+  }
+
+  pub fn publish(&self, screenshot: &Screenshot<'_, Color>) -> usize {
+    let frame = Arc::new(self.hub.pool.take(screenshot));
+    let queues: Vec<_> = {
+      let mut subscribers = self
+        .hub
+        .subscribers
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+      subscribers.retain(|queue| queue.strong_count() > 0);
+      subscribers.iter().filter_map(Weak::upgrade).collect()
+    };
+    for queue in &queues {
+      queue.push(frame.clone());
+    }
+    queues.len()
+  }
+}
+
+impl<Color> Clone for Broadcast<Color> {
+  fn clone(&self) -> Self {
+    Self {
+      hub: self.hub.clone(),
+    }
+  }
+}
+
+impl<Color> Default for Broadcast<Color> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Color> Drop for Hub<Color> {
+  fn drop(&mut self) {
+    let subscribers = self
+      .subscribers
+      .get_mut()
+      .unwrap_or_else(PoisonError::into_inner);
+    for queue in subscribers.iter().filter_map(Weak::upgrade) {
+      queue
+        .state
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .closed = true;
+      queue.ready.notify_all();
+    }
+  }
+}
+
+impl<Color> Queue<Color> {
+  fn push(&self, frame: Arc<ScreenshotOwned<Color>>) {
+    let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+    if state.frames.len() >= self.capacity {
+      match self.policy {
+        QueuePolicy::DropOldest | QueuePolicy::LatestOnly => {
+          state.frames.pop_front();
+          state.lagged += 1;
+        }
+        QueuePolicy::DropNewest => {
+          state.lagged += 1;
+          return;
+        }
+        QueuePolicy::Block => {
+          while state.frames.len() >= self.capacity && state.detached.not() {
+            state = self
+              .space
+              .wait(state)
+              .unwrap_or_else(PoisonError::into_inner);
+          }
+          if state.detached {
+            return;
+          }
+        }
+      }
+    }
+    state.frames.push_back(frame);
+    self.ready.notify_one();
+  }
+}
+
+pub struct Subscriber<Color> {
+  queue: Arc<Queue<Color>>,
+}
+
+impl<Color> Subscriber<Color> {
+  pub fn recv(&self) -> Option<Arc<ScreenshotOwned<Color>>> {
+    let mut state = self
+      .queue
+      .state
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    loop {
+      if let Some(frame) = state.frames.pop_front() {
+        self.queue.space.notify_one();
+        return Some(frame);
+      }
+      if state.closed {
+        return None;
+      }
+      state = self
+        .queue
+        .ready
+        .wait(state)
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+  }
+
+  pub fn try_recv(&self) -> Option<Arc<ScreenshotOwned<Color>>> {
+    let frame = self
+      .queue
+      .state
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .frames
+      .pop_front();
+    self.queue.space.notify_one();
+    frame
+  }
+
+  pub fn lagged(&self) -> u64 {
+    self
+      .queue
+      .state
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .lagged
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.queue.capacity
+  }
+
+  pub fn policy(&self) -> QueuePolicy {
+    self.queue.policy
+  }
+}
+
+impl<Color> Drop for Subscriber<Color> {
+  fn drop(&mut self) {
+    self
+      .queue
+      .state
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .detached = true;
+    self.queue.space.notify_all();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{FrameMeta, BGRA},
+    std::{marker::PhantomData, thread},
+  };
+
+  fn publish(broadcast: &Broadcast<BGRA>, value: u8) -> usize {
+    broadcast.publish(&Screenshot {
+      width: 1,
+      height: 1,
+      stride: 4,
+      image: &vec![value; 4],
+      meta: FrameMeta::new(value as u64),
+      marker: PhantomData,
+    })
+  }
+
+  fn drain(subscriber: &Subscriber<BGRA>) -> Vec<u8> {
+    std::iter::from_fn(|| subscriber.try_recv())
+      .map(|frame| frame.as_bytes()[0])
+      .collect()
+  }
+
+  #[test]
+  fn drop_oldest_keeps_the_newest_frames() {
+    let broadcast = Broadcast::new();
+    let subscriber = broadcast.subscribe_with(2, QueuePolicy::DropOldest);
+    for value in 1..=3 {
+      assert_eq!(publish(&broadcast, value), 1);
+    }
+    assert_eq!(drain(&subscriber), [2, 3]);
+    assert_eq!(subscriber.lagged(), 1);
+  }
+
+  #[test]
+  fn drop_newest_keeps_the_oldest_frames() {
+    let broadcast = Broadcast::new();
+    let subscriber = broadcast.subscribe_with(2, QueuePolicy::DropNewest);
+    for value in 1..=3 {
+      publish(&broadcast, value);
+    }
+    assert_eq!(drain(&subscriber), [1, 2]);
+    assert_eq!(subscriber.lagged(), 1);
+  }
+
+  #[test]
+  fn latest_only_holds_a_single_frame() {
+    let broadcast = Broadcast::with_policy(QueuePolicy::LatestOnly);
+    let subscriber = broadcast.subscribe_with_capacity(8);
+    assert_eq!(subscriber.capacity(), 1);
+    for value in 1..=3 {
+      publish(&broadcast, value);
+    }
+    assert_eq!(drain(&subscriber), [3]);
+    assert_eq!(subscriber.lagged(), 2);
+  }
+
+  #[test]
+  fn block_waits_for_the_subscriber() {
+    let broadcast = Broadcast::new();
+    let subscriber = broadcast.subscribe_with(1, QueuePolicy::Block);
+    publish(&broadcast, 1);
+    let publisher = {
+      let broadcast = broadcast.clone();
+      thread::spawn(move || publish(&broadcast, 2))
+    };
+    assert_eq!(subscriber.recv().unwrap().as_bytes()[0], 1);
+    assert_eq!(publisher.join().unwrap(), 1);
+    assert_eq!(drain(&subscriber), [2]);
+    assert_eq!(subscriber.lagged(), 0);
+  }
+
+  #[test]
+  fn dropped_subscribers_release_blocked_publishers() {
+    let broadcast = Broadcast::new();
+    let subscriber = broadcast.subscribe_with(1, QueuePolicy::Block);
+    publish(&broadcast, 1);
+    let publisher = {
+      let broadcast = broadcast.clone();
+      thread::spawn(move || publish(&broadcast, 2))
+    };
+    drop(subscriber);
+    publisher.join().unwrap();
+    assert_eq!(broadcast.subscriber_count(), 0);
+  }
+
+  #[test]
+  fn recv_ends_once_the_broadcast_is_dropped() {
+    let broadcast = Broadcast::new();
+    let subscriber = broadcast.subscribe();
+    publish(&broadcast, 1);
+    drop(broadcast);
+    assert_eq!(subscriber.recv().unwrap().as_bytes()[0], 1);
+    assert!(subscriber.recv().is_none());
+  }
+}
@@ -0,0 +1,113 @@
+use std::{array, sync::OnceLock};
+
+fn f16_to_f32(bits: u16) -> f32 {
+  let sign = ((bits >> 15) as u32) << 31;
+  let exponent = ((bits >> 10) & 0x1f) as u32;
+  let mantissa = (bits & 0x3ff) as u32;
+  match exponent {
+    0 => {
+      let magnitude = mantissa as f32 / (1 << 24) as f32;
+      f32::from_bits(magnitude.to_bits() | sign)
+    }
+    0x1f => f32::from_bits(sign | 0x7f80_0000 | mantissa << 13),
+    _ => f32::from_bits(sign | (exponent + 112) << 23 | mantissa << 13),
+  }
+}
+
+pub(crate) fn f16_channel(pixel: &[u8], index: usize) -> f32 {
+  f16_to_f32(u16::from_le_bytes([pixel[2 * index], pixel[2 * index + 1]]))
+}
+
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+  let bits = value.to_bits();
+  let sign = ((bits >> 16) & 0x8000) as u16;
+  let exponent = ((bits >> 23) & 0xff) as i32 - 112;
+  let mantissa = bits & 0x7f_ffff;
+
+  if value.is_nan() {
+    return sign | 0x7e00;
+  }
+  if exponent >= 0x1f {
+    return sign | 0x7c00;
+  }
+  if exponent <= 0 {
+    if exponent < -10 {
+      return sign;
+    }
+    return sign | ((mantissa | 0x80_0000) >> (14 - exponent)) as u16;
+  }
+  let half = (exponent as u32) << 10 | mantissa >> 13;
+  sign | (half + (mantissa >> 12 & 1)) as u16
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+  let value = value.clamp(0.0, 1.0);
+  match value <= 0.003_130_8 {
+    true => value * 12.92,
+    false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+  }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+  match value <= 0.040_45 {
+    true => value / 12.92,
+    false => ((value + 0.055) / 1.055).powf(2.4),
+  }
+}
+
+pub fn srgb8_to_linear(value: u8) -> f32 {
+  static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+  TABLE.get_or_init(|| array::from_fn(|index| srgb_to_linear(index as f32 / 255.0)))[value as usize]
+}
+
+pub fn linear_to_srgb8(value: f32) -> u8 {
+  unorm8(linear_to_srgb(value))
+}
+
+pub(crate) fn unorm8(value: f32) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn srgb8_round_trips_through_linear() {
+    for value in 0..=255 {
+      assert_eq!(linear_to_srgb8(srgb8_to_linear(value)), value);
+    }
+    assert_eq!(srgb8_to_linear(0), 0.0);
+    assert!((srgb8_to_linear(255) - 1.0).abs() < 1e-6);
+    assert!((srgb8_to_linear(128) - 0.215_861).abs() < 1e-5);
+  }
+
+  #[test]
+  fn linear_to_srgb_clamps_out_of_range_values() {
+    assert_eq!(linear_to_srgb8(-0.5), 0);
+    assert_eq!(linear_to_srgb8(2.0), 255);
+    assert_eq!(unorm8(0.5), 128);
+  }
+
+  #[test]
+  fn encodes_f16() {
+    assert_eq!(f32_to_f16(1.0), 0x3c00);
+    assert_eq!(f32_to_f16(0.5), 0x3800);
+    assert_eq!(f32_to_f16(-2.0), 0xc000);
+    assert_eq!(f32_to_f16(65504.0), 0x7bff);
+    assert_eq!(f32_to_f16(1e6), 0x7c00);
+    assert_eq!(f32_to_f16(f32::NAN), 0x7e00);
+    assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+    assert_eq!(f32_to_f16(1e-10), 0);
+  }
+
+  #[test]
+  fn f16_round_trips() {
+    for value in [0.0, 1.0, -0.25, 0.333_251_95, 1024.0, 2f32.powi(-20)] {
+      assert_eq!(f16_to_f32(f32_to_f16(value)), value);
+    }
+    let pixel = [0x00, 0x3c, 0x00, 0xb8];
+    assert_eq!(f16_channel(&pixel, 0), 1.0);
+    assert_eq!(f16_channel(&pixel, 1), -0.5);
+  }
+}
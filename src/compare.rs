@@ -0,0 +1,231 @@
+use {
+  crate::{PixelFormat, Rect, Result, Screenshot, StreamshotError},
+  png::{BitDepth, ColorType, Transformations},
+  std::{
+    env,
+    fs::{self, File},
+    io::BufReader,
+    ops::Not,
+    path::{Path, PathBuf},
+  },
+};
+
+const UPDATE_GOLDEN_VAR: &str = "STREAMSHOT_UPDATE_GOLDEN";
+const DIFF_COLOR: [u8; 3] = [255, 0, 255];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GoldenTolerance {
+  channel: u8,
+  ignore: Vec<Rect>,
+}
+
+impl GoldenTolerance {
+  pub fn exact() -> Self {
+    Self::default()
+  }
+
+  pub fn with_channel_tolerance(mut self, channel: u8) -> Self {
+    self.channel = channel;
+    self
+  }
+
+  pub fn channel_tolerance(&self) -> u8 {
+    self.channel
+  }
+
+  pub fn with_ignore_region(mut self, region: Rect) -> Self {
+    self.ignore.push(region);
+    self
+  }
+
+  pub fn ignore_regions(&self) -> &[Rect] {
+    &self.ignore
+  }
+
+  fn ignores(&self, x: i32, y: i32) -> bool {
+    self
+      .ignore
+      .iter()
+      .any(|region| x >= region.left && x < region.right && y >= region.top && y < region.bottom)
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct GoldenComparison {
+  width: u32,
+  height: u32,
+  mismatched_pixels: usize,
+  max_difference: u8,
+  diff: Vec<u8>,
+}
+
+impl GoldenComparison {
+  pub fn matches(&self) -> bool {
+    self.mismatched_pixels == 0
+  }
+
+  pub fn mismatched_pixels(&self) -> usize {
+    self.mismatched_pixels
+  }
+
+  pub fn max_difference(&self) -> u8 {
+    self.max_difference
+  }
+
+  pub fn save_diff(&self, path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, encode_rgb(self.width, self.height, &self.diff)?)?;
+    Ok(())
+  }
+}
+
+pub fn compare_golden<Color: PixelFormat>(
+  screenshot: &Screenshot<'_, Color>,
+  path: impl AsRef<Path>,
+  tolerance: &GoldenTolerance,
+) -> Result<GoldenComparison> {
+  let (width, height, golden) = load_rgb(path.as_ref())?;
+  if (width, height) != (screenshot.width(), screenshot.height()) {
+    return Err(StreamshotError::InvalidSize {
+      width: screenshot.width() as i32,
+      height: screenshot.height() as i32,
+    });
+  }
+
+  let actual = screenshot.to_rgb();
+  let mut diff = Vec::with_capacity(actual.len());
+  let mut mismatched_pixels = 0;
+  let mut max_difference = 0;
+  for (index, (actual, golden)) in actual
+    .chunks_exact(3)
+    .zip(golden.chunks_exact(3))
+    .enumerate()
+  {
+    let (x, y) = (
+      (index % width as usize) as i32,
+      (index / width as usize) as i32,
+    );
+    if tolerance.ignores(x, y) {
+      diff.extend(actual.iter().map(|&value| value / 4));
+      continue;
+    }
+    let difference = actual
+      .iter()
+      .zip(golden)
+      .map(|(&actual, &golden)| actual.abs_diff(golden))
+      .max()
+      .unwrap_or(0);
+    max_difference = max_difference.max(difference);
+    match difference > tolerance.channel {
+      true => {
+        mismatched_pixels += 1;
+        diff.extend_from_slice(&DIFF_COLOR);
+      }
+      false => diff.extend(actual.iter().map(|&value| 128 + value / 2)),
+    }
+  }
+
+  Ok(GoldenComparison {
+    width,
+    height,
+    mismatched_pixels,
+    max_difference,
+    diff,
+  })
+}
+
+#[track_caller]
+pub fn assert_matches_golden<Color: PixelFormat>(
+  screenshot: &Screenshot<'_, Color>,
+  path: impl AsRef<Path>,
+  tolerance: &GoldenTolerance,
+) {
+  let path = path.as_ref();
+  let actual_path = sibling(path, "actual");
+  if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+    if let Err(error) = screenshot.save_png(path) {
+      panic!("failed to update golden image {}: {error}", path.display());
+    }
+    return;
+  }
+  if path.exists().not() {
+    let _ = screenshot.save_png(&actual_path);
+    panic!(
+      "golden image {} does not exist, the capture was written to {}; set {UPDATE_GOLDEN_VAR} to accept it",
+      path.display(),
+      actual_path.display(),
+    );
+  }
+
+  let comparison = match compare_golden(screenshot, path, tolerance) {
+    Ok(comparison) => comparison,
+    Err(error) => {
+      let _ = screenshot.save_png(&actual_path);
+      panic!("failed to compare against {}: {error}", path.display());
+    }
+  };
+  if comparison.matches() {
+    return;
+  }
+  let diff_path = sibling(path, "diff");
+  let _ = screenshot.save_png(&actual_path);
+  let _ = comparison.save_diff(&diff_path);
+  panic!(
+    "{} pixels differ from {} by more than {} (max difference {}), see {} and {}",
+    comparison.mismatched_pixels,
+    path.display(),
+    tolerance.channel,
+    comparison.max_difference,
+    actual_path.display(),
+    diff_path.display(),
+  );
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+  let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+  path.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+fn load_rgb(path: &Path) -> Result<(u32, u32, Vec<u8>)> {
+  let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+  decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+  let mut reader = decoder.read_info().map_err(encoding_error)?;
+  let Some(size) = reader.output_buffer_size() else {
+    return Err(StreamshotError::Unsupported("golden image is too large"));
+  };
+  let mut image = vec![0; size];
+  let info = reader.next_frame(&mut image).map_err(encoding_error)?;
+  image.truncate(info.buffer_size());
+  let rgb = match info.color_type {
+    ColorType::Rgb => image,
+    ColorType::Rgba => image
+      .chunks_exact(4)
+      .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+      .collect(),
+    ColorType::Grayscale => image.iter().flat_map(|&gray| [gray; 3]).collect(),
+    ColorType::GrayscaleAlpha => image
+      .chunks_exact(2)
+      .flat_map(|pixel| [pixel[0]; 3])
+      .collect(),
+    ColorType::Indexed => {
+      return Err(StreamshotError::Unsupported(
+        "indexed golden images are not supported",
+      ))
+    }
+  };
+  Ok((info.width, info.height, rgb))
+}
+
+fn encode_rgb(width: u32, height: u32, image: &[u8]) -> Result<Vec<u8>> {
+  let mut png = Vec::new();
+  let mut encoder = png::Encoder::new(&mut png, width, height);
+  encoder.set_color(ColorType::Rgb);
+  encoder.set_depth(BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(encoding_error)?;
+  writer.write_image_data(image).map_err(encoding_error)?;
+  writer.finish().map_err(encoding_error)?;
+  Ok(png)
+}
+
+fn encoding_error(error: impl std::error::Error) -> StreamshotError {
+  StreamshotError::Encoding(error.to_string())
+}
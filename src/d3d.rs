@@ -0,0 +1,351 @@
+use {
+  crate::{
+    gpu_yuv::GpuYuvConverter, shared_texture::SharedTextureExport, FrameBuffer, FrameMeta,
+    GpuFrame, Result, SharedTexture, SharedTextureKind, StreamshotError, YuvFormat, YuvFrame,
+    YuvMatrix,
+  },
+  std::slice,
+  windows::{
+    core::{ComInterface, Error},
+    Win32::{
+      Foundation::E_FAIL,
+      Graphics::{
+        Direct3D::{D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_UNKNOWN},
+        Direct3D11::{
+          D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D, D3D11_BIND_FLAG,
+          D3D11_BIND_SHADER_RESOURCE, D3D11_BOX, D3D11_CPU_ACCESS_FLAG, D3D11_CPU_ACCESS_READ,
+          D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ,
+          D3D11_RESOURCE_MISC_FLAG, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT,
+          D3D11_USAGE_STAGING,
+        },
+        Dxgi::{Common::DXGI_FORMAT_R16G16B16A16_FLOAT, IDXGIAdapter},
+      },
+    },
+  },
+};
+
+pub(crate) struct D3dReadback {
+  device: ID3D11Device,
+  context: ID3D11DeviceContext,
+  staging: Option<ID3D11Texture2D>,
+  output: Option<ID3D11Texture2D>,
+  shared: Option<SharedTextureExport>,
+  yuv: Option<GpuYuvConverter>,
+}
+
+impl D3dReadback {
+  pub(crate) fn new(adapter: Option<&IDXGIAdapter>) -> Result<Self> {
+    let driver_type = match adapter {
+      Some(_) => D3D_DRIVER_TYPE_UNKNOWN,
+      None => D3D_DRIVER_TYPE_HARDWARE,
+    };
+
+    let mut device = None;
+    let mut context = None;
+    unsafe {
+      D3D11CreateDevice(
+        adapter,
+        driver_type,
+        None,
+        D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+        None,
+        D3D11_SDK_VERSION,
+        Some(&mut device),
+        None,
+        Some(&mut context),
+      )?;
+    }
+
+    match (device, context) {
+      (Some(device), Some(context)) => Ok(Self {
+        device,
+        context,
+        staging: None,
+        output: None,
+        shared: None,
+        yuv: None,
+      }),
+      _ => Err(Error::new(E_FAIL, "D3D11CreateDevice error".into()).into()),
+    }
+  }
+
+  pub(crate) fn device(&self) -> &ID3D11Device {
+    &self.device
+  }
+
+  pub(crate) fn reset(&mut self) {
+    self.staging = None;
+    self.output = None;
+    if let Some(shared) = &mut self.shared {
+      shared.reset();
+    }
+  }
+
+  pub(crate) fn copy_to_texture(
+    &mut self,
+    texture: &ID3D11Texture2D,
+    left: i32,
+    top: i32,
+    width: i32,
+    height: i32,
+  ) -> Result<()> {
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      texture.GetDesc(&mut desc);
+    }
+    let region = crop_box(&desc, left, top, width, height)?;
+    desc.Width = region.right - region.left;
+    desc.Height = region.bottom - region.top;
+    desc.MipLevels = 1;
+    desc.ArraySize = 1;
+    desc.Usage = D3D11_USAGE_DEFAULT;
+    desc.BindFlags = D3D11_BIND_SHADER_RESOURCE;
+    desc.CPUAccessFlags = D3D11_CPU_ACCESS_FLAG(0);
+    desc.MiscFlags = D3D11_RESOURCE_MISC_FLAG(0);
+    let output = reuse_texture(&self.device, &mut self.output, desc)?;
+    unsafe {
+      self
+        .context
+        .CopySubresourceRegion(&output, 0, 0, 0, 0, texture, 0, Some(&region));
+    }
+    Ok(())
+  }
+
+  pub(crate) fn set_shared(&mut self, kind: Option<SharedTextureKind>) {
+    self.shared = kind.map(SharedTextureExport::new);
+  }
+
+  pub(crate) fn shared_texture(&self) -> Option<SharedTexture> {
+    self.shared.as_ref().and_then(SharedTextureExport::info)
+  }
+
+  pub(crate) fn export_shared(
+    &mut self,
+    texture: &ID3D11Texture2D,
+    left: i32,
+    top: i32,
+    width: i32,
+    height: i32,
+  ) -> Result<()> {
+    let shared = match &mut self.shared {
+      Some(shared) => shared,
+      None => return Ok(()),
+    };
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      texture.GetDesc(&mut desc);
+    }
+    let region = crop_box(&desc, left, top, width, height)?;
+    shared.export(&self.device, &self.context, texture, &region)
+  }
+
+  pub(crate) fn convert_yuv(
+    &mut self,
+    size: Option<(u32, u32)>,
+    format: YuvFormat,
+    matrix: YuvMatrix,
+  ) -> Result<YuvFrame> {
+    let output = match &self.output {
+      Some(output) => output,
+      None => return Err(Error::new(E_FAIL, "no frame captured yet".into()).into()),
+    };
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      output.GetDesc(&mut desc);
+    }
+    let converter = match &mut self.yuv {
+      Some(converter) => converter,
+      yuv => yuv.insert(GpuYuvConverter::new(&self.device)?),
+    };
+    converter.convert(
+      &self.device,
+      &self.context,
+      output,
+      size.unwrap_or((desc.Width, desc.Height)),
+      format,
+      matrix,
+    )
+  }
+
+  pub(crate) fn gpu_frame(&self, meta: FrameMeta) -> Option<GpuFrame<'_>> {
+    let output = self.output.as_ref()?;
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      output.GetDesc(&mut desc);
+    }
+    Some(GpuFrame::new(
+      output,
+      &self.device,
+      &self.context,
+      (desc.Width, desc.Height),
+      desc.Format == DXGI_FORMAT_R16G16B16A16_FLOAT,
+      meta,
+    ))
+  }
+
+  pub(crate) fn copy_to_frame(
+    &mut self,
+    texture: &ID3D11Texture2D,
+    left: i32,
+    top: i32,
+    frame: &mut FrameBuffer,
+  ) -> Result<()> {
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      texture.GetDesc(&mut desc);
+    }
+    let width = frame.width() as i32;
+    let height = frame.height() as i32;
+    let region = crop_box(&desc, left, top, width, height)?;
+    desc.Width = region.right - region.left;
+    desc.Height = region.bottom - region.top;
+    desc.MipLevels = 1;
+    desc.ArraySize = 1;
+    desc.Usage = D3D11_USAGE_STAGING;
+    desc.BindFlags = D3D11_BIND_FLAG(0);
+    desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+    desc.MiscFlags = D3D11_RESOURCE_MISC_FLAG(0);
+    let staging = reuse_texture(&self.device, &mut self.staging, desc)?;
+
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    unsafe {
+      self
+        .context
+        .CopySubresourceRegion(&staging, 0, 0, 0, 0, texture, 0, Some(&region));
+      self
+        .context
+        .Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+    }
+
+    let region_width = desc.Width as i32;
+    let region_height = desc.Height as i32;
+    let pitch = mapped.RowPitch as i32;
+    let source =
+      unsafe { slice::from_raw_parts(mapped.pData as *const u8, (pitch * region_height) as usize) };
+
+    let x_offset = region.left as i32 - left;
+    let y_offset = region.top as i32 - top;
+
+    let hdr = desc.Format == DXGI_FORMAT_R16G16B16A16_FLOAT;
+    let bytes_per_pixel = if hdr { 8 } else { 4 };
+    let buffer = match hdr {
+      true => frame.hdr_buffer_mut(),
+      false => frame.buffer_mut(),
+    };
+    if region_width != width || region_height != height {
+      buffer.fill(0);
+    }
+    let row_len = (region_width * bytes_per_pixel) as usize;
+    for y in 0..region_height {
+      let src = (y * pitch) as usize;
+      let dst = (((y + y_offset) * width + x_offset) * bytes_per_pixel) as usize;
+      buffer[dst..dst + row_len].copy_from_slice(&source[src..src + row_len]);
+    }
+
+    unsafe {
+      self.context.Unmap(&staging, 0);
+    }
+    match hdr {
+      true => frame.resolve_hdr(),
+      false => frame.clear_hdr(),
+    }
+    frame.stamp();
+    Ok(())
+  }
+}
+
+fn crop_box(
+  desc: &D3D11_TEXTURE2D_DESC,
+  left: i32,
+  top: i32,
+  width: i32,
+  height: i32,
+) -> Result<D3D11_BOX> {
+  let x_start = left.clamp(0, desc.Width as i32);
+  let x_end = (left + width).clamp(x_start, desc.Width as i32);
+  let y_start = top.clamp(0, desc.Height as i32);
+  let y_end = (top + height).clamp(y_start, desc.Height as i32);
+  if x_start == x_end || y_start == y_end {
+    return Err(StreamshotError::InvalidSize {
+      width: x_end - x_start,
+      height: y_end - y_start,
+    });
+  }
+  Ok(D3D11_BOX {
+    left: x_start as u32,
+    top: y_start as u32,
+    front: 0,
+    right: x_end as u32,
+    bottom: y_end as u32,
+    back: 1,
+  })
+}
+
+fn reuse_texture(
+  device: &ID3D11Device,
+  slot: &mut Option<ID3D11Texture2D>,
+  desc: D3D11_TEXTURE2D_DESC,
+) -> Result<ID3D11Texture2D> {
+  if let Some(texture) = slot {
+    let mut existing = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      texture.GetDesc(&mut existing);
+    }
+    if existing.Width == desc.Width
+      && existing.Height == desc.Height
+      && existing.Format == desc.Format
+    {
+      return Ok(texture.clone());
+    }
+  }
+
+  let mut texture = None;
+  unsafe {
+    device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+  }
+  let texture = texture.ok_or_else(|| Error::new(E_FAIL, "CreateTexture2D error".into()))?;
+  *slot = Some(texture.clone());
+  Ok(texture)
+}
+
+pub(crate) fn texture_from<T: ComInterface>(resource: Option<T>) -> Result<ID3D11Texture2D> {
+  let texture = resource
+    .ok_or_else(|| Error::new(E_FAIL, "missing frame resource".into()))?
+    .cast()?;
+  Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn desc(width: u32, height: u32) -> D3D11_TEXTURE2D_DESC {
+    D3D11_TEXTURE2D_DESC {
+      Width: width,
+      Height: height,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn crop_box_clamps_to_the_texture() {
+    let crop = crop_box(&desc(100, 50), -10, 40, 30, 30).unwrap();
+    assert_eq!(
+      (crop.left, crop.top, crop.right, crop.bottom),
+      (0, 40, 20, 50)
+    );
+    assert_eq!((crop.front, crop.back), (0, 1));
+  }
+
+  #[test]
+  fn crop_box_rejects_regions_outside_the_texture() {
+    assert!(matches!(
+      crop_box(&desc(100, 50), 100, 0, 10, 10),
+      Err(StreamshotError::InvalidSize {
+        width: 0,
+        height: 10
+      })
+    ));
+    assert!(crop_box(&desc(100, 50), 0, -20, 10, 20).is_err());
+  }
+}
@@ -0,0 +1,48 @@
+use {
+  crate::wrappers::HdeskWrapper,
+  std::mem::size_of_val,
+  windows::Win32::{
+    Foundation::HANDLE,
+    System::StationsAndDesktops::{GetUserObjectInformationW, UOI_NAME},
+  },
+};
+
+const DEFAULT_DESKTOP: &str = "Default";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DesktopState {
+  #[default]
+  Available,
+  Unavailable,
+}
+
+impl DesktopState {
+  pub fn current() -> Self {
+    let Ok(desktop) = HdeskWrapper::open_input() else {
+      return Self::Unavailable;
+    };
+    let mut name = [0u16; 64];
+    let mut needed = 0;
+    let found = unsafe {
+      GetUserObjectInformationW(
+        HANDLE(desktop.inner().0),
+        UOI_NAME,
+        Some(name.as_mut_ptr().cast()),
+        size_of_val(&name) as u32,
+        Some(&mut needed),
+      )
+    };
+    if found.as_bool() {
+      let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+      if String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case(DEFAULT_DESKTOP) {
+        return Self::Available;
+      }
+    }
+    Self::Unavailable
+  }
+
+  pub fn is_available(self) -> bool {
+    self == Self::Available
+  }
+}
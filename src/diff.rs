@@ -0,0 +1,149 @@
+use crate::{PixelFormat, Rect, Screenshot};
+
+const DEFAULT_TILE_SIZE: u32 = 32;
+
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn diff(&self, previous: &Screenshot<'_, Color>) -> Vec<Rect> {
+    self.diff_with_tile_size(previous, DEFAULT_TILE_SIZE)
+  }
+
+  pub fn diff_with_tile_size(&self, previous: &Screenshot<'_, Color>, tile_size: u32) -> Vec<Rect> {
+    let mut changed = Vec::new();
+    self.diff_into(previous, tile_size, &mut changed);
+    changed
+  }
+
+  pub fn diff_into(
+    &self,
+    previous: &Screenshot<'_, Color>,
+    tile_size: u32,
+    changed: &mut Vec<Rect>,
+  ) {
+    changed.clear();
+    let full = Rect {
+      left: 0,
+      top: 0,
+      right: self.width as i32,
+      bottom: self.height as i32,
+    };
+    if self.width != previous.width
+      || self.height != previous.height
+      || self.stride != previous.stride
+    {
+      changed.push(full);
+      return;
+    }
+    if self.image.is_empty() {
+      return;
+    }
+
+    let width = self.width as usize;
+    let height = self.height as usize;
+    let stride = self.stride as usize;
+    let bytes_per_pixel = Color::BYTES_PER_PIXEL;
+    let tile = tile_size.max(1) as usize;
+
+    for tile_top in (0..height).step_by(tile) {
+      let tile_bottom = (tile_top + tile).min(height);
+      let mut run: Option<(usize, usize)> = None;
+      for tile_left in (0..width).step_by(tile) {
+        let tile_right = (tile_left + tile).min(width);
+        let columns = tile_left * bytes_per_pixel..tile_right * bytes_per_pixel;
+        let dirty = (tile_top..tile_bottom).any(|y| {
+          let row = y * stride;
+          self.image[row + columns.start..row + columns.end]
+            != previous.image[row + columns.start..row + columns.end]
+        });
+
+        run = match (dirty, run) {
+          (true, Some((left, _))) => Some((left, tile_right)),
+          (true, None) => Some((tile_left, tile_right)),
+          (false, Some((left, right))) => {
+            changed.push(tile_rect(left, tile_top, right, tile_bottom));
+            None
+          }
+          (false, None) => None,
+        };
+      }
+      if let Some((left, right)) = run {
+        changed.push(tile_rect(left, tile_top, right, tile_bottom));
+      }
+    }
+  }
+}
+
+fn tile_rect(left: usize, top: usize, right: usize, bottom: usize) -> Rect {
+  Rect {
+    left: left as i32,
+    top: top as i32,
+    right: right as i32,
+    bottom: bottom as i32,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{FrameMeta, Gray8},
+    std::marker::PhantomData,
+  };
+
+  fn gray(width: u32, image: &Vec<u8>) -> Screenshot<'_, Gray8> {
+    Screenshot {
+      width,
+      height: image.len() as u32 / width,
+      stride: width,
+      image,
+      meta: FrameMeta::new(1),
+      marker: PhantomData,
+    }
+  }
+
+  fn changed(previous: &Vec<u8>, current: &Vec<u8>) -> Vec<Rect> {
+    gray(8, current).diff_with_tile_size(&gray(8, previous), 2)
+  }
+
+  fn rect(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+    Rect {
+      left,
+      top,
+      right,
+      bottom,
+    }
+  }
+
+  #[test]
+  fn identical_frames_have_no_changes() {
+    let frame = vec![7; 32];
+    assert!(changed(&frame, &frame).is_empty());
+  }
+
+  #[test]
+  fn reports_dirty_tiles_and_merges_runs() {
+    let previous = vec![0; 32];
+    let mut current = previous.clone();
+    current[8 + 5] = 1;
+    assert_eq!(changed(&previous, &current), [rect(4, 0, 6, 2)]);
+
+    current[0] = 1;
+    current[2] = 1;
+    assert_eq!(changed(&previous, &current), [rect(0, 0, 6, 2)]);
+
+    current[3 * 8 + 7] = 1;
+    assert_eq!(
+      changed(&previous, &current),
+      [rect(0, 0, 6, 2), rect(6, 2, 8, 4)]
+    );
+  }
+
+  #[test]
+  fn size_changes_mark_the_whole_frame() {
+    let previous = vec![0; 32];
+    let current = vec![0; 16];
+    assert_eq!(
+      gray(4, &current).diff(&gray(8, &previous)),
+      [rect(0, 0, 4, 4)]
+    );
+  }
+}
@@ -0,0 +1,61 @@
+use {
+  crate::Result,
+  windows::Win32::{
+    Foundation::{E_ACCESSDENIED, HANDLE, HWND},
+    Graphics::Gdi::HMONITOR,
+    UI::HiDpi::{
+      GetDpiForMonitor, GetDpiForWindow, GetProcessDpiAwareness, SetProcessDpiAwareness,
+      MDT_EFFECTIVE_DPI, PROCESS_PER_MONITOR_DPI_AWARE,
+    },
+  },
+};
+
+pub(crate) const DEFAULT_DPI: u32 = 96;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DpiPolicy {
+  #[default]
+  Inherit,
+  SetPerMonitorAware,
+}
+
+impl DpiPolicy {
+  pub fn apply(self) -> Result<()> {
+    match self {
+      Self::Inherit => Ok(()),
+      Self::SetPerMonitorAware => unsafe {
+        match SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE) {
+          Err(error)
+            if error.code() == E_ACCESSDENIED
+              && GetProcessDpiAwareness(HANDLE::default()) == Ok(PROCESS_PER_MONITOR_DPI_AWARE) =>
+          {
+            Ok(())
+          }
+          result => Ok(result?),
+        }
+      },
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameScale {
+  #[default]
+  Physical,
+  Logical,
+}
+
+pub(crate) fn window_dpi(handle: HWND) -> u32 {
+  match unsafe { GetDpiForWindow(handle) } {
+    0 => DEFAULT_DPI,
+    dpi => dpi,
+  }
+}
+
+pub(crate) fn monitor_dpi(monitor: HMONITOR) -> u32 {
+  let (mut dpi_x, mut dpi_y) = (0, 0);
+  match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+    Ok(()) if dpi_x != 0 => dpi_x,
+    _ => DEFAULT_DPI,
+  }
+}
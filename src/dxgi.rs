@@ -0,0 +1,284 @@
+use {
+  crate::{
+    d3d::{texture_from, D3dReadback},
+    rect_size, window_monitor, window_rect, window_size, Capture, FrameBuffer, GpuCapture,
+    GpuFrame, Result, SharedTexture, SharedTextureKind, StreamshotError, YuvFormat, YuvFrame,
+    YuvMatrix,
+  },
+  std::time::Duration,
+  windows::{
+    core::ComInterface,
+    Win32::{
+      Foundation::HWND,
+      Graphics::{
+        Direct3D11::ID3D11Texture2D,
+        Dxgi::{
+          Common::DXGI_FORMAT_R16G16B16A16_FLOAT, CreateDXGIFactory1, IDXGIAdapter1, IDXGIFactory1,
+          IDXGIOutput, IDXGIOutput1, IDXGIOutput5, IDXGIOutputDuplication, DXGI_ERROR_ACCESS_LOST,
+          DXGI_ERROR_WAIT_TIMEOUT, DXGI_OUTDUPL_FRAME_INFO, DXGI_OUTPUT_DESC,
+        },
+        Gdi::{MonitorFromWindow, HMONITOR, MONITOR_DEFAULTTONEAREST},
+      },
+    },
+  },
+};
+
+const FIRST_FRAME_TIMEOUT_MS: u32 = 1000;
+
+pub struct DxgiDuplicationBuffer {
+  handle: HWND,
+  readback: D3dReadback,
+  output: IDXGIOutput1,
+  duplication: Option<IDXGIOutputDuplication>,
+  desc: DXGI_OUTPUT_DESC,
+  hdr: bool,
+  yuv_size: Option<(u32, u32)>,
+  has_frame: bool,
+  frame: FrameBuffer,
+}
+
+impl DxgiDuplicationBuffer {
+  pub fn new(handle: HWND) -> Result<Self> {
+    let (width, height) = window_size(handle)?;
+
+    let monitor = unsafe { MonitorFromWindow(handle, MONITOR_DEFAULTTONEAREST) };
+    let (adapter, output, desc) = find_output(monitor)?;
+    let readback = D3dReadback::new(Some(&adapter.cast()?))?;
+    let duplication = duplicate(&output, &readback, false)?;
+
+    Ok(Self {
+      handle,
+      readback,
+      output,
+      duplication: Some(duplication),
+      desc,
+      hdr: false,
+      yuv_size: None,
+      has_frame: false,
+      frame: FrameBuffer::new(width, height),
+    })
+  }
+
+  pub fn with_hdr(mut self, hdr: bool) -> Result<Self> {
+    if self.hdr != hdr {
+      self.duplication = None;
+      self.duplication = Some(duplicate(&self.output, &self.readback, hdr)?);
+      self.readback.reset();
+      self.hdr = hdr;
+      self.has_frame = false;
+    }
+    Ok(self)
+  }
+
+  pub fn is_hdr(&self) -> bool {
+    self.hdr
+  }
+
+  pub fn with_shared_texture(mut self, kind: SharedTextureKind) -> Self {
+    self.readback.set_shared(Some(kind));
+    self
+  }
+
+  pub fn shared_texture(&self) -> Option<SharedTexture> {
+    self.readback.shared_texture()
+  }
+
+  pub fn with_yuv_size(mut self, width: u32, height: u32) -> Self {
+    self.yuv_size = Some((width, height));
+    self
+  }
+
+  pub fn yuv_size(&self) -> Option<(u32, u32)> {
+    self.yuv_size
+  }
+
+  fn read_yuv(&mut self, format: YuvFormat, matrix: YuvMatrix) -> Result<YuvFrame> {
+    if self.hdr {
+      return YuvFrame::from_bgra(self, format, matrix);
+    }
+    self.read_texture()?;
+    self.readback.convert_yuv(self.yuv_size, format, matrix)
+  }
+
+  fn duplication(&mut self) -> Result<&IDXGIOutputDuplication> {
+    let duplication = match self.duplication.take() {
+      Some(duplication) => duplication,
+      None => duplicate(&self.output, &self.readback, self.hdr)?,
+    };
+    Ok(self.duplication.insert(duplication))
+  }
+
+  fn release_frame(&self) -> Result<()> {
+    if let Some(duplication) = &self.duplication {
+      unsafe {
+        duplication.ReleaseFrame()?;
+      }
+    }
+    Ok(())
+  }
+
+  fn acquire(&mut self) -> Result<Option<ID3D11Texture2D>> {
+    let timeout = if self.has_frame {
+      0
+    } else {
+      FIRST_FRAME_TIMEOUT_MS
+    };
+
+    let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
+    let mut resource = None;
+    let duplication = self.duplication()?;
+    let acquired = unsafe { duplication.AcquireNextFrame(timeout, &mut frame_info, &mut resource) };
+    match acquired {
+      Ok(()) => {}
+      Err(error) if error.code() == DXGI_ERROR_WAIT_TIMEOUT => return Ok(None),
+      Err(error) if error.code() == DXGI_ERROR_ACCESS_LOST => {
+        self.duplication = None;
+        self.readback.reset();
+        self.duplication = Some(duplicate(&self.output, &self.readback, self.hdr)?);
+        return Ok(None);
+      }
+      Err(error) => return Err(error.into()),
+    }
+    match texture_from(resource) {
+      Ok(texture) => Ok(Some(texture)),
+      Err(error) => {
+        self.release_frame()?;
+        Err(error)
+      }
+    }
+  }
+}
+
+impl Capture for DxgiDuplicationBuffer {
+  fn read(&mut self) -> Result<()> {
+    let rect = window_rect(self.handle)?;
+    let (width, height) = rect_size(&rect)?;
+    let texture = match (self.acquire()?, self.has_frame) {
+      (Some(texture), _) => texture,
+      (None, true) => {
+        self.frame.mark_stale();
+        return Ok(());
+      }
+      (None, false) => {
+        return Err(StreamshotError::Timeout(Duration::from_millis(
+          FIRST_FRAME_TIMEOUT_MS as u64,
+        )))
+      }
+    };
+
+    self.frame.resize(width, height);
+    let desktop = self.desc.DesktopCoordinates;
+    let (left, top) = (rect.left - desktop.left, rect.top - desktop.top);
+    let result = self
+      .readback
+      .export_shared(&texture, left, top, width, height)
+      .and_then(|()| {
+        self
+          .readback
+          .copy_to_frame(&texture, left, top, &mut self.frame)
+      });
+    self.release_frame()?;
+    self.has_frame |= result.is_ok();
+    if result.is_ok() {
+      self.frame.set_monitor(window_monitor(self.handle));
+    }
+    result
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    Some(window_monitor(self.handle))
+  }
+
+  fn get_nv12_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    self.read_yuv(YuvFormat::Nv12, matrix)
+  }
+
+  fn get_i420_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    self.read_yuv(YuvFormat::I420, matrix)
+  }
+}
+
+impl GpuCapture for DxgiDuplicationBuffer {
+  fn read_texture(&mut self) -> Result<GpuFrame<'_>> {
+    let rect = window_rect(self.handle)?;
+    let (width, height) = rect_size(&rect)?;
+    if let Some(texture) = self.acquire()? {
+      let desktop = self.desc.DesktopCoordinates;
+      let (left, top) = (rect.left - desktop.left, rect.top - desktop.top);
+      let result = self
+        .readback
+        .export_shared(&texture, left, top, width, height)
+        .and_then(|()| {
+          self
+            .readback
+            .copy_to_texture(&texture, left, top, width, height)
+        });
+      self.release_frame()?;
+      result?;
+      self.has_frame = true;
+      self.frame.stamp();
+    }
+    self
+      .readback
+      .gpu_frame(self.frame.meta())
+      .ok_or(StreamshotError::Timeout(Duration::from_millis(
+        FIRST_FRAME_TIMEOUT_MS as u64,
+      )))
+  }
+}
+
+fn duplicate(
+  output: &IDXGIOutput1,
+  readback: &D3dReadback,
+  hdr: bool,
+) -> Result<IDXGIOutputDuplication> {
+  let duplication = unsafe {
+    match hdr {
+      true => output.cast::<IDXGIOutput5>()?.DuplicateOutput1(
+        readback.device(),
+        0,
+        &[DXGI_FORMAT_R16G16B16A16_FLOAT],
+      )?,
+      false => output.DuplicateOutput(readback.device())?,
+    }
+  };
+  Ok(duplication)
+}
+
+pub(crate) fn monitor_output(monitor: Option<HMONITOR>) -> Result<IDXGIOutput> {
+  if let Some(monitor) = monitor {
+    let (_, output, _) = find_output(monitor)?;
+    return Ok(output.cast()?);
+  }
+  let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1()? };
+  let output = unsafe { factory.EnumAdapters1(0)?.EnumOutputs(0)? };
+  Ok(output)
+}
+
+fn find_output(monitor: HMONITOR) -> Result<(IDXGIAdapter1, IDXGIOutput1, DXGI_OUTPUT_DESC)> {
+  let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1()? };
+
+  let mut adapter_index = 0;
+  while let Ok(adapter) = unsafe { factory.EnumAdapters1(adapter_index) } {
+    let mut output_index = 0;
+    while let Ok(output) = unsafe { adapter.EnumOutputs(output_index) } {
+      let mut desc = DXGI_OUTPUT_DESC::default();
+      unsafe {
+        output.GetDesc(&mut desc)?;
+      }
+      if desc.Monitor == monitor {
+        return Ok((adapter, output.cast()?, desc));
+      }
+      output_index += 1;
+    }
+    adapter_index += 1;
+  }
+
+  Err(StreamshotError::Unsupported(
+    "no DXGI output found for the window's monitor",
+  ))
+}
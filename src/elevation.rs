@@ -0,0 +1,69 @@
+use {
+  crate::wrappers::HandleWrapper,
+  std::{ffi::c_void, mem::size_of, ops::Not},
+  windows::Win32::{
+    Foundation::{HANDLE, HWND},
+    Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY},
+    System::Threading::{GetCurrentProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION},
+    UI::WindowsAndMessaging::GetWindowThreadProcessId,
+  },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Elevation {
+  Standard,
+  Elevated,
+  Unknown,
+}
+
+impl Elevation {
+  pub fn current() -> Self {
+    token_elevation(unsafe { GetCurrentProcess() })
+  }
+
+  pub fn is_elevated(self) -> bool {
+    self == Self::Elevated
+  }
+}
+
+pub(crate) fn process_elevation(pid: u32) -> Elevation {
+  match HandleWrapper::open_process(PROCESS_QUERY_LIMITED_INFORMATION, pid) {
+    Ok(process) => token_elevation(process.inner()),
+    Err(_) => Elevation::Unknown,
+  }
+}
+
+pub(crate) fn requires_elevation(handle: HWND) -> bool {
+  let mut pid = 0;
+  unsafe { GetWindowThreadProcessId(handle, Some(&mut pid)) };
+  Elevation::current().is_elevated().not() && process_elevation(pid) != Elevation::Standard
+}
+
+fn token_elevation(process: HANDLE) -> Elevation {
+  let mut token = HANDLE::default();
+  if unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }
+    .as_bool()
+    .not()
+  {
+    return Elevation::Unknown;
+  }
+  let Ok(token) = HandleWrapper::from_handle(token) else {
+    return Elevation::Unknown;
+  };
+  let mut elevation = TOKEN_ELEVATION::default();
+  let mut len = 0;
+  let queried = unsafe {
+    GetTokenInformation(
+      token.inner(),
+      TokenElevation,
+      Some(&mut elevation as *mut TOKEN_ELEVATION as *mut c_void),
+      size_of::<TOKEN_ELEVATION>() as u32,
+      &mut len,
+    )
+  };
+  match (queried.as_bool(), elevation.TokenIsElevated != 0) {
+    (true, true) => Elevation::Elevated,
+    (true, false) => Elevation::Standard,
+    (false, _) => Elevation::Unknown,
+  }
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "jpeg")]
+use jpeg_encoder::SamplingFactor;
+#[cfg(feature = "png")]
+use png::{BitDepth, ColorType};
+use {
+  crate::{gdi::bitmap_info_header, PixelFormat, Result, Screenshot},
+  std::{fs, mem::size_of, path::Path, slice},
+  windows::Win32::Graphics::Gdi::{BITMAPFILEHEADER, BITMAPINFOHEADER},
+};
+
+#[cfg(any(feature = "png", feature = "jpeg", feature = "webp"))]
+use crate::StreamshotError;
+
+const BMP_SIGNATURE: u16 = u16::from_le_bytes(*b"BM");
+
+#[cfg(feature = "jpeg")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChromaSubsampling {
+  #[default]
+  Yuv420,
+  Yuv422,
+  Yuv444,
+}
+
+#[cfg(feature = "jpeg")]
+impl ChromaSubsampling {
+  fn sampling_factor(self) -> SamplingFactor {
+    match self {
+      Self::Yuv420 => SamplingFactor::R_4_2_0,
+      Self::Yuv422 => SamplingFactor::R_4_2_2,
+      Self::Yuv444 => SamplingFactor::R_4_4_4,
+    }
+  }
+}
+
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn encode_bmp(&self) -> Vec<u8> {
+    let info_header = BITMAPINFOHEADER {
+      biHeight: self.height as i32,
+      ..bitmap_info_header(self.width as i32, self.height as i32)
+    };
+    let offset = size_of::<BITMAPFILEHEADER>() + size_of::<BITMAPINFOHEADER>();
+    let pixels_len = 4 * self.total_pixels() as usize;
+    let file_header = BITMAPFILEHEADER {
+      bfType: BMP_SIGNATURE,
+      bfSize: (offset + pixels_len) as u32,
+      bfReserved1: 0,
+      bfReserved2: 0,
+      bfOffBits: offset as u32,
+    };
+
+    let mut bmp = Vec::with_capacity(offset + pixels_len);
+    bmp.extend_from_slice(as_bytes(&file_header));
+    bmp.extend_from_slice(as_bytes(&info_header));
+    for row in self.rows().rev() {
+      Color::extend_bgra(row, &mut bmp);
+    }
+    bmp
+  }
+
+  pub fn save_bmp(&self, path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, self.encode_bmp())?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "png")]
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn encode_png(&self) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(encoding_error)?;
+    writer
+      .write_image_data(&self.to_rgb())
+      .map_err(encoding_error)?;
+    writer.finish().map_err(encoding_error)?;
+    Ok(png)
+  }
+
+  pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, self.encode_png()?)?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "jpeg")]
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn encode_jpeg(&self, quality: u8) -> Result<Vec<u8>> {
+    self.encode_jpeg_with_subsampling(quality, ChromaSubsampling::default())
+  }
+
+  pub fn encode_jpeg_with_subsampling(
+    &self,
+    quality: u8,
+    subsampling: ChromaSubsampling,
+  ) -> Result<Vec<u8>> {
+    let (Ok(width), Ok(height)) = (u16::try_from(self.width), u16::try_from(self.height)) else {
+      return Err(StreamshotError::InvalidSize {
+        width: self.width as i32,
+        height: self.height as i32,
+      });
+    };
+
+    let mut jpeg = Vec::new();
+    let mut encoder = jpeg_encoder::Encoder::new(&mut jpeg, quality.clamp(1, 100));
+    encoder.set_sampling_factor(subsampling.sampling_factor());
+    encoder
+      .encode(&self.to_rgb(), width, height, jpeg_encoder::ColorType::Rgb)
+      .map_err(encoding_error)?;
+    Ok(jpeg)
+  }
+
+  pub fn save_jpeg(&self, path: impl AsRef<Path>, quality: u8) -> Result<()> {
+    fs::write(path, self.encode_jpeg(quality)?)?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "webp")]
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn encode_webp(&self, lossless: bool, quality: f32) -> Result<Vec<u8>> {
+    let rgb = self.to_rgb();
+    let webp = webp::Encoder::from_rgb(&rgb, self.width, self.height)
+      .encode_simple(lossless, quality.clamp(0.0, 100.0))
+      .map_err(|error| StreamshotError::Encoding(format!("{error:?}")))?;
+    Ok(webp.to_vec())
+  }
+
+  pub fn save_webp(&self, path: impl AsRef<Path>, lossless: bool, quality: f32) -> Result<()> {
+    fs::write(path, self.encode_webp(lossless, quality)?)?;
+    Ok(())
+  }
+}
+
+fn as_bytes<T: Copy>(header: &T) -> &[u8] {
+  unsafe { slice::from_raw_parts(header as *const T as *const u8, size_of::<T>()) }
+}
+
+#[cfg(any(feature = "png", feature = "jpeg"))]
+fn encoding_error(error: impl std::error::Error) -> StreamshotError {
+  StreamshotError::Encoding(error.to_string())
+}
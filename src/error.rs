@@ -0,0 +1,107 @@
+use {
+  std::{
+    fmt::{self, Display, Formatter},
+    io,
+    sync::Arc,
+    time::Duration,
+  },
+  windows::core::Error,
+};
+
+#[derive(Clone, Debug)]
+pub enum StreamshotError {
+  WindowNotFound,
+  MonitorNotFound,
+  WindowClosed,
+  WindowMinimized,
+  CaptureProtected,
+  ElevationRequired,
+  InvalidSize {
+    width: i32,
+    height: i32,
+  },
+  BufferTooSmall {
+    required: usize,
+    provided: usize,
+  },
+  OutOfBounds {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+  },
+  PointOutOfBounds {
+    x: i32,
+    y: i32,
+  },
+  Timeout(Duration),
+  GdiFailure(Error),
+  Windows(Error),
+  Unsupported(&'static str),
+  Encoding(String),
+  Io(Arc<io::Error>),
+}
+
+pub type Result<T> = std::result::Result<T, StreamshotError>;
+
+impl Display for StreamshotError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::WindowNotFound => write!(f, "no matching window was found"),
+      Self::MonitorNotFound => write!(f, "no matching monitor was found"),
+      Self::WindowClosed => write!(f, "the target window has been closed"),
+      Self::WindowMinimized => write!(f, "the target window is minimized"),
+      Self::CaptureProtected => write!(
+        f,
+        "the target window excludes itself from capture with SetWindowDisplayAffinity"
+      ),
+      Self::ElevationRequired => write!(
+        f,
+        "the target window belongs to an elevated process; run the capturing process as administrator"
+      ),
+      Self::InvalidSize { width, height } => write!(f, "invalid capture size {width}x{height}"),
+      Self::BufferTooSmall { required, provided } => write!(
+        f,
+        "buffer of {provided} bytes is too small for a {required} byte frame"
+      ),
+      Self::OutOfBounds {
+        x,
+        y,
+        width,
+        height,
+      } => write!(
+        f,
+        "region {width}x{height} at ({x}, {y}) lies outside the frame"
+      ),
+      Self::PointOutOfBounds { x, y } => write!(f, "point ({x}, {y}) lies outside the window"),
+      Self::Timeout(elapsed) => write!(f, "capture timed out after {} ms", elapsed.as_millis()),
+      Self::GdiFailure(error) => write!(f, "gdi call failed: {error}"),
+      Self::Windows(error) => write!(f, "windows api call failed: {error}"),
+      Self::Unsupported(reason) => write!(f, "unsupported: {reason}"),
+      Self::Encoding(reason) => write!(f, "image encoding failed: {reason}"),
+      Self::Io(error) => write!(f, "io error: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for StreamshotError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::GdiFailure(error) | Self::Windows(error) => Some(error),
+      Self::Io(error) => Some(error.as_ref()),
+      _ => None,
+    }
+  }
+}
+
+impl From<Error> for StreamshotError {
+  fn from(error: Error) -> Self {
+    Self::Windows(error)
+  }
+}
+
+impl From<io::Error> for StreamshotError {
+  fn from(error: io::Error) -> Self {
+    Self::Io(Arc::new(error))
+  }
+}
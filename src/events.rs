@@ -0,0 +1,196 @@
+use {
+  crate::{wrappers::WinEventHookWrapper, Result, StreamshotError},
+  std::{
+    cell::RefCell,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+  },
+  windows::Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::{
+      Accessibility::HWINEVENTHOOK,
+      WindowsAndMessaging::{
+        GetMessageW, GetWindowThreadProcessId, PostThreadMessageW, CHILDID_SELF,
+        EVENT_OBJECT_CREATE, EVENT_OBJECT_DESTROY, EVENT_OBJECT_LOCATIONCHANGE,
+        EVENT_OBJECT_NAMECHANGE, EVENT_SYSTEM_MINIMIZEEND, EVENT_SYSTEM_MINIMIZESTART,
+        EVENT_SYSTEM_MOVESIZEEND, EVENT_SYSTEM_MOVESIZESTART, MSG, OBJID_WINDOW, WM_QUIT,
+      },
+    },
+  },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowEventKind {
+  Created,
+  Destroyed,
+  TitleChanged,
+  MoveSizeStarted,
+  MoveSizeEnded,
+  LocationChanged,
+  Minimized,
+  Restored,
+}
+
+impl WindowEventKind {
+  fn from_event(event: u32) -> Option<Self> {
+    match event {
+      EVENT_OBJECT_CREATE => Some(Self::Created),
+      EVENT_OBJECT_DESTROY => Some(Self::Destroyed),
+      EVENT_OBJECT_NAMECHANGE => Some(Self::TitleChanged),
+      EVENT_SYSTEM_MOVESIZESTART => Some(Self::MoveSizeStarted),
+      EVENT_SYSTEM_MOVESIZEEND => Some(Self::MoveSizeEnded),
+      EVENT_OBJECT_LOCATIONCHANGE => Some(Self::LocationChanged),
+      EVENT_SYSTEM_MINIMIZESTART => Some(Self::Minimized),
+      EVENT_SYSTEM_MINIMIZEEND => Some(Self::Restored),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowEvent {
+  hwnd: HWND,
+  kind: WindowEventKind,
+}
+
+impl WindowEvent {
+  pub fn hwnd(&self) -> HWND {
+    self.hwnd
+  }
+
+  pub fn kind(&self) -> WindowEventKind {
+    self.kind
+  }
+}
+
+struct Sink {
+  sender: Sender<WindowEvent>,
+  window: Option<HWND>,
+}
+
+thread_local! {
+  static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+pub struct WindowEvents {
+  receiver: Receiver<WindowEvent>,
+  thread_id: u32,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl WindowEvents {
+  pub fn watch_all() -> Result<Self> {
+    Self::spawn(0, None)
+  }
+
+  pub fn watch_process(pid: u32) -> Result<Self> {
+    Self::spawn(pid, None)
+  }
+
+  pub fn watch_window(handle: HWND) -> Result<Self> {
+    let mut pid = 0;
+    match unsafe { GetWindowThreadProcessId(handle, Some(&mut pid)) } {
+      0 => Err(StreamshotError::WindowClosed),
+      _ => Self::spawn(pid, Some(handle)),
+    }
+  }
+
+  fn spawn(pid: u32, window: Option<HWND>) -> Result<Self> {
+    let (sender, receiver) = mpsc::channel();
+    let (ready, started) = mpsc::sync_channel(1);
+    let thread = thread::Builder::new()
+      .name("streamshot-events".to_owned())
+      .spawn(move || {
+        let hooks = WinEventHookWrapper::set(
+          EVENT_SYSTEM_MOVESIZESTART,
+          EVENT_SYSTEM_MINIMIZEEND,
+          Some(on_event),
+          pid,
+        )
+        .and_then(|system| {
+          WinEventHookWrapper::set(
+            EVENT_OBJECT_CREATE,
+            EVENT_OBJECT_LOCATIONCHANGE,
+            Some(on_event),
+            pid,
+          )
+          .map(|object| (system, object))
+        });
+        let _hooks = match hooks {
+          Ok(hooks) => hooks,
+          Err(error) => {
+            let _ = ready.send(Err(error));
+            return;
+          }
+        };
+        SINK.with(|sink| *sink.borrow_mut() = Some(Sink { sender, window }));
+        let _ = ready.send(Ok(unsafe { GetCurrentThreadId() }));
+
+        let mut message = MSG::default();
+        while unsafe { GetMessageW(&mut message, HWND(0), 0, 0) }.0 > 0 {}
+        SINK.with(|sink| sink.borrow_mut().take());
+      })?;
+    match started.recv() {
+      Ok(Ok(thread_id)) => Ok(Self {
+        receiver,
+        thread_id,
+        thread: Some(thread),
+      }),
+      Ok(Err(error)) => Err(error),
+      Err(_) => Err(StreamshotError::Unsupported("event thread exited")),
+    }
+  }
+
+  pub fn recv(&self) -> Option<WindowEvent> {
+    self.receiver.recv().ok()
+  }
+
+  pub fn try_recv(&self) -> Option<WindowEvent> {
+    self.receiver.try_recv().ok()
+  }
+
+  pub fn recv_timeout(&self, timeout: Duration) -> Option<WindowEvent> {
+    self.receiver.recv_timeout(timeout).ok()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = WindowEvent> + '_ {
+    self.receiver.iter()
+  }
+}
+
+impl Drop for WindowEvents {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+    }
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+unsafe extern "system" fn on_event(
+  _hook: HWINEVENTHOOK,
+  event: u32,
+  hwnd: HWND,
+  object: i32,
+  child: i32,
+  _thread: u32,
+  _time: u32,
+) {
+  if hwnd.0 == 0 || object != OBJID_WINDOW.0 || child != CHILDID_SELF as i32 {
+    return;
+  }
+  let Some(kind) = WindowEventKind::from_event(event) else {
+    return;
+  };
+  SINK.with(|sink| {
+    if let Some(sink) = sink.borrow().as_ref() {
+      if sink.window.is_none_or(|window| window == hwnd) {
+        let _ = sink.sender.send(WindowEvent { hwnd, kind });
+      }
+    }
+  });
+}
@@ -0,0 +1,234 @@
+use {
+  crate::{Capture, MonitorFinder, Result, StreamshotError, WindowFinder, WindowScreenshotBuffer},
+  std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    ops::Not,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+  },
+};
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WsStatus {
+  Ok = 0,
+  InvalidArgument = 1,
+  WindowNotFound = 2,
+  MonitorNotFound = 3,
+  WindowClosed = 4,
+  WindowMinimized = 5,
+  InvalidSize = 6,
+  BufferTooSmall = 7,
+  Unsupported = 8,
+  Failed = 9,
+  Panicked = 10,
+  Timeout = 11,
+  CaptureProtected = 12,
+  ElevationRequired = 13,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct WsFrame {
+  pub width: u32,
+  pub height: u32,
+  pub stride: u32,
+  pub seq: u64,
+  pub data: *mut u8,
+  pub len: usize,
+}
+
+pub struct WsCapture {
+  capture: Box<dyn Capture>,
+}
+
+impl From<&StreamshotError> for WsStatus {
+  fn from(error: &StreamshotError) -> Self {
+    match error {
+      StreamshotError::WindowNotFound => Self::WindowNotFound,
+      StreamshotError::MonitorNotFound => Self::MonitorNotFound,
+      StreamshotError::WindowClosed => Self::WindowClosed,
+      StreamshotError::WindowMinimized => Self::WindowMinimized,
+      StreamshotError::InvalidSize { .. } => Self::InvalidSize,
+      StreamshotError::BufferTooSmall { .. } => Self::BufferTooSmall,
+      StreamshotError::Unsupported(_) => Self::Unsupported,
+      StreamshotError::Timeout(_) => Self::Timeout,
+      StreamshotError::CaptureProtected => Self::CaptureProtected,
+      StreamshotError::ElevationRequired => Self::ElevationRequired,
+      _ => Self::Failed,
+    }
+  }
+}
+
+fn set_last_error(message: String) {
+  let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+  LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn guard(f: impl FnOnce() -> Result<()>) -> WsStatus {
+  LAST_ERROR.with(|last| *last.borrow_mut() = None);
+  match panic::catch_unwind(AssertUnwindSafe(f)) {
+    Ok(Ok(())) => WsStatus::Ok,
+    Ok(Err(error)) => {
+      set_last_error(error.to_string());
+      WsStatus::from(&error)
+    }
+    Err(_) => {
+      set_last_error("panicked inside win-streamshot".to_owned());
+      WsStatus::Panicked
+    }
+  }
+}
+
+fn invalid_argument(message: &str) -> WsStatus {
+  set_last_error(message.to_owned());
+  WsStatus::InvalidArgument
+}
+
+unsafe fn open(
+  out: *mut *mut WsCapture,
+  make_capture: impl FnOnce() -> Result<Box<dyn Capture>>,
+) -> WsStatus {
+  if out.is_null() {
+    return invalid_argument("the output pointer is null");
+  }
+  *out = ptr::null_mut();
+  guard(|| {
+    let capture = make_capture()?;
+    *out = Box::into_raw(Box::new(WsCapture { capture }));
+    Ok(())
+  })
+}
+
+unsafe fn string_arg<'a>(value: *const c_char) -> Option<&'a str> {
+  match value.is_null() {
+    true => None,
+    false => CStr::from_ptr(value).to_str().ok(),
+  }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_find_window(
+  title: *const c_char,
+  out: *mut *mut WsCapture,
+) -> WsStatus {
+  let Some(title) = string_arg(title) else {
+    return invalid_argument("the window title is null or not valid UTF-8");
+  };
+  open(out, || Ok(Box::new(WindowFinder::new()?.find(title)?)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_capture_window(hwnd: isize, out: *mut *mut WsCapture) -> WsStatus {
+  open(out, || {
+    Ok(Box::new(WindowScreenshotBuffer::from_raw_handle(hwnd)?))
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_capture_primary_monitor(out: *mut *mut WsCapture) -> WsStatus {
+  open(out, || Ok(Box::new(MonitorFinder::new()?.primary()?)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_capture_monitor(
+  name: *const c_char,
+  out: *mut *mut WsCapture,
+) -> WsStatus {
+  let Some(name) = string_arg(name) else {
+    return invalid_argument("the monitor name is null or not valid UTF-8");
+  };
+  open(out, || Ok(Box::new(MonitorFinder::new()?.find(name)?)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_free_capture(capture: *mut WsCapture) {
+  if capture.is_null().not() {
+    drop(Box::from_raw(capture));
+  }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_capture_frame(
+  capture: *mut WsCapture,
+  frame: *mut WsFrame,
+) -> WsStatus {
+  let (Some(capture), Some(frame)) = (capture.as_mut(), frame.as_mut()) else {
+    return invalid_argument("the capture or frame pointer is null");
+  };
+  guard(|| {
+    let screenshot = capture.capture.get_bgr_screenshot()?;
+    let data = Box::<[u8]>::from(screenshot.image.as_slice());
+    *frame = WsFrame {
+      width: screenshot.width(),
+      height: screenshot.height(),
+      stride: screenshot.stride(),
+      seq: screenshot.meta().seq(),
+      len: data.len(),
+      data: Box::into_raw(data).cast(),
+    };
+    Ok(())
+  })
+}
+
+/// Writes the pixels into `buffer`, which stays owned by the caller. The
+/// returned frame's `data` is NULL; only `len` and the geometry are filled.
+#[no_mangle]
+pub unsafe extern "C" fn ws_capture_into(
+  capture: *mut WsCapture,
+  buffer: *mut u8,
+  len: usize,
+  frame: *mut WsFrame,
+) -> WsStatus {
+  let (Some(capture), Some(frame)) = (capture.as_mut(), frame.as_mut()) else {
+    return invalid_argument("the capture or frame pointer is null");
+  };
+  if buffer.is_null() {
+    return invalid_argument("the buffer pointer is null");
+  }
+  guard(|| {
+    let info = capture
+      .capture
+      .read_into(slice::from_raw_parts_mut(buffer, len))?;
+    *frame = WsFrame {
+      width: info.width(),
+      height: info.height(),
+      stride: info.stride(),
+      seq: info.meta().seq(),
+      data: ptr::null_mut(),
+      len: info.byte_len(),
+    };
+    Ok(())
+  })
+}
+
+/// Releases the pixels of a frame filled by `ws_capture_frame`. Frames with
+/// NULL `data`, such as those from `ws_capture_into`, are left untouched.
+#[no_mangle]
+pub unsafe extern "C" fn ws_free_frame(frame: *mut WsFrame) {
+  let Some(frame) = frame.as_mut() else {
+    return;
+  };
+  if frame.data.is_null().not() {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+      frame.data, frame.len,
+    )));
+  }
+  frame.data = ptr::null_mut();
+  frame.len = 0;
+}
+
+#[no_mangle]
+pub extern "C" fn ws_last_error() -> *const c_char {
+  LAST_ERROR.with(|last| {
+    last
+      .borrow()
+      .as_ref()
+      .map_or(ptr::null(), |message| message.as_ptr())
+  })
+}
@@ -0,0 +1,600 @@
+use {
+  crate::{
+    elevation::process_elevation,
+    icon::window_icon,
+    trace::{event, span},
+    wrappers::{ComScope, HandleWrapper, PerMonitorDpiScope},
+    Elevation, Monitor, MonitorFinder, Rect, Result, ScreenshotOwned, StreamshotError,
+    WindowScreenshotBuffer, BGRA,
+  },
+  std::{ffi::c_void, mem::size_of, ops::Not},
+  windows::{
+    core::{Error, PWSTR},
+    Win32::{
+      Foundation::{BOOL, HWND, LPARAM, MAX_PATH, RECT},
+      Graphics::{
+        Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
+        Gdi::{MonitorFromWindow, HMONITOR, MONITOR_DEFAULTTONEAREST},
+      },
+      System::{
+        Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+        Threading::{
+          QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+        },
+      },
+      UI::{
+        Shell::{IVirtualDesktopManager, VirtualDesktopManager},
+        WindowsAndMessaging::{
+          EnumChildWindows, EnumWindows, GetClassNameW, GetDlgCtrlID, GetWindow,
+          GetWindowDisplayAffinity, GetWindowLongW, GetWindowRect, GetWindowTextLengthW,
+          GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindowVisible, GWL_EXSTYLE,
+          GW_OWNER, WDA_EXCLUDEFROMCAPTURE, WDA_MONITOR, WS_EX_APPWINDOW, WS_EX_NOACTIVATE,
+          WS_EX_TOOLWINDOW,
+        },
+      },
+    },
+  },
+};
+
+const MAX_CLASS_NAME_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFilter {
+  skip_tool_windows: bool,
+  skip_owned: bool,
+  skip_empty: bool,
+  current_desktop_only: bool,
+}
+
+impl WindowFilter {
+  pub fn new() -> Self {
+    Self {
+      skip_tool_windows: false,
+      skip_owned: false,
+      skip_empty: false,
+      current_desktop_only: true,
+    }
+  }
+
+  pub fn alt_tab() -> Self {
+    Self {
+      skip_tool_windows: true,
+      skip_owned: true,
+      skip_empty: true,
+      current_desktop_only: true,
+    }
+  }
+
+  pub fn with_skip_tool_windows(mut self, skip: bool) -> Self {
+    self.skip_tool_windows = skip;
+    self
+  }
+
+  pub fn skip_tool_windows(&self) -> bool {
+    self.skip_tool_windows
+  }
+
+  pub fn with_skip_owned(mut self, skip: bool) -> Self {
+    self.skip_owned = skip;
+    self
+  }
+
+  pub fn skip_owned(&self) -> bool {
+    self.skip_owned
+  }
+
+  pub fn with_skip_empty(mut self, skip: bool) -> Self {
+    self.skip_empty = skip;
+    self
+  }
+
+  pub fn skip_empty(&self) -> bool {
+    self.skip_empty
+  }
+
+  pub fn with_current_desktop_only(mut self, current_desktop_only: bool) -> Self {
+    self.current_desktop_only = current_desktop_only;
+    self
+  }
+
+  pub fn current_desktop_only(&self) -> bool {
+    self.current_desktop_only
+  }
+
+  pub fn matches(&self, window: &WindowInfo) -> bool {
+    let app_window = window.ex_style & WS_EX_APPWINDOW.0 != 0;
+    let tool_window = window.ex_style & (WS_EX_TOOLWINDOW.0 | WS_EX_NOACTIVATE.0) != 0;
+    let empty = window.rect.width() <= 0 || window.rect.height() <= 0;
+    (self.skip_tool_windows && tool_window && app_window.not()
+      || self.skip_owned && window.owner.0 != 0 && app_window.not()
+      || self.skip_empty && empty
+      || self.current_desktop_only && window.on_current_desktop.not())
+    .not()
+  }
+}
+
+impl Default for WindowFilter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub struct WindowFinder {
+  all: Vec<WindowInfo>,
+  windows: Vec<WindowInfo>,
+  filter: WindowFilter,
+}
+
+impl WindowFinder {
+  pub fn new() -> Result<Self> {
+    let mut finder = Self {
+      all: Vec::new(),
+      windows: Vec::new(),
+      filter: WindowFilter::default(),
+    };
+    finder.refresh()?;
+    Ok(finder)
+  }
+
+  pub fn with_filter(mut self, filter: WindowFilter) -> Self {
+    self.filter = filter;
+    self.apply_filter();
+    self
+  }
+
+  pub fn filter(&self) -> WindowFilter {
+    self.filter
+  }
+
+  pub fn refresh(&mut self) -> Result<()> {
+    get_windows(&mut self.all)?;
+    self.apply_filter();
+    Ok(())
+  }
+
+  fn apply_filter(&mut self) {
+    let filter = self.filter;
+    self.windows.clear();
+    self.windows.extend(
+      self
+        .all
+        .iter()
+        .filter(|window| filter.matches(window))
+        .cloned(),
+    );
+  }
+
+  pub fn windows(&self) -> impl Iterator<Item = &WindowInfo> {
+    self.windows.iter()
+  }
+
+  pub fn list(&self) -> &[WindowInfo] {
+    &self.windows
+  }
+
+  pub fn find(&self, name: &str) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| window.name.contains(name))
+  }
+
+  pub fn find_all(&self, name: &str) -> Vec<WindowInfo> {
+    self
+      .windows
+      .iter()
+      .filter(|window| window.name.contains(name))
+      .cloned()
+      .collect()
+  }
+
+  pub fn topmost_matching(&self, name: &str) -> Result<&WindowInfo> {
+    self
+      .windows
+      .iter()
+      .filter(|window| window.name.contains(name))
+      .min_by_key(|window| window.z_order)
+      .ok_or(StreamshotError::WindowNotFound)
+  }
+
+  pub fn find_exact(&self, name: &str) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| window.name == name)
+  }
+
+  pub fn find_by_pid(&self, pid: u32) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| window.pid == pid)
+  }
+
+  pub fn find_by_process_name(&self, process_name: &str) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| {
+      window
+        .process_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case(process_name))
+    })
+  }
+
+  pub fn find_by_class(&self, class: &str) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| window.class == class)
+  }
+
+  pub fn find_glob(&self, pattern: &str) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| glob_match(pattern, &window.name))
+  }
+
+  #[cfg(feature = "regex")]
+  pub fn find_matching(&self, regex: &regex::Regex) -> Result<WindowScreenshotBuffer> {
+    self.find_by(|window| regex.is_match(&window.name))
+  }
+
+  fn find_by(&self, predicate: impl FnMut(&&WindowInfo) -> bool) -> Result<WindowScreenshotBuffer> {
+    self
+      .windows
+      .iter()
+      .find(predicate)
+      .ok_or(StreamshotError::WindowNotFound)
+      .and_then(WindowInfo::capture)
+  }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowInfo {
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hwnd"))]
+  handle: HWND,
+  name: String,
+  class: String,
+  pid: u32,
+  rect: Rect,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hmonitor"))]
+  monitor: HMONITOR,
+  visible: bool,
+  minimized: bool,
+  on_current_desktop: bool,
+  ex_style: u32,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hwnd"))]
+  owner: HWND,
+  affinity: DisplayAffinity,
+  z_order: usize,
+}
+
+impl WindowInfo {
+  pub fn hwnd(&self) -> HWND {
+    self.handle
+  }
+
+  pub fn title(&self) -> &str {
+    &self.name
+  }
+
+  pub fn class(&self) -> &str {
+    &self.class
+  }
+
+  pub fn pid(&self) -> u32 {
+    self.pid
+  }
+
+  pub fn process_name(&self) -> Option<String> {
+    get_process_name(self.pid)
+  }
+
+  pub fn icon(&self) -> Result<ScreenshotOwned<BGRA>> {
+    window_icon(self.handle, get_process_path(self.pid))
+  }
+
+  pub fn elevation(&self) -> Elevation {
+    process_elevation(self.pid)
+  }
+
+  pub fn rect(&self) -> Rect {
+    self.rect
+  }
+
+  pub fn monitor(&self) -> HMONITOR {
+    self.monitor
+  }
+
+  pub fn monitors(&self) -> Vec<Monitor> {
+    let Ok(finder) = MonitorFinder::new() else {
+      return Vec::new();
+    };
+    finder
+      .monitors()
+      .iter()
+      .filter(|monitor| {
+        let bounds = monitor.bounds();
+        bounds.left < self.rect.right
+          && self.rect.left < bounds.right
+          && bounds.top < self.rect.bottom
+          && self.rect.top < bounds.bottom
+      })
+      .cloned()
+      .collect()
+  }
+
+  pub fn is_visible(&self) -> bool {
+    self.visible
+  }
+
+  pub fn is_minimized(&self) -> bool {
+    self.minimized
+  }
+
+  pub fn is_on_current_desktop(&self) -> bool {
+    self.on_current_desktop
+  }
+
+  pub fn is_tool_window(&self) -> bool {
+    self.ex_style & WS_EX_TOOLWINDOW.0 != 0
+  }
+
+  pub fn owner(&self) -> Option<HWND> {
+    match self.owner.0 {
+      0 => None,
+      _ => Some(self.owner),
+    }
+  }
+
+  pub fn display_affinity(&self) -> DisplayAffinity {
+    self.affinity
+  }
+
+  pub fn z_order(&self) -> usize {
+    self.z_order
+  }
+
+  pub fn capture(&self) -> Result<WindowScreenshotBuffer> {
+    WindowScreenshotBuffer::new(self.handle)
+  }
+}
+
+struct Enumeration<'a> {
+  windows: &'a mut Vec<WindowInfo>,
+  desktops: Option<IVirtualDesktopManager>,
+}
+
+fn get_windows(windows: &mut Vec<WindowInfo>) -> Result<()> {
+  let _span = span!("enumerate_windows");
+  let _com = ComScope::enter();
+  let _dpi = PerMonitorDpiScope::enter();
+  windows.clear();
+  let mut enumeration = Enumeration {
+    windows,
+    desktops: virtual_desktop_manager(),
+  };
+  unsafe {
+    let result = EnumWindows(
+      Some(wl_callback),
+      LPARAM(&mut enumeration as *mut Enumeration as isize),
+    );
+    if result == false {
+      return Err(Error::from_win32().into());
+    }
+  }
+  event!(
+    debug,
+    count = enumeration.windows.len(),
+    "enumerated windows"
+  );
+  Ok(())
+}
+
+fn virtual_desktop_manager() -> Option<IVirtualDesktopManager> {
+  unsafe { CoCreateInstance(&VirtualDesktopManager, None, CLSCTX_INPROC_SERVER).ok() }
+}
+
+unsafe extern "system" fn wl_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+  let enumeration = &mut *(lparam.0 as *mut Enumeration);
+
+  let visible = IsWindowVisible(hwnd).as_bool();
+  if visible.not() {
+    return BOOL::from(true);
+  }
+
+  let on_current_desktop = match is_cloaked(hwnd) {
+    false => true,
+    true => match enumeration
+      .desktops
+      .as_ref()
+      .map(|desktops| desktops.IsWindowOnCurrentVirtualDesktop(hwnd))
+    {
+      Some(Ok(current)) if current.as_bool().not() => false,
+      _ => return BOOL::from(true),
+    },
+  };
+
+  let window_text_length = GetWindowTextLengthW(hwnd);
+  if window_text_length == 0 {
+    return BOOL::from(true);
+  }
+
+  let mut name_buf: Vec<u16> = vec![0; (window_text_length + 1) as usize];
+  if GetWindowTextW(hwnd, &mut name_buf) == 0 {
+    return BOOL::from(true);
+  }
+
+  let name_buf = match name_buf.split_last() {
+    Some((_, last)) => last,
+    None => return BOOL::from(true),
+  };
+
+  let name = String::from_utf16_lossy(name_buf);
+
+  let class = get_class_name(hwnd);
+
+  let mut pid = 0;
+  GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+  let mut rect = RECT::default();
+  GetWindowRect(hwnd, &mut rect);
+
+  let windows = &mut *enumeration.windows;
+  windows.push(WindowInfo {
+    handle: hwnd,
+    name,
+    class,
+    pid,
+    rect: rect.into(),
+    monitor: MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST),
+    visible,
+    minimized: IsIconic(hwnd).as_bool(),
+    on_current_desktop,
+    ex_style: GetWindowLongW(hwnd, GWL_EXSTYLE) as u32,
+    owner: GetWindow(hwnd, GW_OWNER),
+    affinity: display_affinity(hwnd),
+    z_order: windows.len(),
+  });
+
+  BOOL::from(true)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DisplayAffinity {
+  #[default]
+  None,
+  Monitor,
+  ExcludeFromCapture,
+}
+
+impl DisplayAffinity {
+  pub fn is_protected(self) -> bool {
+    self != Self::None
+  }
+}
+
+pub(crate) fn display_affinity(hwnd: HWND) -> DisplayAffinity {
+  let mut affinity = 0;
+  match unsafe { GetWindowDisplayAffinity(hwnd, &mut affinity).as_bool() } {
+    true if affinity == WDA_EXCLUDEFROMCAPTURE.0 => DisplayAffinity::ExcludeFromCapture,
+    true if affinity == WDA_MONITOR.0 => DisplayAffinity::Monitor,
+    _ => DisplayAffinity::None,
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildWindow<'a> {
+  Class(&'a str),
+  Id(i32),
+}
+
+struct ChildSearch<'a> {
+  child: ChildWindow<'a>,
+  found: Option<HWND>,
+}
+
+pub(crate) fn find_child(parent: HWND, child: ChildWindow) -> Result<HWND> {
+  let mut search = ChildSearch { child, found: None };
+  unsafe {
+    EnumChildWindows(
+      parent,
+      Some(child_callback),
+      LPARAM(&mut search as *mut ChildSearch as isize),
+    );
+  }
+  search.found.ok_or(StreamshotError::WindowNotFound)
+}
+
+unsafe extern "system" fn child_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+  let search = &mut *(lparam.0 as *mut ChildSearch);
+
+  let matches = match search.child {
+    ChildWindow::Class(class) => get_class_name(hwnd) == class,
+    ChildWindow::Id(id) => GetDlgCtrlID(hwnd) == id,
+  };
+  if matches {
+    search.found = Some(hwnd);
+    return BOOL::from(false);
+  }
+
+  BOOL::from(true)
+}
+
+fn get_class_name(hwnd: HWND) -> String {
+  let mut class_buf = [0u16; MAX_CLASS_NAME_LEN];
+  let class_len = unsafe { GetClassNameW(hwnd, &mut class_buf) };
+  String::from_utf16_lossy(&class_buf[..class_len.max(0) as usize])
+}
+
+pub(crate) fn is_cloaked(hwnd: HWND) -> bool {
+  let mut cloaked = 0u32;
+  let result = unsafe {
+    DwmGetWindowAttribute(
+      hwnd,
+      DWMWA_CLOAKED,
+      &mut cloaked as *mut u32 as *mut c_void,
+      size_of::<u32>() as u32,
+    )
+  };
+  result.is_ok() && cloaked != 0
+}
+
+fn get_process_name(pid: u32) -> Option<String> {
+  get_process_path(pid)?
+    .rsplit('\\')
+    .next()
+    .map(str::to_owned)
+}
+
+fn get_process_path(pid: u32) -> Option<String> {
+  let process = HandleWrapper::open_process(PROCESS_QUERY_LIMITED_INFORMATION, pid).ok()?;
+
+  let mut path_buf = [0u16; MAX_PATH as usize];
+  let mut path_len = path_buf.len() as u32;
+  unsafe {
+    let result = QueryFullProcessImageNameW(
+      process.inner(),
+      PROCESS_NAME_WIN32,
+      PWSTR(path_buf.as_mut_ptr()),
+      &mut path_len,
+    );
+    if result == false {
+      return None;
+    }
+  }
+
+  Some(String::from_utf16_lossy(&path_buf[..path_len as usize]))
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+  let pattern: Vec<char> = pattern.chars().collect();
+  let text: Vec<char> = text.chars().collect();
+
+  let (mut p, mut t) = (0, 0);
+  let mut backtrack = None;
+  while t < text.len() {
+    match pattern.get(p) {
+      Some('*') => {
+        backtrack = Some((p, t));
+        p += 1;
+      }
+      Some(&c) if c == '?' || c == text[t] => {
+        p += 1;
+        t += 1;
+      }
+      _ => match backtrack {
+        Some((star, matched)) => {
+          p = star + 1;
+          t = matched + 1;
+          backtrack = Some((star, matched + 1));
+        }
+        None => return false,
+      },
+    }
+  }
+  pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn glob_matches_wildcards() {
+    assert!(glob_match("*", ""));
+    assert!(glob_match("notepad.exe", "notepad.exe"));
+    assert!(glob_match("*.exe", "game.exe"));
+    assert!(glob_match("g?me*", "game - level 1"));
+    assert!(glob_match("*a*b*", "xxaxxbxx"));
+    assert!(glob_match("*.exe", "game.exe.bak").not());
+    assert!(glob_match("g?me", "gme").not());
+    assert!(glob_match("", "game").not());
+  }
+}
@@ -0,0 +1,484 @@
+use {
+  crate::{
+    color::{f16_channel, f32_to_f16, linear_to_srgb8, srgb8_to_linear, unorm8},
+    dpi::DEFAULT_DPI,
+    overlay::Overlay,
+    parallel::map_pixels,
+    swizzle::swap_red_blue,
+    text::TextOverlay,
+    trace::span,
+    Gray8, PixelFormat, ResizeFilter, Result, Screenshot, StreamshotError, BGRA, RGB24, RGBA,
+    RGBA16F,
+  },
+  std::{
+    marker::PhantomData,
+    mem,
+    ops::Not,
+    time::{Duration, Instant, SystemTime},
+  },
+  windows::Win32::Graphics::Gdi::HMONITOR,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameMeta {
+  #[cfg_attr(feature = "serde", serde(skip, default = "Instant::now"))]
+  captured_at: Instant,
+  wall_clock: SystemTime,
+  seq: u64,
+  stale: bool,
+  alpha: bool,
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hmonitor"))]
+  monitor: HMONITOR,
+}
+
+impl FrameMeta {
+  pub(crate) fn new(seq: u64) -> Self {
+    Self {
+      captured_at: Instant::now(),
+      wall_clock: SystemTime::now(),
+      seq,
+      stale: false,
+      alpha: false,
+      monitor: HMONITOR::default(),
+    }
+  }
+
+  pub(crate) fn with_wall_clock(seq: u64, wall_clock: SystemTime) -> Self {
+    Self {
+      wall_clock,
+      ..Self::new(seq)
+    }
+  }
+
+  pub fn captured_at(&self) -> Instant {
+    self.captured_at
+  }
+
+  pub fn wall_clock(&self) -> SystemTime {
+    self.wall_clock
+  }
+
+  pub fn seq(&self) -> u64 {
+    self.seq
+  }
+
+  pub fn age(&self) -> Duration {
+    self.captured_at.elapsed()
+  }
+
+  pub fn is_stale(&self) -> bool {
+    self.stale
+  }
+
+  pub fn has_alpha(&self) -> bool {
+    self.alpha
+  }
+
+  pub fn monitor(&self) -> Option<HMONITOR> {
+    match self.monitor.is_invalid() {
+      true => None,
+      false => Some(self.monitor),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameInfo {
+  width: u32,
+  height: u32,
+  stride: u32,
+  meta: FrameMeta,
+}
+
+impl FrameInfo {
+  pub(crate) fn new(width: u32, height: u32, stride: u32, meta: FrameMeta) -> Self {
+    Self {
+      width,
+      height,
+      stride,
+      meta,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn stride(&self) -> u32 {
+    self.stride
+  }
+
+  pub fn meta(&self) -> FrameMeta {
+    self.meta
+  }
+
+  pub fn byte_len(&self) -> usize {
+    (self.stride * self.height) as usize
+  }
+
+  pub(crate) fn target<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a mut [u8]> {
+    let required = self.byte_len();
+    if buffer.len() < required {
+      return Err(StreamshotError::BufferTooSmall {
+        required,
+        provided: buffer.len(),
+      });
+    }
+    Ok(&mut buffer[..required])
+  }
+}
+
+pub struct FrameBuffer {
+  width: i32,
+  height: i32,
+  row_alignment: u32,
+  buffer: Vec<u8>,
+  converted: Vec<u8>,
+  hdr: Vec<u8>,
+  padded: Vec<u8>,
+  overlays: Vec<Overlay>,
+  text_overlays: Vec<TextOverlay>,
+  composited: bool,
+  meta: FrameMeta,
+}
+
+#[derive(Clone, Copy)]
+enum Plane {
+  Buffer,
+  Converted,
+  Hdr,
+}
+
+impl FrameBuffer {
+  pub(crate) fn new(width: i32, height: i32) -> Self {
+    Self {
+      width,
+      height,
+      row_alignment: 1,
+      buffer: vec![0; (4 * width * height) as usize],
+      converted: Vec::new(),
+      hdr: Vec::new(),
+      padded: Vec::new(),
+      overlays: Vec::new(),
+      text_overlays: Vec::new(),
+      composited: true,
+      meta: FrameMeta::new(0),
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width as u32
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height as u32
+  }
+
+  pub fn row_alignment(&self) -> u32 {
+    self.row_alignment
+  }
+
+  pub fn set_row_alignment(&mut self, alignment: u32) {
+    self.row_alignment = alignment.max(1);
+  }
+
+  pub fn stride(&self) -> u32 {
+    self.stride_for(4) as u32
+  }
+
+  pub(crate) fn is_packed(&self) -> bool {
+    self.stride_for(4) == 4 * self.width as usize
+  }
+
+  fn stride_for(&self, bytes_per_pixel: usize) -> usize {
+    let alignment = self.row_alignment as usize;
+    (self.width as usize * bytes_per_pixel).div_ceil(alignment) * alignment
+  }
+
+  pub fn add_overlay(&mut self, overlay: Overlay) {
+    self.overlays.push(overlay);
+  }
+
+  pub fn add_text_overlay(&mut self, text: TextOverlay) {
+    self.text_overlays.push(text);
+  }
+
+  pub fn clear_overlays(&mut self) {
+    self.overlays.clear();
+    self.text_overlays.clear();
+  }
+
+  pub fn overlays(&self) -> &[Overlay] {
+    &self.overlays
+  }
+
+  pub fn text_overlays(&self) -> &[TextOverlay] {
+    &self.text_overlays
+  }
+
+  pub(crate) fn has_overlays(&self) -> bool {
+    self.overlays.is_empty().not() || self.text_overlays.is_empty().not()
+  }
+
+  fn composite(&mut self) {
+    if self.composited {
+      return;
+    }
+    for overlay in &self.overlays {
+      overlay.blend(&mut self.buffer, self.width as u32, self.height as u32);
+    }
+    for text in &mut self.text_overlays {
+      text.blend(
+        &mut self.buffer,
+        self.width as u32,
+        self.height as u32,
+        self.meta,
+      );
+    }
+    self.composited = true;
+  }
+
+  pub fn is_hdr(&self) -> bool {
+    self.hdr.is_empty().not()
+  }
+
+  pub(crate) fn resize(&mut self, width: i32, height: i32) {
+    if self.width == width && self.height == height {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+    self.buffer.resize((4 * width * height) as usize, 0);
+  }
+
+  pub(crate) fn scale_for_dpi(&mut self, dpi: u32) {
+    if dpi == DEFAULT_DPI || self.buffer.is_empty() {
+      return;
+    }
+    let logical = |physical: i32| ((physical as u32 * DEFAULT_DPI + dpi / 2) / dpi).max(1);
+    let source: Screenshot<'_, BGRA> = Screenshot {
+      width: self.width as u32,
+      height: self.height as u32,
+      stride: 4 * self.width as u32,
+      image: &self.buffer,
+      meta: self.meta,
+      marker: PhantomData,
+    };
+    let Ok(mut scaled) =
+      source.resize(logical(self.width), logical(self.height), ResizeFilter::Box)
+    else {
+      return;
+    };
+    self.width = scaled.width as i32;
+    self.height = scaled.height as i32;
+    self.buffer = mem::take(&mut scaled.image);
+  }
+
+  pub fn info(&self) -> FrameInfo {
+    FrameInfo::new(
+      self.width as u32,
+      self.height as u32,
+      self.stride(),
+      self.meta,
+    )
+  }
+
+  pub fn meta(&self) -> FrameMeta {
+    self.meta
+  }
+
+  pub(crate) fn has_frame(&self) -> bool {
+    self.meta.seq > 0
+  }
+
+  pub(crate) fn mark_stale(&mut self) {
+    self.meta.stale = true;
+  }
+
+  pub(crate) fn mark_alpha(&mut self) {
+    self.meta.alpha = true;
+  }
+
+  pub(crate) fn set_monitor(&mut self, monitor: HMONITOR) {
+    self.meta.monitor = monitor;
+  }
+
+  pub(crate) fn stamp(&mut self) -> FrameMeta {
+    self.meta = FrameMeta::new(self.meta.seq + 1);
+    self.composited = false;
+    self.meta
+  }
+
+  pub(crate) fn copy_into(&mut self, buffer: &mut [u8]) -> Result<FrameInfo> {
+    self.composite();
+    let info = self.info();
+    let target = info.target(buffer)?;
+    match self.is_packed() {
+      true => target.copy_from_slice(&self.buffer),
+      false => {
+        let row_len = 4 * self.width as usize;
+        for (target, source) in target
+          .chunks_exact_mut(info.stride() as usize)
+          .zip(self.buffer.chunks_exact(row_len))
+        {
+          target[..row_len].copy_from_slice(source);
+        }
+      }
+    }
+    Ok(info)
+  }
+
+  pub(crate) fn buffer_mut(&mut self) -> &mut Vec<u8> {
+    &mut self.buffer
+  }
+
+  pub(crate) fn hdr_buffer_mut(&mut self) -> &mut Vec<u8> {
+    self.hdr.resize((8 * self.width * self.height) as usize, 0);
+    &mut self.hdr
+  }
+
+  pub(crate) fn clear_hdr(&mut self) {
+    self.hdr = Vec::new();
+  }
+
+  pub(crate) fn resolve_hdr(&mut self) {
+    map_pixels(&self.hdr, 8, &mut self.buffer, 4, |hdr, pixel| {
+      let channel = |index: usize| f16_channel(hdr, index);
+      pixel[0] = linear_to_srgb8(channel(2));
+      pixel[1] = linear_to_srgb8(channel(1));
+      pixel[2] = linear_to_srgb8(channel(0));
+      pixel[3] = unorm8(channel(3));
+    });
+  }
+
+  pub(crate) fn bgr_screenshot(&mut self) -> Screenshot<'_, BGRA> {
+    self.composite();
+    self.view(Plane::Buffer)
+  }
+
+  pub(crate) fn rgb_screenshot(&mut self) -> Screenshot<'_, RGBA> {
+    self.composite();
+    let _span = span!("convert", format = "RGBA");
+    self.converted.clear();
+    self.converted.extend_from_slice(&self.buffer);
+    swap_red_blue(&mut self.converted);
+    self.view(Plane::Converted)
+  }
+
+  pub(crate) fn gray_screenshot(&mut self) -> Screenshot<'_, Gray8> {
+    self.composite();
+    let _span = span!("convert", format = "Gray8");
+    self.converted.resize(self.buffer.len() / 4, 0);
+    map_pixels(&self.buffer, 4, &mut self.converted, 1, |pixel, gray| {
+      let luma = 29 * pixel[0] as u32 + 150 * pixel[1] as u32 + 77 * pixel[2] as u32 + 128;
+      gray[0] = (luma >> 8) as u8;
+    });
+    self.view(Plane::Converted)
+  }
+
+  pub(crate) fn rgb24_screenshot(&mut self) -> Screenshot<'_, RGB24> {
+    self.composite();
+    let _span = span!("convert", format = "RGB24");
+    self.converted.resize(self.buffer.len() / 4 * 3, 0);
+    map_pixels(&self.buffer, 4, &mut self.converted, 3, |pixel, rgb| {
+      rgb.copy_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+    });
+    self.view(Plane::Converted)
+  }
+
+  pub(crate) fn hdr_screenshot(&mut self) -> Screenshot<'_, RGBA16F> {
+    let plane = match self.is_hdr() {
+      true => Plane::Hdr,
+      false => {
+        self.composite();
+        self.converted.resize(self.buffer.len() * 2, 0);
+        map_pixels(&self.buffer, 4, &mut self.converted, 8, |pixel, hdr| {
+          let linear = |value: u8| f32_to_f16(srgb8_to_linear(value));
+          let channels = [
+            linear(pixel[2]),
+            linear(pixel[1]),
+            linear(pixel[0]),
+            f32_to_f16(1.0),
+          ];
+          for (target, channel) in hdr.chunks_exact_mut(2).zip(channels) {
+            target.copy_from_slice(&channel.to_le_bytes());
+          }
+        });
+        Plane::Converted
+      }
+    };
+    self.view(plane)
+  }
+
+  fn view<Color: PixelFormat>(&mut self, plane: Plane) -> Screenshot<'_, Color> {
+    let row_len = self.width as usize * Color::BYTES_PER_PIXEL;
+    let stride = self.stride_for(Color::BYTES_PER_PIXEL);
+    let mut image = match plane {
+      Plane::Buffer => &self.buffer,
+      Plane::Converted => &self.converted,
+      Plane::Hdr => &self.hdr,
+    };
+    if stride != row_len {
+      self.padded.clear();
+      self.padded.resize(stride * self.height as usize, 0);
+      for (target, source) in self
+        .padded
+        .chunks_exact_mut(stride)
+        .zip(image.chunks_exact(row_len))
+      {
+        target[..row_len].copy_from_slice(source);
+      }
+      image = &self.padded;
+    }
+    Screenshot {
+      width: self.width as u32,
+      height: self.height as u32,
+      stride: stride as u32,
+      image,
+      meta: self.meta,
+      marker: PhantomData,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rgb_screenshot_is_stable_across_stale_reads() {
+    let mut frame = FrameBuffer::new(2, 1);
+    frame
+      .buffer_mut()
+      .copy_from_slice(&[10, 20, 30, 255, 40, 50, 60, 128]);
+    frame.stamp();
+    let first = frame.rgb_screenshot().as_bytes().to_vec();
+    frame.mark_stale();
+    let second = frame.rgb_screenshot().as_bytes().to_vec();
+    assert_eq!(first, [30, 20, 10, 255, 60, 50, 40, 128]);
+    assert_eq!(second, first);
+    assert_eq!(
+      frame.bgr_screenshot().as_bytes(),
+      [10, 20, 30, 255, 40, 50, 60, 128]
+    );
+  }
+
+  #[test]
+  fn stamp_advances_seq_and_clears_the_stale_flag() {
+    let mut frame = FrameBuffer::new(1, 1);
+    assert_eq!(frame.meta().seq(), 0);
+    frame.stamp();
+    frame.mark_stale();
+    assert!(frame.meta().is_stale());
+    let before = frame.meta().wall_clock();
+    frame.stamp();
+    assert!(frame.meta().is_stale().not());
+    assert_eq!(frame.meta().seq(), 2);
+    assert!(frame.meta().wall_clock() >= before);
+  }
+}
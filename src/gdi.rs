@@ -0,0 +1,249 @@
+use {
+  crate::{
+    trace::span,
+    wrappers::{CreatedHdcWrapper, HbitmapWrapper, HdcWrapper, PerMonitorDpiScope},
+    FrameBuffer, Result, StreamshotError,
+  },
+  std::{mem::size_of, ops::Not, slice},
+  windows::{
+    core::Error,
+    Win32::{
+      Foundation::{ERROR_INVALID_PARAMETER, E_FAIL, HWND},
+      Graphics::Gdi::{
+        BitBlt, DeleteObject, GdiFlush, GetDIBits, GetPixel, SelectObject, SetViewportOrgEx,
+        BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT, CLR_INVALID, DIB_RGB_COLORS, HDC,
+        SRCCOPY,
+      },
+      UI::WindowsAndMessaging::{
+        DrawIconEx, GetCursorInfo, GetIconInfo, CURSORINFO, CURSOR_SHOWING, DI_NORMAL, HICON,
+        ICONINFO,
+      },
+    },
+  },
+};
+
+pub(crate) struct DibSection {
+  hdc: CreatedHdcWrapper,
+  _hbitmap: HbitmapWrapper,
+  bits: *mut u8,
+  width: i32,
+  height: i32,
+}
+
+unsafe impl Send for DibSection {}
+
+impl DibSection {
+  pub(crate) fn create(width: i32, height: i32) -> Result<Self> {
+    let hdc = CreatedHdcWrapper::create_compatible_dc(HDC::default())?;
+    let info = BITMAPINFO {
+      bmiHeader: bitmap_info_header(width, height),
+      ..Default::default()
+    };
+    let (hbitmap, bits) = HbitmapWrapper::create_dib_section(hdc.inner(), &info)?;
+    select_bitmap(&hdc, &hbitmap)?;
+    Ok(Self {
+      hdc,
+      _hbitmap: hbitmap,
+      bits,
+      width,
+      height,
+    })
+  }
+
+  pub(crate) fn reuse(slot: &mut Option<Self>, width: i32, height: i32) -> Result<&Self> {
+    if let Some(dib) = slot {
+      if (dib.width, dib.height) != (width, height) {
+        *slot = None;
+      }
+    }
+    match slot {
+      Some(dib) => Ok(dib),
+      None => Ok(slot.insert(Self::create(width, height)?)),
+    }
+  }
+
+  pub(crate) fn hdc(&self) -> &CreatedHdcWrapper {
+    &self.hdc
+  }
+
+  pub(crate) fn copy_into(&self, buffer: &mut [u8]) -> Result<()> {
+    let _span = span!("copy_dib_section", width = self.width, height = self.height);
+    let len = 4 * self.width as usize * self.height as usize;
+    if buffer.len() < len {
+      return Err(StreamshotError::BufferTooSmall {
+        required: len,
+        provided: buffer.len(),
+      });
+    }
+    unsafe {
+      GdiFlush();
+      buffer[..len].copy_from_slice(slice::from_raw_parts(self.bits, len));
+    }
+    Ok(())
+  }
+}
+
+pub(crate) fn select_bitmap(hdc: &CreatedHdcWrapper, hbitmap: &HbitmapWrapper) -> Result<()> {
+  unsafe {
+    if SelectObject(hdc.inner(), hbitmap.inner()).is_invalid() {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn set_viewport_origin(hdc: &CreatedHdcWrapper, x: i32, y: i32) -> Result<()> {
+  unsafe {
+    if SetViewportOrgEx(hdc.inner(), x, y, None) == false {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn bit_blt(
+  hdc: &CreatedHdcWrapper,
+  source: HDC,
+  x: i32,
+  y: i32,
+  width: i32,
+  height: i32,
+) -> Result<()> {
+  unsafe {
+    let copied = BitBlt(
+      hdc.inner(),
+      0,
+      0,
+      width,
+      height,
+      source,
+      x,
+      y,
+      SRCCOPY | CAPTUREBLT,
+    );
+    if copied == false {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn get_pixel(hdc: HDC, x: i32, y: i32) -> Result<[u8; 4]> {
+  let color = unsafe { GetPixel(hdc, x, y) };
+  if color.0 == CLR_INVALID {
+    return Err(StreamshotError::GdiFailure(Error::from_win32()));
+  }
+  let [red, green, blue, _] = color.0.to_le_bytes();
+  Ok([blue, green, red, 255])
+}
+
+pub(crate) fn bitmap_info_header(width: i32, height: i32) -> BITMAPINFOHEADER {
+  BITMAPINFOHEADER {
+    biSize: size_of::<BITMAPINFOHEADER>() as u32,
+    biPlanes: 1,
+    biBitCount: 32,
+    biWidth: width,
+    biHeight: -height,
+    biCompression: BI_RGB.0 as u32,
+    ..Default::default()
+  }
+}
+
+pub(crate) fn get_dib_bits(
+  hdc: &CreatedHdcWrapper,
+  hbitmap: &HbitmapWrapper,
+  frame: &mut FrameBuffer,
+) -> Result<()> {
+  let width = frame.width() as i32;
+  let height = frame.height() as i32;
+  get_dib_bits_into(hdc, hbitmap, width, height, frame.buffer_mut())?;
+  frame.stamp();
+  Ok(())
+}
+
+pub(crate) fn get_dib_bits_into(
+  hdc: &CreatedHdcWrapper,
+  hbitmap: &HbitmapWrapper,
+  width: i32,
+  height: i32,
+  buffer: &mut [u8],
+) -> Result<()> {
+  let bit_map_info = BITMAPINFO {
+    bmiHeader: bitmap_info_header(width, height),
+    ..Default::default()
+  };
+
+  unsafe {
+    let gdb = GetDIBits(
+      hdc.inner(),
+      hbitmap.inner(),
+      0,
+      height as u32,
+      Some(buffer.as_mut_ptr() as *mut core::ffi::c_void),
+      &mut bit_map_info.clone(),
+      DIB_RGB_COLORS,
+    );
+    if gdb == 0 || gdb == ERROR_INVALID_PARAMETER.0 as i32 {
+      return Err(StreamshotError::GdiFailure(Error::new(
+        E_FAIL,
+        "GetDIBits error".into(),
+      )));
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn draw_cursor(hdc: &CreatedHdcWrapper, origin_x: i32, origin_y: i32) -> Result<()> {
+  let mut cursor_info = CURSORINFO {
+    cbSize: size_of::<CURSORINFO>() as u32,
+    ..Default::default()
+  };
+  unsafe {
+    if GetCursorInfo(&mut cursor_info).as_bool().not() {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+  }
+  if cursor_info.flags != CURSOR_SHOWING {
+    return Ok(());
+  }
+
+  let icon = HICON(cursor_info.hCursor.0);
+  let mut icon_info = ICONINFO::default();
+  unsafe {
+    if GetIconInfo(icon, &mut icon_info).as_bool().not() {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+    if icon_info.hbmMask.is_invalid().not() {
+      DeleteObject(icon_info.hbmMask);
+    }
+    if icon_info.hbmColor.is_invalid().not() {
+      DeleteObject(icon_info.hbmColor);
+    }
+  }
+
+  let x = cursor_info.ptScreenPos.x - origin_x - icon_info.xHotspot as i32;
+  let y = cursor_info.ptScreenPos.y - origin_y - icon_info.yHotspot as i32;
+  unsafe {
+    if DrawIconEx(hdc.inner(), x, y, icon, 0, 0, 0, None, DI_NORMAL) == false {
+      return Err(StreamshotError::GdiFailure(Error::from_win32()));
+    }
+  }
+  Ok(())
+}
+
+pub(crate) fn read_screen(x: i32, y: i32, cursor: bool, frame: &mut FrameBuffer) -> Result<()> {
+  let width = frame.width() as i32;
+  let height = frame.height() as i32;
+  let _dpi = PerMonitorDpiScope::enter();
+  let hdc_screen = HdcWrapper::get_dc(HWND::default())?;
+
+  let hdc = CreatedHdcWrapper::create_compatible_dc(hdc_screen.inner())?;
+  let hbitmap = HbitmapWrapper::create_compatible_bitmap(hdc_screen.inner(), width, height)?;
+  select_bitmap(&hdc, &hbitmap)?;
+
+  bit_blt(&hdc, hdc_screen.inner(), x, y, width, height)?;
+  if cursor {
+    draw_cursor(&hdc, x, y)?;
+  }
+  get_dib_bits(&hdc, &hbitmap, frame)
+}
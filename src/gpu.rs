@@ -0,0 +1,68 @@
+use {
+  crate::{FrameMeta, Result},
+  windows::Win32::Graphics::Direct3D11::{ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D},
+};
+
+pub trait GpuCapture {
+  fn read_texture(&mut self) -> Result<GpuFrame<'_>>;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GpuFrame<'a> {
+  texture: &'a ID3D11Texture2D,
+  device: &'a ID3D11Device,
+  context: &'a ID3D11DeviceContext,
+  width: u32,
+  height: u32,
+  hdr: bool,
+  meta: FrameMeta,
+}
+
+impl<'a> GpuFrame<'a> {
+  pub(crate) fn new(
+    texture: &'a ID3D11Texture2D,
+    device: &'a ID3D11Device,
+    context: &'a ID3D11DeviceContext,
+    (width, height): (u32, u32),
+    hdr: bool,
+    meta: FrameMeta,
+  ) -> Self {
+    Self {
+      texture,
+      device,
+      context,
+      width,
+      height,
+      hdr,
+      meta,
+    }
+  }
+
+  pub fn texture(&self) -> &'a ID3D11Texture2D {
+    self.texture
+  }
+
+  pub fn device(&self) -> &'a ID3D11Device {
+    self.device
+  }
+
+  pub fn context(&self) -> &'a ID3D11DeviceContext {
+    self.context
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn is_hdr(&self) -> bool {
+    self.hdr
+  }
+
+  pub fn meta(&self) -> FrameMeta {
+    self.meta
+  }
+}
@@ -0,0 +1,329 @@
+use {
+  crate::{Result, StreamshotError, YuvFormat, YuvFrame, YuvMatrix},
+  std::{mem::size_of, ops::Not, slice},
+  windows::{
+    core::{Error, PCSTR},
+    Win32::{
+      Foundation::E_FAIL,
+      Graphics::{
+        Direct3D::{
+          Fxc::{D3DCompile, D3DCOMPILE_OPTIMIZATION_LEVEL3},
+          ID3DBlob, ID3DInclude,
+        },
+        Direct3D11::{
+          ID3D11Buffer, ID3D11ClassLinkage, ID3D11ComputeShader, ID3D11Device, ID3D11DeviceContext,
+          ID3D11SamplerState, ID3D11Texture2D, ID3D11UnorderedAccessView,
+          D3D11_BIND_CONSTANT_BUFFER, D3D11_BIND_FLAG, D3D11_BIND_UNORDERED_ACCESS,
+          D3D11_BUFFER_DESC, D3D11_COMPARISON_NEVER, D3D11_CPU_ACCESS_FLAG, D3D11_CPU_ACCESS_READ,
+          D3D11_FILTER_MIN_MAG_MIP_LINEAR, D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ,
+          D3D11_RESOURCE_MISC_FLAG, D3D11_SAMPLER_DESC, D3D11_TEXTURE2D_DESC,
+          D3D11_TEXTURE_ADDRESS_CLAMP, D3D11_USAGE_DEFAULT, D3D11_USAGE_STAGING,
+        },
+        Dxgi::Common::{
+          DXGI_FORMAT, DXGI_FORMAT_R8G8_UNORM, DXGI_FORMAT_R8_UNORM, DXGI_SAMPLE_DESC,
+        },
+      },
+    },
+  },
+};
+
+const THREAD_GROUP_SIZE: u32 = 8;
+
+const SHADER: &str = r#"
+Texture2D<float4> source : register(t0);
+SamplerState bilinear : register(s0);
+RWTexture2D<float> luma : register(u0);
+RWTexture2D<float2> chroma : register(u1);
+
+cbuffer Params : register(b0) {
+  float4 y_row;
+  float4 u_row;
+  float4 v_row;
+  uint2 size;
+};
+
+[numthreads(8, 8, 1)]
+void main(uint3 id : SV_DispatchThreadID) {
+  uint2 block = id.xy * 2;
+  if (block.x >= size.x || block.y >= size.y) {
+    return;
+  }
+  float3 sum = 0;
+  [unroll] for (uint i = 0; i < 4; i++) {
+    uint2 pixel = min(block + uint2(i & 1, i >> 1), size - 1);
+    float3 rgb = source.SampleLevel(bilinear, (pixel + 0.5) / size, 0).rgb;
+    luma[pixel] = dot(rgb, y_row.xyz) + y_row.w;
+    sum += rgb;
+  }
+  float3 rgb = sum / 4;
+  chroma[id.xy] = float2(dot(rgb, u_row.xyz) + u_row.w, dot(rgb, v_row.xyz) + v_row.w);
+}
+"#;
+
+#[repr(C)]
+struct Params {
+  y_row: [f32; 4],
+  u_row: [f32; 4],
+  v_row: [f32; 4],
+  size: [u32; 4],
+}
+
+struct Plane {
+  texture: ID3D11Texture2D,
+  view: ID3D11UnorderedAccessView,
+  staging: ID3D11Texture2D,
+  width: u32,
+  height: u32,
+  bytes_per_pixel: usize,
+}
+
+pub(crate) struct GpuYuvConverter {
+  shader: ID3D11ComputeShader,
+  sampler: ID3D11SamplerState,
+  params: ID3D11Buffer,
+  planes: Option<(Plane, Plane)>,
+}
+
+impl GpuYuvConverter {
+  pub(crate) fn new(device: &ID3D11Device) -> Result<Self> {
+    let bytecode = compile()?;
+    let mut shader = None;
+    let mut sampler = None;
+    let mut params = None;
+    let sampler_desc = D3D11_SAMPLER_DESC {
+      Filter: D3D11_FILTER_MIN_MAG_MIP_LINEAR,
+      AddressU: D3D11_TEXTURE_ADDRESS_CLAMP,
+      AddressV: D3D11_TEXTURE_ADDRESS_CLAMP,
+      AddressW: D3D11_TEXTURE_ADDRESS_CLAMP,
+      ComparisonFunc: D3D11_COMPARISON_NEVER,
+      MaxLOD: f32::MAX,
+      ..Default::default()
+    };
+    let params_desc = D3D11_BUFFER_DESC {
+      ByteWidth: size_of::<Params>() as u32,
+      Usage: D3D11_USAGE_DEFAULT,
+      BindFlags: D3D11_BIND_CONSTANT_BUFFER,
+      ..Default::default()
+    };
+    unsafe {
+      device.CreateComputeShader(
+        blob_bytes(&bytecode),
+        None::<&ID3D11ClassLinkage>,
+        Some(&mut shader),
+      )?;
+      device.CreateSamplerState(&sampler_desc, Some(&mut sampler))?;
+      device.CreateBuffer(&params_desc, None, Some(&mut params))?;
+    }
+    match (shader, sampler, params) {
+      (Some(shader), Some(sampler), Some(params)) => Ok(Self {
+        shader,
+        sampler,
+        params,
+        planes: None,
+      }),
+      _ => Err(Error::new(E_FAIL, "compute shader setup error".into()).into()),
+    }
+  }
+
+  pub(crate) fn convert(
+    &mut self,
+    device: &ID3D11Device,
+    context: &ID3D11DeviceContext,
+    source: &ID3D11Texture2D,
+    (width, height): (u32, u32),
+    format: YuvFormat,
+    matrix: YuvMatrix,
+  ) -> Result<YuvFrame> {
+    if width == 0 || height == 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: width as i32,
+        height: height as i32,
+      });
+    }
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    if self
+      .planes
+      .as_ref()
+      .is_some_and(|(luma, _)| (luma.width, luma.height) == (width, height))
+      .not()
+    {
+      self.planes = Some((
+        Plane::new(device, width, height, DXGI_FORMAT_R8_UNORM, 1)?,
+        Plane::new(
+          device,
+          chroma_width,
+          chroma_height,
+          DXGI_FORMAT_R8G8_UNORM,
+          2,
+        )?,
+      ));
+    }
+    let (luma, chroma) = match &self.planes {
+      Some(planes) => planes,
+      None => return Err(Error::new(E_FAIL, "missing YUV planes".into()).into()),
+    };
+
+    let mut view = None;
+    unsafe {
+      device.CreateShaderResourceView(source, None, Some(&mut view))?;
+    }
+    let [y_row, u_row, v_row] = matrix.coefficients();
+    let row = |row: [i32; 3], offset: f32| {
+      let [r, g, b] = row.map(|coefficient| coefficient as f32 / 256.0);
+      [r, g, b, offset / 255.0]
+    };
+    let params = Params {
+      y_row: row(y_row, 16.0),
+      u_row: row(u_row, 128.0),
+      v_row: row(v_row, 128.0),
+      size: [width, height, 0, 0],
+    };
+
+    unsafe {
+      context.UpdateSubresource(
+        &self.params,
+        0,
+        None,
+        &params as *const Params as *const _,
+        0,
+        0,
+      );
+      context.CSSetShader(&self.shader, None);
+      context.CSSetShaderResources(0, Some(&[view]));
+      context.CSSetSamplers(0, Some(&[Some(self.sampler.clone())]));
+      context.CSSetConstantBuffers(0, Some(&[Some(self.params.clone())]));
+      let views = [Some(luma.view.clone()), Some(chroma.view.clone())];
+      context.CSSetUnorderedAccessViews(0, views.len() as u32, Some(views.as_ptr()), None);
+      context.Dispatch(
+        chroma_width.div_ceil(THREAD_GROUP_SIZE),
+        chroma_height.div_ceil(THREAD_GROUP_SIZE),
+        1,
+      );
+      context.CSSetUnorderedAccessViews(0, 2, Some([None, None].as_ptr()), None);
+      context.CSSetShaderResources(0, Some(&[None]));
+      context.CSSetShader(None::<&ID3D11ComputeShader>, None);
+    }
+
+    let luma_len = (width * height) as usize;
+    let chroma_len = (chroma_width * chroma_height) as usize;
+    let mut data = vec![0; luma_len + 2 * chroma_len];
+    let (luma_data, chroma_data) = data.split_at_mut(luma_len);
+    luma.read(context, |row, y| {
+      luma_data[y * width as usize..][..row.len()].copy_from_slice(row)
+    })?;
+    let chroma_row = 2 * chroma_width as usize;
+    chroma.read(context, |row, y| match format {
+      YuvFormat::Nv12 => chroma_data[y * chroma_row..][..row.len()].copy_from_slice(row),
+      YuvFormat::I420 => {
+        let (u, v) = chroma_data.split_at_mut(chroma_len);
+        let start = y * chroma_width as usize;
+        for (x, uv) in row.chunks_exact(2).enumerate() {
+          u[start + x] = uv[0];
+          v[start + x] = uv[1];
+        }
+      }
+    })?;
+    Ok(YuvFrame::new(width, height, format, matrix, data))
+  }
+}
+
+impl Plane {
+  fn new(
+    device: &ID3D11Device,
+    width: u32,
+    height: u32,
+    format: DXGI_FORMAT,
+    bytes_per_pixel: usize,
+  ) -> Result<Self> {
+    let mut desc = D3D11_TEXTURE2D_DESC {
+      Width: width,
+      Height: height,
+      MipLevels: 1,
+      ArraySize: 1,
+      Format: format,
+      SampleDesc: DXGI_SAMPLE_DESC {
+        Count: 1,
+        Quality: 0,
+      },
+      Usage: D3D11_USAGE_DEFAULT,
+      BindFlags: D3D11_BIND_UNORDERED_ACCESS,
+      CPUAccessFlags: D3D11_CPU_ACCESS_FLAG(0),
+      MiscFlags: D3D11_RESOURCE_MISC_FLAG(0),
+    };
+    let mut texture = None;
+    let mut view = None;
+    let mut staging = None;
+    unsafe {
+      device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+    }
+    let texture = texture.ok_or_else(|| Error::new(E_FAIL, "CreateTexture2D error".into()))?;
+    desc.Usage = D3D11_USAGE_STAGING;
+    desc.BindFlags = D3D11_BIND_FLAG(0);
+    desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+    unsafe {
+      device.CreateUnorderedAccessView(&texture, None, Some(&mut view))?;
+      device.CreateTexture2D(&desc, None, Some(&mut staging))?;
+    }
+    match (view, staging) {
+      (Some(view), Some(staging)) => Ok(Self {
+        texture,
+        view,
+        staging,
+        width,
+        height,
+        bytes_per_pixel,
+      }),
+      _ => Err(Error::new(E_FAIL, "CreateTexture2D error".into()).into()),
+    }
+  }
+
+  fn read(&self, context: &ID3D11DeviceContext, mut row: impl FnMut(&[u8], usize)) -> Result<()> {
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    unsafe {
+      context.CopyResource(&self.staging, &self.texture);
+      context.Map(&self.staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+    }
+    let pitch = mapped.RowPitch as usize;
+    let row_len = self.width as usize * self.bytes_per_pixel;
+    let source =
+      unsafe { slice::from_raw_parts(mapped.pData as *const u8, pitch * self.height as usize) };
+    for y in 0..self.height as usize {
+      row(&source[y * pitch..][..row_len], y);
+    }
+    unsafe {
+      context.Unmap(&self.staging, 0);
+    }
+    Ok(())
+  }
+}
+
+fn compile() -> Result<ID3DBlob> {
+  let mut bytecode = None;
+  let mut errors = None;
+  let compiled = unsafe {
+    D3DCompile(
+      SHADER.as_ptr() as *const _,
+      SHADER.len(),
+      PCSTR::null(),
+      None,
+      None::<&ID3DInclude>,
+      PCSTR(c"main".as_ptr() as *const u8),
+      PCSTR(c"cs_5_0".as_ptr() as *const u8),
+      D3DCOMPILE_OPTIMIZATION_LEVEL3,
+      0,
+      &mut bytecode,
+      Some(&mut errors),
+    )
+  };
+  if let Err(error) = compiled {
+    let message = errors
+      .map(|errors| String::from_utf8_lossy(unsafe { blob_bytes(&errors) }).into_owned())
+      .unwrap_or_default();
+    return Err(Error::new(error.code(), message.as_str().into()).into());
+  }
+  bytecode.ok_or_else(|| Error::new(E_FAIL, "D3DCompile error".into()).into())
+}
+
+unsafe fn blob_bytes(blob: &ID3DBlob) -> &[u8] {
+  slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize())
+}
@@ -0,0 +1,174 @@
+use {
+  crate::{
+    Capture, CaptureOptionsBuilder, PixelFormat, Result, ScreenshotOwned, StreamshotError,
+    WindowScreenshotBuffer, BGRA,
+  },
+  std::{
+    ops::Not,
+    sync::{mpsc, Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+  },
+  windows::Win32::Foundation::HWND,
+};
+
+struct State<Color> {
+  latest: Option<Arc<ScreenshotOwned<Color>>>,
+  error: Option<StreamshotError>,
+  requested: u64,
+  served: u64,
+  closed: bool,
+}
+
+struct Shared<Color> {
+  state: Mutex<State<Color>>,
+  wake: Condvar,
+  served: Condvar,
+}
+
+impl<Color> Shared<Color> {
+  fn lock(&self) -> MutexGuard<'_, State<Color>> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+pub struct CaptureHandle<Color = BGRA> {
+  shared: Arc<Shared<Color>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl<Color: PixelFormat + Send + Sync + 'static> CaptureHandle<Color> {
+  pub fn spawn(handle: HWND) -> Result<Self> {
+    Self::spawn_with(handle, CaptureOptionsBuilder::new())
+  }
+
+  pub fn spawn_with(handle: HWND, options: CaptureOptionsBuilder) -> Result<Self> {
+    let shared = Arc::new(Shared {
+      state: Mutex::new(State {
+        latest: None,
+        error: None,
+        requested: 0,
+        served: 0,
+        closed: false,
+      }),
+      wake: Condvar::new(),
+      served: Condvar::new(),
+    });
+    let (ready, started) = mpsc::sync_channel(1);
+    let worker = shared.clone();
+    let thread = thread::Builder::new()
+      .name("streamshot-capture".to_owned())
+      .spawn(move || {
+        let buffer = match options.build(handle) {
+          Ok(buffer) => buffer,
+          Err(error) => {
+            let _ = ready.send(Err(error));
+            return;
+          }
+        };
+        let _ = ready.send(Ok(()));
+        run(
+          buffer,
+          &worker,
+          Duration::from_secs(1) / options.fps().max(1),
+        );
+      })?;
+    match started.recv() {
+      Ok(Ok(())) => Ok(Self {
+        shared,
+        thread: Some(thread),
+      }),
+      Ok(Err(error)) => Err(error),
+      Err(_) => Err(StreamshotError::Unsupported("capture thread exited")),
+    }
+  }
+
+  pub fn latest_frame(&self) -> Option<Arc<ScreenshotOwned<Color>>> {
+    self.shared.lock().latest.clone()
+  }
+
+  pub fn request_frame(&self) -> Result<Arc<ScreenshotOwned<Color>>> {
+    let mut state = self.shared.lock();
+    state.requested += 1;
+    let ticket = state.requested;
+    self.shared.wake.notify_one();
+    while state.served < ticket {
+      if state.closed {
+        return Err(state.error.clone().unwrap_or(StreamshotError::WindowClosed));
+      }
+      state = self
+        .shared
+        .served
+        .wait(state)
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+    match (&state.error, &state.latest) {
+      (Some(error), _) => Err(error.clone()),
+      (None, Some(frame)) => Ok(frame.clone()),
+      (None, None) => Err(StreamshotError::WindowClosed),
+    }
+  }
+
+  pub fn last_error(&self) -> Option<StreamshotError> {
+    self.shared.lock().error.clone()
+  }
+
+  pub fn is_running(&self) -> bool {
+    self.shared.lock().closed.not()
+  }
+}
+
+impl<Color> Drop for CaptureHandle<Color> {
+  fn drop(&mut self) {
+    self.shared.lock().closed = true;
+    self.shared.wake.notify_all();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn run<Color: PixelFormat>(
+  mut buffer: WindowScreenshotBuffer,
+  shared: &Shared<Color>,
+  interval: Duration,
+) {
+  let mut next = Instant::now();
+  loop {
+    let target = {
+      let mut state = shared.lock();
+      loop {
+        if state.closed {
+          return;
+        }
+        let now = Instant::now();
+        if state.requested > state.served || now >= next {
+          break state.requested;
+        }
+        state = shared
+          .wake
+          .wait_timeout(state, next - now)
+          .unwrap_or_else(PoisonError::into_inner)
+          .0;
+      }
+    };
+    next = Instant::now() + interval;
+
+    let frame = buffer
+      .get_screenshot::<Color>()
+      .map(|screenshot| Arc::new(screenshot.to_owned()));
+    let mut state = shared.lock();
+    match frame {
+      Ok(frame) => {
+        state.latest = Some(frame);
+        state.error = None;
+      }
+      Err(error) => {
+        state.closed |= matches!(error, StreamshotError::WindowClosed);
+        state.error = Some(error);
+      }
+    }
+    state.served = target;
+    shared.served.notify_all();
+  }
+}
@@ -0,0 +1,141 @@
+use {
+  crate::{
+    gdi::get_dib_bits_into,
+    wrappers::{CreatedHdcWrapper, HbitmapWrapper},
+    FrameBuffer, Result, ScreenshotOwned, StreamshotError, BGRA,
+  },
+  std::{ffi::c_void, mem::size_of, ops::Not},
+  windows::{
+    core::{Error, HSTRING},
+    Win32::{
+      Foundation::{HWND, LPARAM, WPARAM},
+      Graphics::Gdi::{GetObjectW, BITMAP, HDC},
+      Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES,
+      UI::{
+        Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON},
+        WindowsAndMessaging::{
+          DestroyIcon, GetClassLongPtrW, GetIconInfo, SendMessageTimeoutW, GCLP_HICON,
+          GCLP_HICONSM, HICON, ICONINFO, ICON_BIG, ICON_SMALL, ICON_SMALL2, SMTO_ABORTIFHUNG,
+          WM_GETICON,
+        },
+      },
+    },
+  },
+};
+
+const GETICON_TIMEOUT_MS: u32 = 100;
+
+pub(crate) fn window_icon(
+  handle: HWND,
+  process_path: Option<String>,
+) -> Result<ScreenshotOwned<BGRA>> {
+  if let Some(icon) = borrowed_icon(handle) {
+    return render_icon(icon);
+  }
+  let path = process_path.ok_or(StreamshotError::Unsupported("the window has no icon"))?;
+  let mut info = SHFILEINFOW::default();
+  unsafe {
+    SHGetFileInfoW(
+      &HSTRING::from(path),
+      FILE_FLAGS_AND_ATTRIBUTES(0),
+      Some(&mut info),
+      size_of::<SHFILEINFOW>() as u32,
+      SHGFI_ICON | SHGFI_LARGEICON,
+    );
+  }
+  if info.hIcon.is_invalid() {
+    return Err(StreamshotError::Unsupported("the window has no icon"));
+  }
+  let icon = render_icon(info.hIcon);
+  unsafe { DestroyIcon(info.hIcon) };
+  icon
+}
+
+fn borrowed_icon(handle: HWND) -> Option<HICON> {
+  for kind in [ICON_BIG, ICON_SMALL2, ICON_SMALL] {
+    let mut icon = 0;
+    unsafe {
+      SendMessageTimeoutW(
+        handle,
+        WM_GETICON,
+        WPARAM(kind as usize),
+        LPARAM(0),
+        SMTO_ABORTIFHUNG,
+        GETICON_TIMEOUT_MS,
+        Some(&mut icon),
+      );
+    }
+    if icon != 0 {
+      return Some(HICON(icon as isize));
+    }
+  }
+  [GCLP_HICON, GCLP_HICONSM]
+    .into_iter()
+    .map(|index| unsafe { GetClassLongPtrW(handle, index) })
+    .find(|&icon| icon != 0)
+    .map(|icon| HICON(icon as isize))
+}
+
+fn render_icon(icon: HICON) -> Result<ScreenshotOwned<BGRA>> {
+  let mut info = ICONINFO::default();
+  if unsafe { GetIconInfo(icon, &mut info) }.as_bool().not() {
+    return Err(StreamshotError::GdiFailure(Error::from_win32()));
+  }
+  let mask = HbitmapWrapper::from_raw(info.hbmMask);
+  let color = match info.hbmColor.is_invalid() {
+    true => None,
+    false => Some(HbitmapWrapper::from_raw(info.hbmColor)),
+  };
+
+  let (width, mask_height) = bitmap_size(&mask)?;
+  let height = match color {
+    Some(_) => mask_height,
+    None => mask_height / 2,
+  };
+  let pixels = 4 * width as usize * height as usize;
+  let hdc = CreatedHdcWrapper::create_compatible_dc(HDC::default())?;
+  let mut mask_bits = vec![0; 4 * width as usize * mask_height as usize];
+  get_dib_bits_into(&hdc, &mask, width, mask_height, &mut mask_bits)?;
+  let mut image = match &color {
+    Some(color) => {
+      let mut bits = vec![0; pixels];
+      get_dib_bits_into(&hdc, color, width, height, &mut bits)?;
+      bits
+    }
+    None => mask_bits[pixels..2 * pixels].to_vec(),
+  };
+
+  let has_alpha = color.is_some() && image.chunks_exact(4).any(|pixel| pixel[3] != 0);
+  if has_alpha.not() {
+    for (pixel, mask) in image.chunks_exact_mut(4).zip(mask_bits.chunks_exact(4)) {
+      pixel[3] = match mask[0] {
+        0 => 255,
+        _ => 0,
+      };
+    }
+  }
+
+  let mut frame = FrameBuffer::new(width, height);
+  frame.buffer_mut().copy_from_slice(&image);
+  frame.stamp();
+  frame.mark_alpha();
+  Ok(frame.bgr_screenshot().to_owned())
+}
+
+fn bitmap_size(bitmap: &HbitmapWrapper) -> Result<(i32, i32)> {
+  let mut info = BITMAP::default();
+  let read = unsafe {
+    GetObjectW(
+      bitmap.inner(),
+      size_of::<BITMAP>() as i32,
+      Some(&mut info as *mut BITMAP as *mut c_void),
+    )
+  };
+  match read == 0 || info.bmWidth <= 0 || info.bmHeight <= 0 {
+    true => Err(StreamshotError::InvalidSize {
+      width: info.bmWidth,
+      height: info.bmHeight,
+    }),
+    false => Ok((info.bmWidth, info.bmHeight)),
+  }
+}
@@ -0,0 +1,82 @@
+use {
+  crate::{swizzle::swap_red_blue, Gray8, Result, Screenshot, StreamshotError, BGRA, RGB24, RGBA},
+  image::{GrayImage, RgbImage, RgbaImage},
+};
+
+impl Screenshot<'_, RGBA> {
+  pub fn to_image(&self) -> Result<RgbaImage> {
+    let mut pixels = self.packed().into_owned();
+    pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+    into_image(self.width, self.height, pixels)
+  }
+}
+
+impl Screenshot<'_, BGRA> {
+  pub fn to_image(&self) -> Result<RgbaImage> {
+    let mut pixels = self.packed().into_owned();
+    swap_red_blue(&mut pixels);
+    pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+    into_image(self.width, self.height, pixels)
+  }
+}
+
+impl Screenshot<'_, RGB24> {
+  pub fn to_image(&self) -> Result<RgbImage> {
+    RgbImage::from_raw(self.width, self.height, self.packed().into_owned()).ok_or(
+      StreamshotError::InvalidSize {
+        width: self.width as i32,
+        height: self.height as i32,
+      },
+    )
+  }
+}
+
+impl Screenshot<'_, Gray8> {
+  pub fn to_image(&self) -> Result<GrayImage> {
+    GrayImage::from_raw(self.width, self.height, self.packed().into_owned()).ok_or(
+      StreamshotError::InvalidSize {
+        width: self.width as i32,
+        height: self.height as i32,
+      },
+    )
+  }
+}
+
+impl TryFrom<Screenshot<'_, RGBA>> for RgbaImage {
+  type Error = StreamshotError;
+
+  fn try_from(screenshot: Screenshot<'_, RGBA>) -> Result<Self> {
+    screenshot.to_image()
+  }
+}
+
+impl TryFrom<Screenshot<'_, BGRA>> for RgbaImage {
+  type Error = StreamshotError;
+
+  fn try_from(screenshot: Screenshot<'_, BGRA>) -> Result<Self> {
+    screenshot.to_image()
+  }
+}
+
+impl TryFrom<Screenshot<'_, RGB24>> for RgbImage {
+  type Error = StreamshotError;
+
+  fn try_from(screenshot: Screenshot<'_, RGB24>) -> Result<Self> {
+    screenshot.to_image()
+  }
+}
+
+impl TryFrom<Screenshot<'_, Gray8>> for GrayImage {
+  type Error = StreamshotError;
+
+  fn try_from(screenshot: Screenshot<'_, Gray8>) -> Result<Self> {
+    screenshot.to_image()
+  }
+}
+
+fn into_image(width: u32, height: u32, pixels: Vec<u8>) -> Result<RgbaImage> {
+  RgbaImage::from_raw(width, height, pixels).ok_or(StreamshotError::InvalidSize {
+    width: width as i32,
+    height: height as i32,
+  })
+}
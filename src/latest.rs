@@ -0,0 +1,184 @@
+use {
+  crate::{Capture, FrameMeta, PixelFormat, Result, Screenshot},
+  std::{
+    marker::PhantomData,
+    mem,
+    ops::Not,
+    sync::{
+      atomic::{AtomicBool, Ordering},
+      Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+  },
+};
+
+struct Slot {
+  width: u32,
+  height: u32,
+  stride: u32,
+  image: Vec<u8>,
+  meta: FrameMeta,
+  sequence: u64,
+}
+
+impl Default for Slot {
+  fn default() -> Self {
+    Self {
+      width: 0,
+      height: 0,
+      stride: 0,
+      image: Vec::new(),
+      meta: FrameMeta::new(0),
+      sequence: 0,
+    }
+  }
+}
+
+#[derive(Default)]
+struct Shared {
+  middle: Mutex<Slot>,
+  closed: AtomicBool,
+}
+
+pub struct LatestFrameWriter<Color> {
+  shared: Arc<Shared>,
+  back: Slot,
+  sequence: u64,
+  marker: PhantomData<Color>,
+}
+
+pub struct LatestFrameReader<Color> {
+  shared: Arc<Shared>,
+  front: Slot,
+  marker: PhantomData<Color>,
+}
+
+pub fn latest_frame<Color>() -> (LatestFrameWriter<Color>, LatestFrameReader<Color>) {
+  let shared = Arc::new(Shared::default());
+  let writer = LatestFrameWriter {
+    shared: shared.clone(),
+    back: Slot::default(),
+    sequence: 0,
+    marker: PhantomData,
+  };
+  let reader = LatestFrameReader {
+    shared,
+    front: Slot::default(),
+    marker: PhantomData,
+  };
+  (writer, reader)
+}
+
+impl<Color: PixelFormat> LatestFrameWriter<Color> {
+  pub fn spawn<C>(capture: C) -> (LatestFrameReader<Color>, JoinHandle<Result<()>>)
+  where
+    C: Capture + Send + 'static,
+    Color: Send + 'static,
+  {
+    let (mut writer, reader) = latest_frame::<Color>();
+    let mut capture = capture;
+    let handle = thread::spawn(move || {
+      while writer.is_closed().not() {
+        writer.capture(&mut capture)?;
+      }
+      Ok(())
+    });
+    (reader, handle)
+  }
+
+  pub fn capture<C: Capture>(&mut self, capture: &mut C) -> Result<()> {
+    let screenshot = capture.get_screenshot::<Color>()?;
+    self.publish(&screenshot);
+    Ok(())
+  }
+
+  pub fn publish(&mut self, screenshot: &Screenshot<'_, Color>) {
+    self.sequence += 1;
+    self.back.width = screenshot.width;
+    self.back.height = screenshot.height;
+    self.back.stride = screenshot.stride;
+    self.back.meta = screenshot.meta;
+    self.back.sequence = self.sequence;
+    self.back.image.clear();
+    self.back.image.extend_from_slice(screenshot.image);
+
+    if let Ok(mut middle) = self.shared.middle.lock() {
+      mem::swap(&mut *middle, &mut self.back);
+    }
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.shared.closed.load(Ordering::Acquire)
+  }
+}
+
+impl<Color> LatestFrameReader<Color> {
+  pub fn latest(&mut self) -> Option<Screenshot<'_, Color>> {
+    if let Ok(mut middle) = self.shared.middle.lock() {
+      if middle.sequence > self.front.sequence {
+        mem::swap(&mut *middle, &mut self.front);
+      }
+    }
+    match self.front.sequence {
+      0 => None,
+      _ => Some(Screenshot {
+        width: self.front.width,
+        height: self.front.height,
+        stride: self.front.stride,
+        image: &self.front.image,
+        meta: self.front.meta,
+        marker: PhantomData,
+      }),
+    }
+  }
+
+  pub fn sequence(&self) -> u64 {
+    self.front.sequence
+  }
+}
+
+impl<Color> Drop for LatestFrameReader<Color> {
+  fn drop(&mut self) {
+    self.shared.closed.store(true, Ordering::Release);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::BGRA};
+
+  fn publish(writer: &mut LatestFrameWriter<BGRA>, pixel: [u8; 4]) {
+    writer.publish(&Screenshot {
+      width: 1,
+      height: 1,
+      stride: 4,
+      image: &pixel.to_vec(),
+      meta: FrameMeta::new(1),
+      marker: PhantomData,
+    });
+  }
+
+  #[test]
+  fn reader_sees_only_the_latest_frame() {
+    let (mut writer, mut reader) = latest_frame::<BGRA>();
+    assert!(reader.latest().is_none());
+
+    publish(&mut writer, [1; 4]);
+    publish(&mut writer, [2; 4]);
+    assert_eq!(reader.latest().unwrap().as_bytes(), [2, 2, 2, 2]);
+    assert_eq!(reader.sequence(), 2);
+    assert_eq!(reader.latest().unwrap().as_bytes(), [2, 2, 2, 2]);
+
+    publish(&mut writer, [3; 4]);
+    assert_eq!(reader.latest().unwrap().as_bytes(), [3, 3, 3, 3]);
+    assert_eq!(reader.sequence(), 3);
+  }
+
+  #[test]
+  fn dropping_the_reader_closes_the_writer() {
+    let (writer, reader) = latest_frame::<BGRA>();
+    assert!(writer.is_closed().not());
+    drop(reader);
+    assert!(writer.is_closed());
+  }
+}
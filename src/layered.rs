@@ -0,0 +1,69 @@
+use windows::Win32::{
+  Foundation::{COLORREF, HWND},
+  UI::WindowsAndMessaging::{
+    GetLayeredWindowAttributes, GetWindowLongW, GWL_EXSTYLE, LAYERED_WINDOW_ATTRIBUTES_FLAGS,
+    LWA_ALPHA, LWA_COLORKEY, WS_EX_LAYERED,
+  },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LayeredAlpha {
+  Opaque,
+  Constant { alpha: u8, key: Option<[u8; 3]> },
+  PerPixel,
+}
+
+impl LayeredAlpha {
+  pub(crate) fn query(handle: HWND) -> Self {
+    let ex_style = unsafe { GetWindowLongW(handle, GWL_EXSTYLE) } as u32;
+    if ex_style & WS_EX_LAYERED.0 == 0 {
+      return Self::Opaque;
+    }
+    let mut key = COLORREF(0);
+    let mut alpha = 255;
+    let mut flags = LAYERED_WINDOW_ATTRIBUTES_FLAGS(0);
+    let queried = unsafe {
+      GetLayeredWindowAttributes(handle, Some(&mut key), Some(&mut alpha), Some(&mut flags))
+    };
+    match queried.as_bool() {
+      true => Self::Constant {
+        alpha: match flags.0 & LWA_ALPHA.0 {
+          0 => 255,
+          _ => alpha,
+        },
+        key: match flags.0 & LWA_COLORKEY.0 {
+          0 => None,
+          _ => Some([(key.0 >> 16) as u8, (key.0 >> 8) as u8, key.0 as u8]),
+        },
+      },
+      false => Self::PerPixel,
+    }
+  }
+
+  pub(crate) fn is_transparent(self) -> bool {
+    self != Self::Opaque
+  }
+
+  pub(crate) fn apply(self, bgra: &mut [u8]) {
+    for pixel in bgra.chunks_exact_mut(4) {
+      match self {
+        Self::Opaque => pixel[3] = 255,
+        Self::Constant { alpha, key } => {
+          pixel[3] = match key.is_some_and(|key| key == [pixel[0], pixel[1], pixel[2]]) {
+            true => 0,
+            false => alpha,
+          };
+        }
+        Self::PerPixel => {
+          let alpha = pixel[3] as u32;
+          for channel in &mut pixel[..3] {
+            *channel = match alpha {
+              0 => 0,
+              alpha => (*channel as u32 * 255 / alpha).min(255) as u8,
+            };
+          }
+        }
+      }
+    }
+  }
+}
@@ -1,226 +1,1238 @@
 use {
-  crate::wrappers::{CreatedHdcWrapper, HbitmapWrapper, HdcWrapper},
+  crate::{
+    color::f16_channel,
+    dpi::window_dpi,
+    elevation::requires_elevation,
+    finder::{display_affinity, find_child},
+    gdi::{bit_blt, draw_cursor, get_pixel, select_bitmap, set_viewport_origin, DibSection},
+    layered::LayeredAlpha,
+    occlusion::window_visibility,
+    pool::PoolShared,
+    trace::{event, span},
+    wrappers::{CreatedHdcWrapper, HbitmapWrapper, HdcWrapper, PerMonitorDpiScope, RestoredWindow},
+  },
   std::{
+    borrow::Cow,
     marker::PhantomData,
-    mem::size_of,
-    ops::{Deref, Not},
+    mem::{self, size_of},
+    ops::{BitOr, BitOrAssign, Deref, Not},
+    sync::Weak,
   },
   windows::{
     core::Error,
     Win32::{
-      Foundation::{BOOL, ERROR_INVALID_PARAMETER, E_FAIL, HWND, LPARAM, RECT},
-      Graphics::Gdi::{
-        GetDIBits, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
-      },
-      Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS},
-      UI::{
-        HiDpi::{SetProcessDpiAwareness, PROCESS_PER_MONITOR_DPI_AWARE},
-        WindowsAndMessaging::{
-          EnumWindows, GetWindowRect, GetWindowTextLengthW, GetWindowTextW, IsWindowVisible,
-          PW_RENDERFULLCONTENT,
-        },
+      Foundation::{ERROR_INVALID_WINDOW_HANDLE, HWND, POINT, RECT},
+      Graphics::Gdi::{ClientToScreen, MonitorFromWindow, HMONITOR, MONITOR_DEFAULTTONEAREST},
+      Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS, PW_CLIENTONLY},
+      UI::WindowsAndMessaging::{
+        GetClientRect, GetForegroundWindow, GetWindowPlacement, GetWindowRect, IsIconic, IsWindow,
+        PW_RENDERFULLCONTENT, WINDOWPLACEMENT,
       },
     },
   },
+  xxhash_rust::xxh3::Xxh3,
 };
 
-pub struct WindowFinder {
-  windows: Vec<Window>,
-}
+mod auto;
+mod broadcast;
+mod color;
+#[cfg(feature = "png")]
+mod compare;
+mod d3d;
+mod desktop;
+mod diff;
+mod dpi;
+mod dxgi;
+mod elevation;
+mod encode;
+mod error;
+mod events;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod finder;
+mod frame;
+mod gdi;
+mod gpu;
+mod gpu_yuv;
+mod handle;
+mod icon;
+#[cfg(feature = "image")]
+mod interop;
+mod latest;
+mod layered;
+mod metrics;
+mod monitor;
+mod multi;
+mod occlusion;
+mod options;
+mod overlay;
+mod pacer;
+mod parallel;
+mod pixels;
+mod pool;
+#[cfg(feature = "raw-window-handle")]
+mod raw_handle;
+mod reacquire;
+mod recorder;
+mod rect;
+mod region;
+mod retry;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod server;
+mod session;
+mod shared_texture;
+mod sink;
+mod stats;
+#[cfg(feature = "stream")]
+mod stream;
+mod swizzle;
+#[cfg(feature = "tokio")]
+mod task;
+mod text;
+mod thumbnail;
+mod trace;
+mod transform;
+#[cfg(feature = "wgc")]
+mod wgc;
+mod wrappers;
+mod yuv;
 
-impl WindowFinder {
-  pub fn new() -> windows::core::Result<Self> {
-    Ok(Self {
-      windows: get_windows()?,
-    })
+#[cfg(feature = "png")]
+pub use crate::compare::{
+  assert_matches_golden, compare_golden, GoldenComparison, GoldenTolerance,
+};
+#[cfg(feature = "jpeg")]
+pub use crate::encode::ChromaSubsampling;
+#[cfg(feature = "gif")]
+pub use crate::recorder::GifRecorder;
+#[cfg(feature = "mp4")]
+pub use crate::recorder::Mp4Recorder;
+#[cfg(feature = "http-stream")]
+pub use crate::server::MjpegServer;
+#[cfg(feature = "rtsp")]
+pub use crate::server::RtspServer;
+#[cfg(feature = "ws")]
+pub use crate::server::{FrameEncoding, WebSocketServer};
+#[cfg(feature = "ndi")]
+pub use crate::sink::NdiSender;
+#[cfg(feature = "webcam")]
+pub use crate::sink::{VirtualWebcam, WebcamFormat};
+#[cfg(feature = "stream")]
+pub use crate::stream::FrameStream;
+#[cfg(feature = "wgc")]
+pub use crate::wgc::WgcCaptureBuffer;
+pub use crate::{
+  auto::ResolvedMethod,
+  broadcast::{Broadcast, QueuePolicy, Subscriber},
+  color::{linear_to_srgb, linear_to_srgb8, srgb8_to_linear, srgb_to_linear},
+  desktop::DesktopState,
+  dpi::{DpiPolicy, FrameScale},
+  dxgi::DxgiDuplicationBuffer,
+  elevation::Elevation,
+  error::{Result, StreamshotError},
+  events::{WindowEvent, WindowEventKind, WindowEvents},
+  finder::{ChildWindow, DisplayAffinity, WindowFilter, WindowFinder, WindowInfo},
+  frame::{FrameBuffer, FrameInfo, FrameMeta},
+  gpu::{GpuCapture, GpuFrame},
+  handle::CaptureHandle,
+  latest::{latest_frame, LatestFrameReader, LatestFrameWriter},
+  monitor::{Monitor, MonitorFinder, MonitorScreenshotBuffer, VirtualDesktopBuffer},
+  multi::{MultiCapture, WindowId},
+  occlusion::{OcclusionMode, Visibility},
+  options::CaptureOptionsBuilder,
+  overlay::Overlay,
+  pacer::Pacing,
+  pool::FramePool,
+  reacquire::{ReacquirePolicy, WindowQuery},
+  rect::Rect,
+  region::RegionScreenshotBuffer,
+  retry::RetryPolicy,
+  session::CaptureSession,
+  shared_texture::{SharedTexture, SharedTextureKind},
+  sink::{FfmpegPipe, PipeSink, SharedMemoryReader, SharedMemorySink, FRAME_HEADER_LEN},
+  stats::{CaptureStats, CaptureStatsHandle},
+  text::TextOverlay,
+  thumbnail::WindowThumbnail,
+  transform::ResizeFilter,
+  yuv::{YuvFormat, YuvFrame, YuvMatrix},
+};
+
+pub trait Capture {
+  fn read(&mut self) -> Result<()>;
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer;
+
+  fn with_row_alignment(mut self, alignment: u32) -> Self
+  where
+    Self: Sized,
+  {
+    self.frame_buffer().set_row_alignment(alignment);
+    self
   }
 
-  pub fn find(&self, name: &str) -> Option<windows::core::Result<WindowScreenshotBuffer>> {
+  fn with_overlay(mut self, overlay: Overlay) -> Self
+  where
+    Self: Sized,
+  {
+    self.frame_buffer().add_overlay(overlay);
     self
-      .windows
-      .iter()
-      .filter(|window| window.name.contains(name))
-      .next()
-      .map(|window| WindowScreenshotBuffer::new(window.handle))
   }
 
-  pub fn find_exact(&self, name: &str) -> Option<windows::core::Result<WindowScreenshotBuffer>> {
+  fn with_text_overlay(mut self, text: TextOverlay) -> Self
+  where
+    Self: Sized,
+  {
+    self.frame_buffer().add_text_overlay(text);
     self
-      .windows
-      .iter()
-      .filter(|window| window.name == name)
-      .next()
-      .map(|window| WindowScreenshotBuffer::new(window.handle))
   }
-}
 
-struct Window {
-  handle: HWND,
-  name: String,
+  fn read_into(&mut self, buffer: &mut [u8]) -> Result<FrameInfo> {
+    self.read()?;
+    self.frame_buffer().copy_into(buffer)
+  }
+
+  fn visibility(&self) -> Result<Visibility> {
+    Ok(Visibility::Visible)
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    None
+  }
+
+  fn retarget(&mut self, _handle: HWND) -> Result<()> {
+    Err(StreamshotError::Unsupported(
+      "this capture backend cannot switch to another window",
+    ))
+  }
+
+  fn get_bgr_screenshot(&mut self) -> Result<Screenshot<'_, BGRA>> {
+    self.read()?;
+    Ok(self.frame_buffer().bgr_screenshot())
+  }
+
+  fn get_rgb_screenshot(&mut self) -> Result<Screenshot<'_, RGBA>> {
+    self.read()?;
+    Ok(self.frame_buffer().rgb_screenshot())
+  }
+
+  fn get_gray_screenshot(&mut self) -> Result<Screenshot<'_, Gray8>> {
+    self.read()?;
+    Ok(self.frame_buffer().gray_screenshot())
+  }
+
+  fn get_rgb24_screenshot(&mut self) -> Result<Screenshot<'_, RGB24>> {
+    self.read()?;
+    Ok(self.frame_buffer().rgb24_screenshot())
+  }
+
+  fn get_hdr_screenshot(&mut self) -> Result<Screenshot<'_, RGBA16F>> {
+    self.read()?;
+    Ok(self.frame_buffer().hdr_screenshot())
+  }
+
+  fn get_nv12_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    YuvFrame::from_bgra(self, YuvFormat::Nv12, matrix)
+  }
+
+  fn get_i420_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    YuvFrame::from_bgra(self, YuvFormat::I420, matrix)
+  }
+
+  fn get_screenshot<Color: PixelFormat>(&mut self) -> Result<Screenshot<'_, Color>>
+  where
+    Self: Sized,
+  {
+    Color::screenshot(self)
+  }
 }
 
-fn get_windows() -> windows::core::Result<Vec<Window>> {
-  let mut windows = Vec::new();
-  unsafe {
-    let result = EnumWindows(
-      Some(wl_callback),
-      LPARAM(&mut windows as *mut Vec<Window> as isize),
+pub trait PixelFormat: Sized {
+  const BYTES_PER_PIXEL: usize;
+  const HALF_FLOAT: bool = false;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>>;
+
+  fn to_rgb(image: &[u8]) -> Vec<u8>;
+
+  fn extend_bgra(image: &[u8], bgra: &mut Vec<u8>) {
+    bgra.extend(
+      Self::to_rgb(image)
+        .chunks_exact(3)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], 255]),
     );
-    if result == false {
-      return Err(Error::from_win32());
-    }
   }
-  Ok(windows)
+
+  fn to_linear_rgb(image: &[u8]) -> Vec<f32> {
+    Self::to_rgb(image)
+      .into_iter()
+      .map(srgb8_to_linear)
+      .collect()
+  }
 }
 
-unsafe extern "system" fn wl_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
-  let windows = lparam.0 as *mut Vec<Window>;
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaptureMethod {
+  #[default]
+  PrintWindow,
+  BitBlt,
+  Auto,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PrintWindowFlags(u32);
+
+impl PrintWindowFlags {
+  pub const NONE: Self = Self(0);
+  pub const CLIENT_ONLY: Self = Self(PW_CLIENTONLY.0);
+  pub const RENDER_FULL_CONTENT: Self = Self(PW_RENDERFULLCONTENT);
 
-  if IsWindowVisible(hwnd) == false {
-    return BOOL::from(true);
+  pub fn from_bits(bits: u32) -> Self {
+    Self(bits)
   }
 
-  let window_text_length = GetWindowTextLengthW(hwnd);
-  if window_text_length == 0 {
-    return BOOL::from(true);
+  pub fn bits(self) -> u32 {
+    self.0
   }
 
-  let mut name_buf: Vec<u16> = vec![0; (window_text_length + 1) as usize];
-  if GetWindowTextW(hwnd, &mut name_buf) == 0 {
-    return BOOL::from(true);
+  pub fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
   }
+}
 
-  let name_buf = match name_buf.split_last() {
-    Some((_, last)) => last,
-    None => return BOOL::from(true),
-  };
+impl Default for PrintWindowFlags {
+  fn default() -> Self {
+    Self::RENDER_FULL_CONTENT
+  }
+}
 
-  let name = String::from_utf16_lossy(name_buf);
-  (*windows).push(Window { handle: hwnd, name });
+impl BitOr for PrintWindowFlags {
+  type Output = Self;
 
-  BOOL::from(true)
+  fn bitor(self, other: Self) -> Self {
+    Self(self.0 | other.0)
+  }
 }
 
-mod wrappers;
+impl BitOrAssign for PrintWindowFlags {
+  fn bitor_assign(&mut self, other: Self) {
+    self.0 |= other.0;
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MinimizedMode {
+  #[default]
+  LastFrame,
+  Restore,
+  Fail,
+}
 
 pub struct WindowScreenshotBuffer {
   handle: HWND,
-  width: i32,
-  height: i32,
-  buffer: Vec<u8>,
+  method: CaptureMethod,
+  print_flags: PrintWindowFlags,
+  minimized_mode: MinimizedMode,
+  cursor: bool,
+  alpha: bool,
+  layered: Option<LayeredAlpha>,
+  region: Option<Rect>,
+  scale: FrameScale,
+  retry: RetryPolicy,
+  dib: Option<DibSection>,
+  resolved: Option<ResolvedMethod>,
+  #[cfg(feature = "wgc")]
+  wgc: Option<Box<WgcCaptureBuffer>>,
+  frame: FrameBuffer,
 }
 
 impl WindowScreenshotBuffer {
-  pub fn new(handle: HWND) -> windows::core::Result<Self> {
-    unsafe {
-      let _ = SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE);
-    }
-
-    let mut rect = RECT::default();
-    unsafe {
-      if GetWindowRect(handle, &mut rect).as_bool().not() {
-        return Err(Error::from_win32());
-      };
-    }
-    let width = rect.right - rect.left;
-    let height = rect.bottom - rect.top;
+  pub fn new(handle: HWND) -> Result<Self> {
+    let _dpi = PerMonitorDpiScope::enter();
+    let (width, height) = match is_minimized(handle) {
+      true => restored_size(handle)?,
+      false => window_size(handle)?,
+    };
 
     Ok(Self {
       handle,
-      width,
-      height,
-      buffer: vec![0; (4 * width * height) as usize],
+      method: CaptureMethod::default(),
+      print_flags: PrintWindowFlags::default(),
+      minimized_mode: MinimizedMode::default(),
+      cursor: false,
+      alpha: false,
+      layered: None,
+      region: None,
+      scale: FrameScale::default(),
+      retry: RetryPolicy::default(),
+      dib: None,
+      resolved: None,
+      #[cfg(feature = "wgc")]
+      wgc: None,
+      frame: FrameBuffer::new(width, height),
     })
   }
 
-  pub fn get_bgr_screenshot(&mut self) -> windows::core::Result<Screenshot<BGRA>> {
-    self.read()?;
-    Ok(Screenshot {
-      width: self.width as u32,
-      height: self.height as u32,
-      image: &self.buffer,
-      marker: PhantomData::default(),
-    })
+  pub fn for_child(parent: HWND, child: ChildWindow) -> Result<Self> {
+    Self::new(find_child(parent, child)?)
   }
 
-  pub fn get_rgb_screenshot(&mut self) -> windows::core::Result<Screenshot<RGBA>> {
-    self.read()?;
+  pub fn from_raw_handle(handle: isize) -> Result<Self> {
+    Self::new(HWND(handle))
+  }
+
+  pub fn hwnd(&self) -> HWND {
+    self.handle
+  }
+
+  pub fn raw_handle(&self) -> isize {
+    self.handle.0
+  }
+
+  pub fn into_raw_handle(self) -> isize {
+    self.handle.0
+  }
+
+  pub fn foreground() -> Result<Self> {
+    match unsafe { GetForegroundWindow() } {
+      HWND(0) => Err(StreamshotError::WindowNotFound),
+      handle => Self::new(handle),
+    }
+  }
+
+  pub fn builder() -> CaptureOptionsBuilder {
+    CaptureOptionsBuilder::new()
+  }
+
+  pub fn with_cursor(mut self, cursor: bool) -> Self {
+    self.cursor = cursor;
     self
-      .buffer
-      .chunks_exact_mut(4)
-      .for_each(|pixel| pixel.swap(0, 2));
-    Ok(Screenshot {
-      width: self.width as u32,
-      height: self.height as u32,
-      image: &self.buffer,
-      marker: PhantomData::default(),
-    })
   }
 
-  fn read(&mut self) -> windows::core::Result<()> {
-    let hdc_screen = HdcWrapper::get_dc(self.handle)?;
+  pub fn cursor(&self) -> bool {
+    self.cursor
+  }
 
-    let hdc = CreatedHdcWrapper::create_compatible_dc(hdc_screen.inner())?;
-    let hbitmap =
-      HbitmapWrapper::create_compatible_bitmap(hdc_screen.inner(), self.width, self.height)?;
+  pub fn with_alpha(mut self, alpha: bool) -> Self {
+    self.alpha = alpha;
+    self
+  }
 
-    unsafe {
-      if SelectObject(hdc.inner(), hbitmap.inner()).is_invalid() {
-        return Err(Error::from_win32());
+  pub fn alpha(&self) -> bool {
+    self.alpha
+  }
+
+  pub fn with_capture_method(mut self, method: CaptureMethod) -> Self {
+    self.method = method;
+    self
+  }
+
+  pub fn capture_method(&self) -> CaptureMethod {
+    self.method
+  }
+
+  pub fn resolved_method(&self) -> Option<ResolvedMethod> {
+    match self.method {
+      CaptureMethod::PrintWindow => Some(ResolvedMethod::PrintWindow),
+      CaptureMethod::BitBlt => Some(ResolvedMethod::BitBlt),
+      CaptureMethod::Auto => self.resolved,
+    }
+  }
+
+  pub fn with_region(mut self, region: Rect) -> Self {
+    self.region = Some(region);
+    self
+  }
+
+  pub fn without_region(mut self) -> Self {
+    self.region = None;
+    self
+  }
+
+  pub fn region(&self) -> Option<Rect> {
+    self.region
+  }
+
+  pub fn with_print_window_flags(mut self, flags: PrintWindowFlags) -> Self {
+    self.print_flags = flags;
+    self
+  }
+
+  pub fn print_window_flags(&self) -> PrintWindowFlags {
+    self.print_flags
+  }
+
+  pub fn with_minimized_mode(mut self, mode: MinimizedMode) -> Self {
+    self.minimized_mode = mode;
+    self
+  }
+
+  pub fn minimized_mode(&self) -> MinimizedMode {
+    self.minimized_mode
+  }
+
+  pub fn dpi(&self) -> u32 {
+    window_dpi(self.handle)
+  }
+
+  pub fn monitor(&self) -> Result<Monitor> {
+    Monitor::from_handle(window_monitor(self.handle))
+  }
+
+  pub fn display_affinity(&self) -> DisplayAffinity {
+    display_affinity(self.handle)
+  }
+
+  pub fn with_frame_scale(mut self, scale: FrameScale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn frame_scale(&self) -> FrameScale {
+    self.scale
+  }
+
+  pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+    self.retry = retry;
+    self
+  }
+
+  pub fn retry_policy(&self) -> RetryPolicy {
+    self.retry
+  }
+
+  pub fn sample_pixels(&self, points: &[(i32, i32)]) -> Result<Vec<[u8; 4]>> {
+    check_window(self.handle)?;
+    check_affinity(self.handle)?;
+    let _dpi = PerMonitorDpiScope::enter();
+    if is_minimized(self.handle) {
+      return Err(StreamshotError::WindowMinimized);
+    }
+    let (width, height) = rect_size(&client_rect(self.handle)?)?;
+    if let Some(&(x, y)) = points
+      .iter()
+      .find(|&&(x, y)| x < 0 || y < 0 || x >= width || y >= height)
+    {
+      return Err(StreamshotError::PointOutOfBounds { x, y });
+    }
+    if points.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let (method, print_flags) = match self.resolved_method() {
+      Some(ResolvedMethod::BitBlt) => (CaptureMethod::BitBlt, self.print_flags),
+      Some(ResolvedMethod::PrintWindow) if self.method == CaptureMethod::PrintWindow => {
+        (CaptureMethod::PrintWindow, self.print_flags)
+      }
+      _ => (
+        CaptureMethod::PrintWindow,
+        PrintWindowFlags::RENDER_FULL_CONTENT,
+      ),
+    };
+    match method {
+      CaptureMethod::BitBlt => {
+        let hdc = HdcWrapper::get_dc(self.handle)?;
+        points
+          .iter()
+          .map(|&(x, y)| get_pixel(hdc.inner(), x, y))
+          .collect()
+      }
+      _ => {
+        let surface = capture_rect(self.handle, method, print_flags)?;
+        let origin = client_origin(self.handle)?;
+        let (x, y) = (origin.x - surface.left, origin.y - surface.top);
+        let (left, top, right, bottom) = points.iter().fold(
+          (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+          |(left, top, right, bottom), &(px, py)| {
+            (left.min(px), top.min(py), right.max(px), bottom.max(py))
+          },
+        );
+        let hdc_screen = HdcWrapper::get_dc(self.handle)?;
+        let hdc = CreatedHdcWrapper::create_compatible_dc(hdc_screen.inner())?;
+        let hbitmap = HbitmapWrapper::create_compatible_bitmap(
+          hdc_screen.inner(),
+          right - left + 1,
+          bottom - top + 1,
+        )?;
+        select_bitmap(&hdc, &hbitmap)?;
+        set_viewport_origin(&hdc, -(x + left), -(y + top))?;
+        unsafe {
+          if PrintWindow(
+            self.handle,
+            hdc.inner(),
+            PRINT_WINDOW_FLAGS(print_flags.bits()),
+          ) == false
+          {
+            return Err(StreamshotError::GdiFailure(Error::from_win32()));
+          }
+        }
+        points
+          .iter()
+          .map(|&(px, py)| get_pixel(hdc.inner(), x + px, y + py))
+          .collect()
       }
     }
+  }
 
-    let flags = PRINT_WINDOW_FLAGS(PW_RENDERFULLCONTENT);
+  fn read_window(&mut self) -> Result<()> {
+    let retry = self.retry;
+    retry.run(|| self.read_window_once())
+  }
 
-    unsafe {
-      if PrintWindow(self.handle, hdc.inner(), flags) == false {
-        return Err(Error::from_win32());
+  fn read_window_once(&mut self) -> Result<()> {
+    let _span = span!("read_window", hwnd = self.handle.0, method = ?self.method);
+    match self.method {
+      CaptureMethod::Auto => self.read_auto()?,
+      method => self.read_gdi(method, self.print_flags)?,
+    }
+    if self.scale == FrameScale::Logical {
+      self.frame.scale_for_dpi(window_dpi(self.handle));
+    }
+    self.frame.set_monitor(window_monitor(self.handle));
+    Ok(())
+  }
+
+  fn read_gdi(&mut self, method: CaptureMethod, print_flags: PrintWindowFlags) -> Result<()> {
+    let surface = capture_rect(self.handle, method, print_flags)?;
+    let area = self.capture_area(&surface)?;
+    let (width, height) = rect_size(&area)?;
+    self.frame.resize(width, height);
+    capture_window(
+      self.handle,
+      method,
+      print_flags,
+      self.cursor,
+      &surface,
+      &area,
+      &mut self.dib,
+    )?
+    .copy_into(self.frame.buffer_mut())?;
+    self.frame.stamp();
+    if let Some(layered) = self.layered {
+      let layered = match method {
+        CaptureMethod::BitBlt => LayeredAlpha::Opaque,
+        _ => layered,
+      };
+      layered.apply(self.frame.buffer_mut());
+      if layered.is_transparent() {
+        self.frame.mark_alpha();
+      }
+    }
+    Ok(())
+  }
+
+  fn read_auto(&mut self) -> Result<()> {
+    let handle = self.handle.0;
+    if let Some(method) = self.resolved.or_else(|| auto::cached_method(handle)) {
+      match self.read_resolved(method) {
+        Ok(()) => {
+          self.resolved = Some(method);
+          return Ok(());
+        }
+        Err(StreamshotError::WindowClosed) => return Err(StreamshotError::WindowClosed),
+        Err(_) => {
+          self.resolved = None;
+          auto::forget_method(handle);
+        }
+      }
+    }
+
+    let mut fallback = None;
+    let mut last_error = None;
+    for &method in auto::FALLBACK_CHAIN {
+      if method == ResolvedMethod::Wgc && self.region.is_some() {
+        continue;
+      }
+      match self.read_resolved(method) {
+        Ok(()) if auto::is_blank(self.frame.buffer_mut()).not() => {
+          self.resolved = Some(method);
+          auto::remember_method(handle, method);
+          return Ok(());
+        }
+        Ok(()) => {
+          fallback.get_or_insert(method);
+        }
+        Err(StreamshotError::WindowClosed) => return Err(StreamshotError::WindowClosed),
+        Err(error) => last_error = Some(error),
       }
     }
 
-    let bitmap_info_header = BITMAPINFOHEADER {
-      biSize: size_of::<BITMAPINFOHEADER>() as u32,
-      biPlanes: 1,
-      biBitCount: 32,
-      biWidth: self.width,
-      biHeight: -self.height,
-      biCompression: BI_RGB.0 as u32,
-      ..Default::default()
+    match (fallback, last_error) {
+      (Some(method), _) => {
+        self.read_resolved(method)?;
+        self.resolved = Some(method);
+        auto::remember_method(handle, method);
+        Ok(())
+      }
+      (None, Some(error)) => Err(error),
+      (None, None) => Err(StreamshotError::Unsupported(
+        "no capture method is available for this window",
+      )),
+    }
+  }
+
+  fn read_resolved(&mut self, method: ResolvedMethod) -> Result<()> {
+    match method {
+      ResolvedMethod::PrintWindow => self.read_gdi(
+        CaptureMethod::PrintWindow,
+        PrintWindowFlags::RENDER_FULL_CONTENT,
+      ),
+      ResolvedMethod::BitBlt => self.read_gdi(CaptureMethod::BitBlt, self.print_flags),
+      ResolvedMethod::Wgc => self.read_wgc(),
+    }
+  }
+
+  fn capture_area(&self, surface: &RECT) -> Result<RECT> {
+    let Some(region) = self.region else {
+      return Ok(*surface);
     };
-    let bit_map_info = BITMAPINFO {
-      bmiHeader: bitmap_info_header,
-      ..Default::default()
+    let origin = client_origin(self.handle)?;
+    Ok(RECT {
+      left: (origin.x + region.left).max(surface.left),
+      top: (origin.y + region.top).max(surface.top),
+      right: (origin.x + region.right).min(surface.right),
+      bottom: (origin.y + region.bottom).min(surface.bottom),
+    })
+  }
+
+  #[cfg(feature = "wgc")]
+  fn read_wgc(&mut self) -> Result<()> {
+    let wgc = match &mut self.wgc {
+      Some(wgc) => wgc,
+      wgc => wgc.insert(Box::new(
+        WgcCaptureBuffer::new(self.handle)?.with_cursor(self.cursor)?,
+      )),
     };
+    let screenshot = wgc.get_bgr_screenshot()?;
+    self
+      .frame
+      .resize(screenshot.width() as i32, screenshot.height() as i32);
+    self
+      .frame
+      .buffer_mut()
+      .copy_from_slice(&screenshot.packed());
+    match screenshot.meta().is_stale() {
+      true => self.frame.mark_stale(),
+      false => {
+        self.frame.stamp();
+      }
+    }
+    Ok(())
+  }
 
-    unsafe {
-      let gdb = GetDIBits(
-        hdc.inner(),
-        hbitmap.inner(),
-        0,
-        self.height as u32,
-        Some(self.buffer.as_mut_ptr() as *mut core::ffi::c_void),
-        &mut bit_map_info.clone(),
-        DIB_RGB_COLORS,
-      );
-      if gdb == 0 || gdb == ERROR_INVALID_PARAMETER.0 as i32 {
-        return Err(Error::new(E_FAIL, "GetDIBits error".into()));
+  #[cfg(not(feature = "wgc"))]
+  fn read_wgc(&mut self) -> Result<()> {
+    Err(StreamshotError::Unsupported(
+      "Windows.Graphics.Capture needs the wgc feature",
+    ))
+  }
+}
+
+impl Capture for WindowScreenshotBuffer {
+  fn read(&mut self) -> Result<()> {
+    check_window(self.handle)?;
+    check_affinity(self.handle)?;
+    self.layered = self.alpha.then(|| LayeredAlpha::query(self.handle));
+    let _dpi = PerMonitorDpiScope::enter();
+    if is_minimized(self.handle).not() {
+      return self.read_window();
+    }
+
+    match self.minimized_mode {
+      MinimizedMode::LastFrame => match self.frame.has_frame() {
+        true => {
+          self.frame.mark_stale();
+          Ok(())
+        }
+        false => Err(StreamshotError::WindowMinimized),
+      },
+      MinimizedMode::Restore => {
+        let _restored = RestoredWindow::restore(self.handle)?;
+        self.read_window()
       }
+      MinimizedMode::Fail => Err(StreamshotError::WindowMinimized),
+    }
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+
+  fn visibility(&self) -> Result<Visibility> {
+    window_visibility(self.handle)
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    Some(window_monitor(self.handle))
+  }
+
+  fn retarget(&mut self, handle: HWND) -> Result<()> {
+    check_window(handle)?;
+    self.handle = handle;
+    self.resolved = None;
+    #[cfg(feature = "wgc")]
+    {
+      self.wgc = None;
     }
     Ok(())
   }
+
+  fn read_into(&mut self, buffer: &mut [u8]) -> Result<FrameInfo> {
+    check_affinity(self.handle)?;
+    let _dpi = PerMonitorDpiScope::enter();
+    if self.method == CaptureMethod::Auto
+      || self.frame.is_packed().not()
+      || self.frame.has_overlays()
+      || self.alpha
+      || self.scale == FrameScale::Logical
+      || is_minimized(self.handle)
+    {
+      self.read()?;
+      return self.frame.copy_into(buffer);
+    }
+
+    let mut dib = self.dib.take();
+    let captured = self.retry.run(|| {
+      let surface = capture_rect(self.handle, self.method, self.print_flags)?;
+      let area = self.capture_area(&surface)?;
+      let (width, height) = rect_size(&area)?;
+      let target = FrameInfo::new(
+        width as u32,
+        height as u32,
+        4 * width as u32,
+        self.frame.meta(),
+      )
+      .target(buffer)?;
+      capture_window(
+        self.handle,
+        self.method,
+        self.print_flags,
+        self.cursor,
+        &surface,
+        &area,
+        &mut dib,
+      )?
+      .copy_into(target)?;
+      Ok((width, height))
+    });
+    self.dib = dib;
+    let (width, height) = captured?;
+    self.frame.stamp();
+    self.frame.set_monitor(window_monitor(self.handle));
+    let stride = 4 * width as u32;
+    Ok(FrameInfo::new(
+      width as u32,
+      height as u32,
+      stride,
+      self.frame.meta(),
+    ))
+  }
+}
+
+fn capture_window<'a>(
+  handle: HWND,
+  method: CaptureMethod,
+  print_flags: PrintWindowFlags,
+  cursor: bool,
+  surface: &RECT,
+  area: &RECT,
+  dib: &'a mut Option<DibSection>,
+) -> Result<&'a DibSection> {
+  let (surface_width, surface_height) = rect_size(surface)?;
+  let (width, height) = rect_size(area)?;
+  let (x, y) = (area.left - surface.left, area.top - surface.top);
+  let _capture = span!("capture_window", hwnd = handle.0, ?method, width, height);
+  let hdc_screen = {
+    let _stage = span!("get_dc");
+    match method {
+      CaptureMethod::PrintWindow | CaptureMethod::Auto => HdcWrapper::get_dc(handle)?,
+      CaptureMethod::BitBlt => HdcWrapper::get_window_dc(handle)?,
+    }
+  };
+
+  let dib = DibSection::reuse(dib, width, height)?;
+  let hdc = dib.hdc();
+
+  match method {
+    CaptureMethod::PrintWindow | CaptureMethod::Auto => {
+      let flags = PRINT_WINDOW_FLAGS(print_flags.bits());
+      let whole = (x, y, width, height) == (0, 0, surface_width, surface_height);
+      let full = match whole {
+        true => None,
+        false => {
+          let full_hdc = CreatedHdcWrapper::create_compatible_dc(hdc_screen.inner())?;
+          let full_bitmap = HbitmapWrapper::create_compatible_bitmap(
+            hdc_screen.inner(),
+            surface_width,
+            surface_height,
+          )?;
+          select_bitmap(&full_hdc, &full_bitmap)?;
+          Some((full_hdc, full_bitmap))
+        }
+      };
+      let target = full
+        .as_ref()
+        .map_or(hdc.inner(), |(full_hdc, _)| full_hdc.inner());
+      let _stage = span!("print_window", flags = print_flags.bits());
+      unsafe {
+        if PrintWindow(handle, target, flags) == false {
+          let error = Error::from_win32();
+          event!(warn, %error, "PrintWindow failed");
+          return Err(match requires_elevation(handle) {
+            true => StreamshotError::ElevationRequired,
+            false => StreamshotError::GdiFailure(error),
+          });
+        }
+      }
+      if full.is_some() {
+        bit_blt(hdc, target, x, y, width, height)?;
+      }
+    }
+    CaptureMethod::BitBlt => {
+      let _stage = span!("bit_blt");
+      bit_blt(hdc, hdc_screen.inner(), x, y, width, height)?
+    }
+  }
+  if cursor {
+    draw_cursor(hdc, area.left, area.top)?;
+  }
+
+  Ok(dib)
+}
+
+fn window_size(handle: HWND) -> Result<(i32, i32)> {
+  rect_size(&window_rect(handle)?)
+}
+
+fn restored_size(handle: HWND) -> Result<(i32, i32)> {
+  let mut placement = WINDOWPLACEMENT {
+    length: size_of::<WINDOWPLACEMENT>() as u32,
+    ..Default::default()
+  };
+  unsafe {
+    if GetWindowPlacement(handle, &mut placement).as_bool().not() {
+      return Err(Error::from_win32().into());
+    }
+  }
+  rect_size(&placement.rcNormalPosition)
+}
+
+fn check_window(handle: HWND) -> Result<()> {
+  match unsafe { IsWindow(handle).as_bool() } {
+    true => Ok(()),
+    false => Err(StreamshotError::WindowClosed),
+  }
+}
+
+fn check_affinity(handle: HWND) -> Result<()> {
+  match display_affinity(handle).is_protected() {
+    true => Err(StreamshotError::CaptureProtected),
+    false => Ok(()),
+  }
+}
+
+fn window_monitor(handle: HWND) -> HMONITOR {
+  unsafe { MonitorFromWindow(handle, MONITOR_DEFAULTTONEAREST) }
+}
+
+fn is_minimized(handle: HWND) -> bool {
+  unsafe { IsIconic(handle).as_bool() }
 }
 
+fn rect_size(rect: &RECT) -> Result<(i32, i32)> {
+  let width = rect.right - rect.left;
+  let height = rect.bottom - rect.top;
+  if width <= 0 || height <= 0 {
+    return Err(StreamshotError::InvalidSize { width, height });
+  }
+  Ok((width, height))
+}
+
+fn window_rect(handle: HWND) -> Result<RECT> {
+  check_window(handle)?;
+  let _dpi = PerMonitorDpiScope::enter();
+  let mut rect = RECT::default();
+  unsafe {
+    if GetWindowRect(handle, &mut rect).as_bool().not() {
+      let error = Error::from_win32();
+      if error.code() == ERROR_INVALID_WINDOW_HANDLE.to_hresult() {
+        return Err(StreamshotError::WindowClosed);
+      }
+      return Err(error.into());
+    }
+  }
+  Ok(rect)
+}
+
+fn capture_rect(
+  handle: HWND,
+  method: CaptureMethod,
+  print_flags: PrintWindowFlags,
+) -> Result<RECT> {
+  match method == CaptureMethod::PrintWindow && print_flags.contains(PrintWindowFlags::CLIENT_ONLY)
+  {
+    true => client_rect(handle),
+    false => window_rect(handle),
+  }
+}
+
+fn client_rect(handle: HWND) -> Result<RECT> {
+  let origin = client_origin(handle)?;
+  let mut rect = RECT::default();
+  unsafe {
+    if GetClientRect(handle, &mut rect).as_bool().not() {
+      return Err(Error::from_win32().into());
+    }
+  }
+  Ok(RECT {
+    left: origin.x,
+    top: origin.y,
+    right: origin.x + rect.right,
+    bottom: origin.y + rect.bottom,
+  })
+}
+
+fn client_origin(handle: HWND) -> Result<POINT> {
+  window_rect(handle)?;
+  let mut origin = POINT::default();
+  unsafe {
+    if ClientToScreen(handle, &mut origin).as_bool().not() {
+      return Err(Error::from_win32().into());
+    }
+  }
+  Ok(origin)
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct BGRA;
+#[derive(Clone, Copy, Debug)]
 pub struct RGBA;
+#[derive(Clone, Copy, Debug)]
+pub struct RGB24;
+#[derive(Clone, Copy, Debug)]
+pub struct Gray8;
+#[derive(Clone, Copy, Debug)]
+pub struct RGBA16F;
+
+impl PixelFormat for BGRA {
+  const BYTES_PER_PIXEL: usize = 4;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>> {
+    capture.get_bgr_screenshot()
+  }
+
+  fn to_rgb(image: &[u8]) -> Vec<u8> {
+    image
+      .chunks_exact(4)
+      .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+      .collect()
+  }
+
+  fn extend_bgra(image: &[u8], bgra: &mut Vec<u8>) {
+    bgra.extend_from_slice(image);
+  }
+}
+
+impl PixelFormat for RGBA {
+  const BYTES_PER_PIXEL: usize = 4;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>> {
+    capture.get_rgb_screenshot()
+  }
+
+  fn to_rgb(image: &[u8]) -> Vec<u8> {
+    image
+      .chunks_exact(4)
+      .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+      .collect()
+  }
+
+  fn extend_bgra(image: &[u8], bgra: &mut Vec<u8>) {
+    bgra.extend(
+      image
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]]),
+    );
+  }
+}
+
+impl PixelFormat for RGB24 {
+  const BYTES_PER_PIXEL: usize = 3;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>> {
+    capture.get_rgb24_screenshot()
+  }
+
+  fn to_rgb(image: &[u8]) -> Vec<u8> {
+    image.to_vec()
+  }
+}
+
+impl PixelFormat for Gray8 {
+  const BYTES_PER_PIXEL: usize = 1;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>> {
+    capture.get_gray_screenshot()
+  }
+
+  fn to_rgb(image: &[u8]) -> Vec<u8> {
+    image.iter().flat_map(|&luma| [luma, luma, luma]).collect()
+  }
+}
+
+impl PixelFormat for RGBA16F {
+  const BYTES_PER_PIXEL: usize = 8;
+  const HALF_FLOAT: bool = true;
+
+  fn screenshot<C: Capture>(capture: &mut C) -> Result<Screenshot<'_, Self>> {
+    capture.get_hdr_screenshot()
+  }
+
+  fn to_rgb(image: &[u8]) -> Vec<u8> {
+    image
+      .chunks_exact(8)
+      .flat_map(|pixel| {
+        let channel = |index: usize| linear_to_srgb8(f16_channel(pixel, index));
+        [channel(0), channel(1), channel(2)]
+      })
+      .collect()
+  }
+
+  fn to_linear_rgb(image: &[u8]) -> Vec<f32> {
+    image
+      .chunks_exact(8)
+      .flat_map(|pixel| [0, 1, 2].map(|index| f16_channel(pixel, index)))
+      .collect()
+  }
+}
 
 pub struct Screenshot<'a, Color> {
+  pub(crate) width: u32,
+  pub(crate) height: u32,
+  pub(crate) stride: u32,
+  pub(crate) image: &'a Vec<u8>,
+  pub(crate) meta: FrameMeta,
+  pub(crate) marker: PhantomData<Color>,
+}
+
+impl<'a, Color> Screenshot<'a, Color> {
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn total_pixels(&self) -> u32 {
+    self.height * self.width
+  }
+
+  pub fn stride(&self) -> u32 {
+    self.stride
+  }
+
+  pub fn meta(&self) -> FrameMeta {
+    self.meta
+  }
+
+  pub fn has_alpha(&self) -> bool {
+    self.meta.has_alpha()
+  }
+
+  pub fn as_bytes(&self) -> &'a [u8] {
+    self.image
+  }
+
+  pub fn as_ptr(&self) -> *const u8 {
+    self.image.as_ptr()
+  }
+
+  pub fn len(&self) -> usize {
+    self.image.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.image.is_empty()
+  }
+
+  pub(crate) fn is_packed(&self) -> bool
+  where
+    Color: PixelFormat,
+  {
+    self.stride as usize == self.width as usize * Color::BYTES_PER_PIXEL
+  }
+
+  pub(crate) fn packed(&self) -> Cow<'a, [u8]>
+  where
+    Color: PixelFormat,
+  {
+    if self.is_packed() {
+      return Cow::Borrowed(self.image);
+    }
+    let row_len = self.width as usize * Color::BYTES_PER_PIXEL;
+    let mut packed = Vec::with_capacity(row_len * self.height as usize);
+    for row in self.image.chunks_exact(self.stride as usize) {
+      packed.extend_from_slice(&row[..row_len]);
+    }
+    Cow::Owned(packed)
+  }
+
+  pub fn to_rgb(&self) -> Vec<u8>
+  where
+    Color: PixelFormat,
+  {
+    Color::to_rgb(&self.packed())
+  }
+
+  pub fn content_hash(&self) -> u64 {
+    let mut hasher = Xxh3::new();
+    hasher.update(&self.width.to_le_bytes());
+    hasher.update(&self.height.to_le_bytes());
+    hasher.update(self.image);
+    hasher.digest()
+  }
+
+  pub fn to_linear_f32(&self) -> Vec<f32>
+  where
+    Color: PixelFormat,
+  {
+    Color::to_linear_rgb(&self.packed())
+  }
+
+  pub fn to_owned(&self) -> ScreenshotOwned<Color> {
+    ScreenshotOwned {
+      width: self.width,
+      height: self.height,
+      stride: self.stride,
+      image: self.image.clone(),
+      meta: self.meta,
+      pool: None,
+      marker: PhantomData,
+    }
+  }
+}
+
+impl<Color> Deref for Screenshot<'_, Color> {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    self.image
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScreenshotOwned<Color> {
   width: u32,
   height: u32,
-  image: &'a Vec<u8>,
+  stride: u32,
+  image: Vec<u8>,
+  meta: FrameMeta,
+  pool: Option<Weak<PoolShared>>,
   marker: PhantomData<Color>,
 }
 
-impl<'a, Color> Screenshot<'a, Color> {
+impl<Color> ScreenshotOwned<Color> {
   pub fn width(&self) -> u32 {
     self.width
   }
@@ -232,12 +1244,64 @@ impl<'a, Color> Screenshot<'a, Color> {
   pub fn total_pixels(&self) -> u32 {
     self.height * self.width
   }
+
+  pub fn stride(&self) -> u32 {
+    self.stride
+  }
+
+  pub fn meta(&self) -> FrameMeta {
+    self.meta
+  }
+
+  pub fn has_alpha(&self) -> bool {
+    self.meta.has_alpha()
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.image
+  }
+
+  pub fn as_ptr(&self) -> *const u8 {
+    self.image.as_ptr()
+  }
+
+  pub fn len(&self) -> usize {
+    self.image.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.image.is_empty()
+  }
+
+  pub fn into_vec(mut self) -> Vec<u8> {
+    self.pool = None;
+    mem::take(&mut self.image)
+  }
+
+  pub fn as_screenshot(&self) -> Screenshot<'_, Color> {
+    Screenshot {
+      width: self.width,
+      height: self.height,
+      stride: self.stride,
+      image: &self.image,
+      meta: self.meta,
+      marker: PhantomData,
+    }
+  }
 }
 
-impl<'a, Color> Deref for Screenshot<'a, Color> {
-  type Target = &'a Vec<u8>;
+impl<Color> Deref for ScreenshotOwned<Color> {
+  type Target = [u8];
 
   fn deref(&self) -> &Self::Target {
     &self.image
   }
 }
+
+impl<Color> Drop for ScreenshotOwned<Color> {
+  fn drop(&mut self) {
+    if let Some(pool) = self.pool.as_ref().and_then(Weak::upgrade) {
+      pool.recycle(mem::take(&mut self.image));
+    }
+  }
+}
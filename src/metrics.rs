@@ -0,0 +1,179 @@
+use {
+  crate::{PixelFormat, Result, Screenshot, ScreenshotOwned, StreamshotError},
+  std::ops::Not,
+};
+
+const SSIM_WINDOW: usize = 8;
+const SSIM_STEP: usize = 4;
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn psnr(&self, other: &Screenshot<'_, Color>) -> Result<f64> {
+    self.check_same_size(other)?;
+    let (ours, theirs) = (self.to_rgb(), other.to_rgb());
+    if ours.is_empty() {
+      return Ok(f64::INFINITY);
+    }
+    let squared: u64 = ours
+      .iter()
+      .zip(&theirs)
+      .map(|(&a, &b)| (a.abs_diff(b) as u64).pow(2))
+      .sum();
+    let mse = squared as f64 / ours.len() as f64;
+    Ok(match mse == 0.0 {
+      true => f64::INFINITY,
+      false => 10.0 * (255.0 * 255.0 / mse).log10(),
+    })
+  }
+
+  pub fn ssim(&self, other: &Screenshot<'_, Color>) -> Result<f64> {
+    self.check_same_size(other)?;
+    let (width, height) = (self.width as usize, self.height as usize);
+    let (ours, theirs) = (luma(&self.to_rgb()), luma(&other.to_rgb()));
+    if ours.is_empty() {
+      return Ok(1.0);
+    }
+
+    let window = SSIM_WINDOW.min(width).min(height);
+    let starts = |length: usize| {
+      let last = length - window;
+      (0..=last)
+        .step_by(SSIM_STEP)
+        .chain(last.is_multiple_of(SSIM_STEP).not().then_some(last))
+    };
+    let mut total = 0.0;
+    let mut windows = 0;
+    for top in starts(height) {
+      for left in starts(width) {
+        total += window_ssim(&ours, &theirs, width, left, top, window);
+        windows += 1;
+      }
+    }
+    Ok(total / windows as f64)
+  }
+
+  fn check_same_size(&self, other: &Screenshot<'_, Color>) -> Result<()> {
+    match (self.width, self.height) == (other.width, other.height) {
+      true => Ok(()),
+      false => Err(StreamshotError::InvalidSize {
+        width: other.width as i32,
+        height: other.height as i32,
+      }),
+    }
+  }
+}
+
+impl<Color: PixelFormat> ScreenshotOwned<Color> {
+  pub fn psnr(&self, other: &ScreenshotOwned<Color>) -> Result<f64> {
+    self.as_screenshot().psnr(&other.as_screenshot())
+  }
+
+  pub fn ssim(&self, other: &ScreenshotOwned<Color>) -> Result<f64> {
+    self.as_screenshot().ssim(&other.as_screenshot())
+  }
+}
+
+fn luma(rgb: &[u8]) -> Vec<f64> {
+  rgb
+    .chunks_exact(3)
+    .map(|pixel| 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64)
+    .collect()
+}
+
+fn window_ssim(
+  ours: &[f64],
+  theirs: &[f64],
+  width: usize,
+  left: usize,
+  top: usize,
+  window: usize,
+) -> f64 {
+  let count = (window * window) as f64;
+  let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+  for y in top..top + window {
+    let row = y * width + left;
+    for (&a, &b) in ours[row..row + window]
+      .iter()
+      .zip(&theirs[row..row + window])
+    {
+      sum_a += a;
+      sum_b += b;
+      sum_aa += a * a;
+      sum_bb += b * b;
+      sum_ab += a * b;
+    }
+  }
+  let (mean_a, mean_b) = (sum_a / count, sum_b / count);
+  let variance_a = sum_aa / count - mean_a * mean_a;
+  let variance_b = sum_bb / count - mean_b * mean_b;
+  let covariance = sum_ab / count - mean_a * mean_b;
+  ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+    / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (variance_a + variance_b + SSIM_C2))
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    crate::{FrameMeta, Gray8, Screenshot},
+    std::marker::PhantomData,
+  };
+
+  fn gray(width: u32, image: &Vec<u8>) -> Screenshot<'_, Gray8> {
+    Screenshot {
+      width,
+      height: image.len() as u32 / width,
+      stride: width,
+      image,
+      meta: FrameMeta::new(1),
+      marker: PhantomData,
+    }
+  }
+
+  fn checkerboard(size: usize, dark: u8, light: u8) -> Vec<u8> {
+    (0..size * size)
+      .map(|index| match (index / size + index % size) % 2 {
+        0 => dark,
+        _ => light,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn identical_frames_match_perfectly() {
+    let image = checkerboard(10, 20, 200);
+    let screenshot = gray(10, &image);
+    assert_eq!(screenshot.psnr(&screenshot).unwrap(), f64::INFINITY);
+    assert!((screenshot.ssim(&screenshot).unwrap() - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn psnr_follows_the_mean_squared_error() {
+    let (ours, theirs) = (vec![100; 4], vec![100, 100, 100, 110]);
+    let psnr = gray(2, &ours).psnr(&gray(2, &theirs)).unwrap();
+    assert!((psnr - 10.0 * (255.0f64 * 255.0 / 25.0).log10()).abs() < 1e-9);
+  }
+
+  #[test]
+  fn ssim_drops_with_structural_changes() {
+    let original = checkerboard(10, 20, 200);
+    let softened = checkerboard(10, 60, 160);
+    let inverted = checkerboard(10, 200, 20);
+    let reference = gray(10, &original);
+    let softened = reference.ssim(&gray(10, &softened)).unwrap();
+    let inverted = reference.ssim(&gray(10, &inverted)).unwrap();
+    assert!(softened < 1.0 && softened > 0.5);
+    assert!(inverted < 0.0);
+    let back = gray(10, &checkerboard(10, 60, 160))
+      .ssim(&reference)
+      .unwrap();
+    assert!((back - softened).abs() < 1e-9);
+  }
+
+  #[test]
+  fn metrics_reject_mismatched_sizes() {
+    let (small, large) = (vec![0; 4], vec![0; 9]);
+    assert!(gray(2, &small).psnr(&gray(3, &large)).is_err());
+    assert!(gray(2, &small).ssim(&gray(3, &large)).is_err());
+  }
+}
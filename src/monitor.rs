@@ -0,0 +1,351 @@
+use {
+  crate::{
+    dpi::{monitor_dpi, DEFAULT_DPI},
+    gdi::read_screen,
+    wrappers::PerMonitorDpiScope,
+    Capture, FrameBuffer, FrameScale, Rect, Result, StreamshotError,
+  },
+  std::mem::size_of,
+  windows::{
+    core::{Error, HSTRING},
+    Win32::{
+      Foundation::{BOOL, LPARAM, RECT},
+      Graphics::Gdi::{
+        EnumDisplayMonitors, EnumDisplaySettingsW, GetMonitorInfoW, DEVMODEW,
+        ENUM_CURRENT_SETTINGS, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+      },
+      UI::WindowsAndMessaging::MONITORINFOF_PRIMARY,
+    },
+  },
+};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Monitor {
+  #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hmonitor"))]
+  handle: HMONITOR,
+  name: String,
+  bounds: Rect,
+  primary: bool,
+  refresh_rate: u32,
+}
+
+impl Monitor {
+  pub fn from_handle(handle: HMONITOR) -> Result<Self> {
+    let _dpi = PerMonitorDpiScope::enter();
+    monitor_info(handle).ok_or(StreamshotError::MonitorNotFound)
+  }
+
+  pub fn handle(&self) -> HMONITOR {
+    self.handle
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn bounds(&self) -> Rect {
+    self.bounds
+  }
+
+  pub fn is_primary(&self) -> bool {
+    self.primary
+  }
+
+  pub fn refresh_rate(&self) -> u32 {
+    self.refresh_rate
+  }
+
+  pub fn dpi(&self) -> u32 {
+    monitor_dpi(self.handle)
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.dpi() as f64 / DEFAULT_DPI as f64
+  }
+}
+
+pub struct MonitorFinder {
+  monitors: Vec<Monitor>,
+}
+
+impl MonitorFinder {
+  pub fn new() -> Result<Self> {
+    Ok(Self {
+      monitors: get_monitors()?,
+    })
+  }
+
+  pub fn monitors(&self) -> &[Monitor] {
+    &self.monitors
+  }
+
+  pub fn primary(&self) -> Result<MonitorScreenshotBuffer> {
+    self
+      .monitors
+      .iter()
+      .find(|monitor| monitor.primary)
+      .ok_or(StreamshotError::MonitorNotFound)
+      .and_then(MonitorScreenshotBuffer::new)
+  }
+
+  pub fn find(&self, name: &str) -> Result<MonitorScreenshotBuffer> {
+    self
+      .monitors
+      .iter()
+      .find(|monitor| monitor.name == name)
+      .ok_or(StreamshotError::MonitorNotFound)
+      .and_then(MonitorScreenshotBuffer::new)
+  }
+}
+
+fn get_monitors() -> Result<Vec<Monitor>> {
+  let _dpi = PerMonitorDpiScope::enter();
+  let mut monitors = Vec::new();
+  unsafe {
+    let result = EnumDisplayMonitors(
+      HDC::default(),
+      None,
+      Some(ml_callback),
+      LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+    );
+    if result == false {
+      return Err(Error::from_win32().into());
+    }
+  }
+  Ok(monitors)
+}
+
+unsafe extern "system" fn ml_callback(
+  hmonitor: HMONITOR,
+  _hdc: HDC,
+  _rect: *mut RECT,
+  lparam: LPARAM,
+) -> BOOL {
+  let monitors = lparam.0 as *mut Vec<Monitor>;
+  if let Some(monitor) = monitor_info(hmonitor) {
+    (*monitors).push(monitor);
+  }
+  BOOL::from(true)
+}
+
+fn monitor_info(hmonitor: HMONITOR) -> Option<Monitor> {
+  let mut info = MONITORINFOEXW::default();
+  info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+  unsafe {
+    if GetMonitorInfoW(
+      hmonitor,
+      &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+    ) == false
+    {
+      return None;
+    }
+  }
+
+  let name_len = info
+    .szDevice
+    .iter()
+    .position(|&c| c == 0)
+    .unwrap_or(info.szDevice.len());
+  let name = String::from_utf16_lossy(&info.szDevice[..name_len]);
+
+  let mut mode = DEVMODEW {
+    dmSize: size_of::<DEVMODEW>() as u16,
+    ..Default::default()
+  };
+  let refresh_rate = unsafe {
+    match EnumDisplaySettingsW(
+      &HSTRING::from(name.as_str()),
+      ENUM_CURRENT_SETTINGS,
+      &mut mode,
+    )
+    .as_bool()
+    {
+      true => mode.dmDisplayFrequency,
+      false => 0,
+    }
+  };
+
+  Some(Monitor {
+    handle: hmonitor,
+    name,
+    bounds: info.monitorInfo.rcMonitor.into(),
+    primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+    refresh_rate,
+  })
+}
+
+pub struct MonitorScreenshotBuffer {
+  handle: HMONITOR,
+  bounds: Rect,
+  cursor: bool,
+  scale: FrameScale,
+  frame: FrameBuffer,
+}
+
+impl MonitorScreenshotBuffer {
+  pub fn new(monitor: &Monitor) -> Result<Self> {
+    let width = monitor.bounds.width();
+    let height = monitor.bounds.height();
+    if width <= 0 || height <= 0 {
+      return Err(StreamshotError::InvalidSize { width, height });
+    }
+
+    Ok(Self {
+      handle: monitor.handle,
+      bounds: monitor.bounds,
+      cursor: false,
+      scale: FrameScale::default(),
+      frame: FrameBuffer::new(width, height),
+    })
+  }
+
+  pub fn with_cursor(mut self, cursor: bool) -> Self {
+    self.cursor = cursor;
+    self
+  }
+
+  pub fn with_frame_scale(mut self, scale: FrameScale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn frame_scale(&self) -> FrameScale {
+    self.scale
+  }
+}
+
+impl Capture for MonitorScreenshotBuffer {
+  fn read(&mut self) -> Result<()> {
+    self.frame.resize(self.bounds.width(), self.bounds.height());
+    read_screen(
+      self.bounds.left,
+      self.bounds.top,
+      self.cursor,
+      &mut self.frame,
+    )?;
+    if self.scale == FrameScale::Logical {
+      self.frame.scale_for_dpi(monitor_dpi(self.handle));
+    }
+    self.frame.set_monitor(self.handle);
+    Ok(())
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    Some(self.handle)
+  }
+}
+
+pub struct VirtualDesktopBuffer {
+  monitors: Vec<Monitor>,
+  bounds: Rect,
+  cursor: bool,
+  frame: FrameBuffer,
+}
+
+impl VirtualDesktopBuffer {
+  pub fn new() -> Result<Self> {
+    let monitors = get_monitors()?;
+    let bounds = virtual_bounds(&monitors)?;
+    Ok(Self {
+      monitors,
+      bounds,
+      cursor: false,
+      frame: FrameBuffer::new(bounds.width(), bounds.height()),
+    })
+  }
+
+  pub fn with_cursor(mut self, cursor: bool) -> Self {
+    self.cursor = cursor;
+    self
+  }
+
+  pub fn bounds(&self) -> Rect {
+    self.bounds
+  }
+
+  pub fn monitors(&self) -> &[Monitor] {
+    &self.monitors
+  }
+
+  pub fn refresh(&mut self) -> Result<()> {
+    let monitors = get_monitors()?;
+    self.bounds = virtual_bounds(&monitors)?;
+    self.monitors = monitors;
+    Ok(())
+  }
+}
+
+impl Capture for VirtualDesktopBuffer {
+  fn read(&mut self) -> Result<()> {
+    self.frame.resize(self.bounds.width(), self.bounds.height());
+    read_screen(
+      self.bounds.left,
+      self.bounds.top,
+      self.cursor,
+      &mut self.frame,
+    )?;
+
+    let width = self.bounds.width();
+    let mut spans = Vec::with_capacity(self.monitors.len());
+    for (y, row) in self
+      .frame
+      .buffer_mut()
+      .chunks_exact_mut(4 * width as usize)
+      .enumerate()
+    {
+      let y = self.bounds.top + y as i32;
+      spans.clear();
+      spans.extend(
+        self
+          .monitors
+          .iter()
+          .map(|monitor| monitor.bounds)
+          .filter(|bounds| (bounds.top..bounds.bottom).contains(&y))
+          .map(|bounds| {
+            (
+              bounds.left - self.bounds.left,
+              bounds.right - self.bounds.left,
+            )
+          }),
+      );
+      spans.sort_unstable();
+      let mut x = 0;
+      for &(left, right) in spans.iter().chain([&(width, width)]) {
+        for pixel in row[4 * x as usize..4 * left.max(x) as usize].chunks_exact_mut(4) {
+          pixel.copy_from_slice(&[0, 0, 0, 255]);
+        }
+        x = x.max(right);
+      }
+    }
+    Ok(())
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+}
+
+fn virtual_bounds(monitors: &[Monitor]) -> Result<Rect> {
+  let bounds = monitors
+    .iter()
+    .map(|monitor| monitor.bounds)
+    .reduce(|union, bounds| Rect {
+      left: union.left.min(bounds.left),
+      top: union.top.min(bounds.top),
+      right: union.right.max(bounds.right),
+      bottom: union.bottom.max(bounds.bottom),
+    })
+    .ok_or(StreamshotError::MonitorNotFound)?;
+  if bounds.width() <= 0 || bounds.height() <= 0 {
+    return Err(StreamshotError::InvalidSize {
+      width: bounds.width(),
+      height: bounds.height(),
+    });
+  }
+  Ok(bounds)
+}
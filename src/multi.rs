@@ -0,0 +1,261 @@
+use {
+  crate::{
+    pacer::Pacer, Capture, FramePool, PixelFormat, Result, ScreenshotOwned, StreamshotError,
+    WindowScreenshotBuffer,
+  },
+  std::{
+    ops::Not,
+    sync::{
+      atomic::{AtomicBool, Ordering},
+      mpsc::{self, Receiver, SyncSender},
+      Arc, Weak,
+    },
+    thread,
+  },
+  windows::Win32::Foundation::HWND,
+};
+
+const DEFAULT_FPS: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WindowId(isize);
+
+impl WindowId {
+  pub fn hwnd(&self) -> HWND {
+    HWND(self.0)
+  }
+}
+
+impl From<HWND> for WindowId {
+  fn from(handle: HWND) -> Self {
+    Self(handle.0)
+  }
+}
+
+type TaggedFrame<Color> = (WindowId, Result<ScreenshotOwned<Color>>);
+
+pub struct MultiCapture<Color> {
+  fps: u32,
+  sender: Weak<SyncSender<TaggedFrame<Color>>>,
+  receiver: Receiver<TaggedFrame<Color>>,
+  windows: Vec<(WindowId, Arc<AtomicBool>)>,
+}
+
+impl<Color: PixelFormat + Send + 'static> MultiCapture<Color> {
+  pub fn new() -> Self {
+    let (_, receiver) = mpsc::sync_channel(1);
+    Self {
+      fps: DEFAULT_FPS,
+      sender: Weak::new(),
+      receiver,
+      windows: Vec::new(),
+    }
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps;
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn add(&mut self, handle: HWND) -> WindowId {
+    self.add_with(WindowId::from(handle), move || {
+      WindowScreenshotBuffer::new(handle)
+    })
+  }
+
+  pub fn add_with<C, F>(&mut self, id: WindowId, make_capture: F) -> WindowId
+  where
+    C: Capture,
+    F: FnOnce() -> Result<C> + Send + 'static,
+  {
+    self.remove(id);
+    let running = Arc::new(AtomicBool::new(true));
+    self.windows.push((id, running.clone()));
+
+    let fps = self.fps;
+    let sender = self.sender();
+    thread::spawn(move || {
+      let _exit = ExitGuard(running.clone());
+      let mut capture = match make_capture() {
+        Ok(capture) => capture,
+        Err(error) => {
+          let _ = sender.send((id, Err(error)));
+          return;
+        }
+      };
+      let mut pacer = Pacer::new(fps);
+      let pool = FramePool::default();
+      while running.load(Ordering::Relaxed) {
+        pacer.wait();
+        let frame = capture
+          .get_screenshot::<Color>()
+          .map(|screenshot| pool.take(&screenshot));
+        let fatal = frame.as_ref().is_err_and(is_fatal);
+        if sender.send((id, frame)).is_err() || fatal {
+          return;
+        }
+      }
+    });
+    id
+  }
+
+  fn sender(&mut self) -> Arc<SyncSender<TaggedFrame<Color>>> {
+    self
+      .windows
+      .retain(|(_, running)| running.load(Ordering::Relaxed));
+    if let Some(sender) = self.sender.upgrade() {
+      return sender;
+    }
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let sender = Arc::new(sender);
+    self.sender = Arc::downgrade(&sender);
+    self.receiver = receiver;
+    sender
+  }
+
+  pub fn remove(&mut self, id: WindowId) -> bool {
+    let count = self.windows.len();
+    self.windows.retain(|(window, running)| {
+      let keep = *window != id;
+      if keep.not() {
+        running.store(false, Ordering::Relaxed);
+      }
+      keep
+    });
+    self.windows.len() != count
+  }
+
+  pub fn windows(&self) -> impl Iterator<Item = WindowId> + '_ {
+    self
+      .windows
+      .iter()
+      .filter(|(_, running)| running.load(Ordering::Relaxed))
+      .map(|(id, _)| *id)
+  }
+
+  pub fn len(&self) -> usize {
+    self.windows().count()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn recv(&self) -> Option<TaggedFrame<Color>> {
+    if self.is_empty() {
+      return self.try_recv();
+    }
+    self.receiver.recv().ok()
+  }
+
+  pub fn try_recv(&self) -> Option<TaggedFrame<Color>> {
+    self.receiver.try_recv().ok()
+  }
+}
+
+impl<Color: PixelFormat + Send + 'static> Default for MultiCapture<Color> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<Color> Drop for MultiCapture<Color> {
+  fn drop(&mut self) {
+    for (_, running) in &self.windows {
+      running.store(false, Ordering::Relaxed);
+    }
+  }
+}
+
+struct ExitGuard(Arc<AtomicBool>);
+
+impl Drop for ExitGuard {
+  fn drop(&mut self) {
+    self.0.store(false, Ordering::Relaxed);
+  }
+}
+
+fn is_fatal(error: &StreamshotError) -> bool {
+  matches!(
+    error,
+    StreamshotError::WindowClosed
+      | StreamshotError::WindowNotFound
+      | StreamshotError::MonitorNotFound
+      | StreamshotError::ElevationRequired
+      | StreamshotError::Unsupported(_)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{FrameBuffer, BGRA},
+  };
+
+  struct FakeCapture {
+    errors: Vec<StreamshotError>,
+    frame: FrameBuffer,
+  }
+
+  impl Capture for FakeCapture {
+    fn read(&mut self) -> Result<()> {
+      match self.errors.pop() {
+        Some(error) => Err(error),
+        None => {
+          self.frame.stamp();
+          Ok(())
+        }
+      }
+    }
+
+    fn frame_buffer(&mut self) -> &mut FrameBuffer {
+      &mut self.frame
+    }
+  }
+
+  fn fake(errors: Vec<StreamshotError>) -> impl FnOnce() -> Result<FakeCapture> {
+    move || {
+      Ok(FakeCapture {
+        errors,
+        frame: FrameBuffer::new(2, 2),
+      })
+    }
+  }
+
+  #[test]
+  fn recv_ends_when_every_worker_exits() {
+    let mut multi = MultiCapture::<BGRA>::new().with_fps(1000);
+    multi.add_with(WindowId(1), fake(vec![StreamshotError::WindowClosed]));
+    let (id, frame) = multi.recv().unwrap();
+    assert_eq!(id, WindowId(1));
+    assert!(matches!(frame, Err(StreamshotError::WindowClosed)));
+    assert!(multi.recv().is_none());
+    assert!(multi.is_empty());
+  }
+
+  #[test]
+  fn transient_errors_keep_the_worker_alive() {
+    let mut multi = MultiCapture::<BGRA>::new().with_fps(1000);
+    multi.add_with(WindowId(1), fake(vec![StreamshotError::WindowMinimized]));
+    assert!(matches!(
+      multi.recv(),
+      Some((_, Err(StreamshotError::WindowMinimized)))
+    ));
+    assert!(matches!(multi.recv(), Some((_, Ok(_)))));
+    assert_eq!(multi.len(), 1);
+  }
+
+  #[test]
+  fn add_after_every_worker_exits_reopens_the_channel() {
+    let mut multi = MultiCapture::<BGRA>::new().with_fps(1000);
+    multi.add_with(WindowId(1), fake(vec![StreamshotError::WindowClosed]));
+    while multi.recv().is_some() {}
+    multi.add_with(WindowId(2), fake(Vec::new()));
+    assert!(matches!(multi.recv(), Some((WindowId(2), Ok(_)))));
+  }
+}
@@ -0,0 +1,117 @@
+use {
+  crate::{
+    check_window,
+    finder::is_cloaked,
+    window_rect,
+    wrappers::{HrgnWrapper, PerMonitorDpiScope},
+    Result,
+  },
+  std::{ffi::c_void, mem::size_of, ops::Not},
+  windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::{
+      Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS},
+      Gdi::{CombineRgn, MonitorFromRect, MONITOR_DEFAULTTONULL, NULLREGION, RGN_AND, RGN_DIFF},
+    },
+    UI::WindowsAndMessaging::{
+      GetSystemMetrics, GetWindow, GetWindowLongW, GetWindowRect, IsIconic, IsWindowVisible,
+      GWL_EXSTYLE, GW_HWNDPREV, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+      SM_YVIRTUALSCREEN, WS_EX_LAYERED, WS_EX_TRANSPARENT,
+    },
+  },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+  Visible,
+  Hidden,
+  OffScreen,
+  Occluded,
+}
+
+impl Visibility {
+  pub fn is_visible(self) -> bool {
+    self == Self::Visible
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OcclusionMode {
+  #[default]
+  Capture,
+  Pause,
+  MarkStale,
+}
+
+pub(crate) fn window_visibility(handle: HWND) -> Result<Visibility> {
+  check_window(handle)?;
+  unsafe {
+    if IsIconic(handle).as_bool() {
+      return Ok(Visibility::Visible);
+    }
+    if IsWindowVisible(handle).as_bool().not() || is_cloaked(handle) {
+      return Ok(Visibility::Hidden);
+    }
+  }
+
+  let rect = window_rect(handle)?;
+  let _dpi = PerMonitorDpiScope::enter();
+  if unsafe { MonitorFromRect(&rect, MONITOR_DEFAULTTONULL) }.is_invalid() {
+    return Ok(Visibility::OffScreen);
+  }
+
+  let visible = HrgnWrapper::create(&rect)?;
+  let screen = HrgnWrapper::create(&virtual_screen())?;
+  unsafe {
+    CombineRgn(visible.inner(), visible.inner(), screen.inner(), RGN_AND);
+  }
+  let mut above = unsafe { GetWindow(handle, GW_HWNDPREV) };
+  while above.0 != 0 {
+    if let Some(cover) = covering_rect(above) {
+      let cover = HrgnWrapper::create(&cover)?;
+      let remaining =
+        unsafe { CombineRgn(visible.inner(), visible.inner(), cover.inner(), RGN_DIFF) };
+      if remaining == NULLREGION {
+        return Ok(Visibility::Occluded);
+      }
+    }
+    above = unsafe { GetWindow(above, GW_HWNDPREV) };
+  }
+  Ok(Visibility::Visible)
+}
+
+fn covering_rect(handle: HWND) -> Option<RECT> {
+  unsafe {
+    if IsWindowVisible(handle).as_bool().not() || IsIconic(handle).as_bool() || is_cloaked(handle) {
+      return None;
+    }
+    let ex_style = GetWindowLongW(handle, GWL_EXSTYLE) as u32;
+    if ex_style & (WS_EX_LAYERED.0 | WS_EX_TRANSPARENT.0) != 0 {
+      return None;
+    }
+    let mut rect = RECT::default();
+    let bounds = DwmGetWindowAttribute(
+      handle,
+      DWMWA_EXTENDED_FRAME_BOUNDS,
+      &mut rect as *mut RECT as *mut c_void,
+      size_of::<RECT>() as u32,
+    );
+    match bounds.is_ok() || GetWindowRect(handle, &mut rect).as_bool() {
+      true => Some(rect),
+      false => None,
+    }
+  }
+}
+
+fn virtual_screen() -> RECT {
+  unsafe {
+    let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+    let top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+    RECT {
+      left,
+      top,
+      right: left + GetSystemMetrics(SM_CXVIRTUALSCREEN),
+      bottom: top + GetSystemMetrics(SM_CYVIRTUALSCREEN),
+    }
+  }
+}
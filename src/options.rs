@@ -0,0 +1,213 @@
+use {
+  crate::{
+    pacer::Pacing, Capture, CaptureMethod, CaptureSession, DpiPolicy, FrameScale, MinimizedMode,
+    OcclusionMode, PrintWindowFlags, Rect, Result, RetryPolicy, WindowScreenshotBuffer,
+  },
+  windows::Win32::Foundation::HWND,
+};
+
+const DEFAULT_FPS: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaptureOptionsBuilder {
+  method: CaptureMethod,
+  print_flags: PrintWindowFlags,
+  minimized_mode: MinimizedMode,
+  cursor: bool,
+  alpha: bool,
+  region: Option<Rect>,
+  row_alignment: u32,
+  fps: u32,
+  skip_unchanged: bool,
+  pacing: Pacing,
+  dpi_policy: DpiPolicy,
+  scale: FrameScale,
+  retry: RetryPolicy,
+  occlusion: OcclusionMode,
+}
+
+impl CaptureOptionsBuilder {
+  pub fn new() -> Self {
+    Self {
+      method: CaptureMethod::default(),
+      print_flags: PrintWindowFlags::default(),
+      minimized_mode: MinimizedMode::default(),
+      cursor: false,
+      alpha: false,
+      region: None,
+      row_alignment: 1,
+      fps: DEFAULT_FPS,
+      skip_unchanged: false,
+      pacing: Pacing::default(),
+      dpi_policy: DpiPolicy::default(),
+      scale: FrameScale::default(),
+      retry: RetryPolicy::default(),
+      occlusion: OcclusionMode::default(),
+    }
+  }
+
+  pub fn with_capture_method(mut self, method: CaptureMethod) -> Self {
+    self.method = method;
+    self
+  }
+
+  pub fn capture_method(&self) -> CaptureMethod {
+    self.method
+  }
+
+  pub fn with_print_window_flags(mut self, flags: PrintWindowFlags) -> Self {
+    self.print_flags = flags;
+    self
+  }
+
+  pub fn print_window_flags(&self) -> PrintWindowFlags {
+    self.print_flags
+  }
+
+  pub fn with_minimized_mode(mut self, mode: MinimizedMode) -> Self {
+    self.minimized_mode = mode;
+    self
+  }
+
+  pub fn minimized_mode(&self) -> MinimizedMode {
+    self.minimized_mode
+  }
+
+  pub fn with_cursor(mut self, cursor: bool) -> Self {
+    self.cursor = cursor;
+    self
+  }
+
+  pub fn cursor(&self) -> bool {
+    self.cursor
+  }
+
+  pub fn with_alpha(mut self, alpha: bool) -> Self {
+    self.alpha = alpha;
+    self
+  }
+
+  pub fn alpha(&self) -> bool {
+    self.alpha
+  }
+
+  pub fn with_region(mut self, region: Rect) -> Self {
+    self.region = Some(region);
+    self
+  }
+
+  pub fn region(&self) -> Option<Rect> {
+    self.region
+  }
+
+  pub fn with_row_alignment(mut self, alignment: u32) -> Self {
+    self.row_alignment = alignment.max(1);
+    self
+  }
+
+  pub fn row_alignment(&self) -> u32 {
+    self.row_alignment
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps;
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn with_skip_unchanged(mut self, skip_unchanged: bool) -> Self {
+    self.skip_unchanged = skip_unchanged;
+    self
+  }
+
+  pub fn skip_unchanged(&self) -> bool {
+    self.skip_unchanged
+  }
+
+  pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+    self.pacing = pacing;
+    self
+  }
+
+  pub fn pacing(&self) -> Pacing {
+    self.pacing
+  }
+
+  pub fn with_dpi_policy(mut self, policy: DpiPolicy) -> Self {
+    self.dpi_policy = policy;
+    self
+  }
+
+  pub fn dpi_policy(&self) -> DpiPolicy {
+    self.dpi_policy
+  }
+
+  pub fn with_frame_scale(mut self, scale: FrameScale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn frame_scale(&self) -> FrameScale {
+    self.scale
+  }
+
+  pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+    self.retry = retry;
+    self
+  }
+
+  pub fn retry_policy(&self) -> RetryPolicy {
+    self.retry
+  }
+
+  pub fn with_occlusion(mut self, occlusion: OcclusionMode) -> Self {
+    self.occlusion = occlusion;
+    self
+  }
+
+  pub fn occlusion(&self) -> OcclusionMode {
+    self.occlusion
+  }
+
+  pub fn build(&self, handle: HWND) -> Result<WindowScreenshotBuffer> {
+    self.dpi_policy.apply()?;
+    Ok(self.configure(WindowScreenshotBuffer::new(handle)?))
+  }
+
+  pub fn build_session(&self, handle: HWND) -> Result<CaptureSession<WindowScreenshotBuffer>> {
+    Ok(self.session(self.build(handle)?))
+  }
+
+  pub fn configure(&self, buffer: WindowScreenshotBuffer) -> WindowScreenshotBuffer {
+    let buffer = match self.region {
+      Some(region) => buffer.with_region(region),
+      None => buffer,
+    };
+    buffer
+      .with_capture_method(self.method)
+      .with_print_window_flags(self.print_flags)
+      .with_minimized_mode(self.minimized_mode)
+      .with_cursor(self.cursor)
+      .with_alpha(self.alpha)
+      .with_frame_scale(self.scale)
+      .with_retry_policy(self.retry)
+      .with_row_alignment(self.row_alignment)
+  }
+
+  pub fn session<C: Capture>(&self, capture: C) -> CaptureSession<C> {
+    CaptureSession::new(capture)
+      .with_fps(self.fps)
+      .with_skip_unchanged(self.skip_unchanged)
+      .with_pacing(self.pacing)
+      .with_occlusion(self.occlusion)
+  }
+}
+
+impl Default for CaptureOptionsBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
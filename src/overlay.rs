@@ -0,0 +1,126 @@
+use crate::{Result, StreamshotError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overlay {
+  width: u32,
+  height: u32,
+  image: Vec<u8>,
+  x: i32,
+  y: i32,
+}
+
+impl Overlay {
+  pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Result<Self> {
+    let required = 4 * width as usize * height as usize;
+    if rgba.len() < required {
+      return Err(StreamshotError::BufferTooSmall {
+        required,
+        provided: rgba.len(),
+      });
+    }
+    Ok(Self {
+      width,
+      height,
+      image: rgba,
+      x: 0,
+      y: 0,
+    })
+  }
+
+  pub fn with_position(mut self, x: i32, y: i32) -> Self {
+    self.x = x;
+    self.y = y;
+    self
+  }
+
+  pub fn position(&self) -> (i32, i32) {
+    (self.x, self.y)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub(crate) fn blend(&self, frame: &mut [u8], width: u32, height: u32) {
+    let left = self.x.max(0);
+    let top = self.y.max(0);
+    let right = (self.x + self.width as i32).min(width as i32);
+    let bottom = (self.y + self.height as i32).min(height as i32);
+    if left >= right || top >= bottom {
+      return;
+    }
+
+    let columns = (right - left) as usize;
+    for y in top..bottom {
+      let source = 4 * ((y - self.y) as usize * self.width as usize + (left - self.x) as usize);
+      let target = 4 * (y as usize * width as usize + left as usize);
+      for (pixel, overlay) in frame[target..target + 4 * columns]
+        .chunks_exact_mut(4)
+        .zip(self.image[source..source + 4 * columns].chunks_exact(4))
+      {
+        let alpha = overlay[3] as u32;
+        let blend = |over: u8, under: u8| {
+          ((over as u32 * alpha + under as u32 * (255 - alpha) + 127) / 255) as u8
+        };
+        pixel[0] = blend(overlay[2], pixel[0]);
+        pixel[1] = blend(overlay[1], pixel[1]);
+        pixel[2] = blend(overlay[0], pixel[2]);
+        pixel[3] = blend(255, pixel[3]);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn opaque_pixels_replace_the_frame() {
+    let overlay = Overlay::new(1, 1, vec![10, 20, 30, 255]).unwrap();
+    let mut frame = vec![0, 0, 0, 0];
+    overlay.blend(&mut frame, 1, 1);
+    assert_eq!(frame, [30, 20, 10, 255]);
+  }
+
+  #[test]
+  fn alpha_mixes_with_the_frame() {
+    let overlay = Overlay::new(2, 1, vec![255, 255, 255, 0, 255, 0, 0, 128]).unwrap();
+    let mut frame = vec![40, 50, 60, 255, 0, 0, 0, 0];
+    overlay.blend(&mut frame, 2, 1);
+    assert_eq!(frame, [40, 50, 60, 255, 0, 0, 128, 128]);
+  }
+
+  #[test]
+  fn clips_to_the_frame() {
+    let overlay = Overlay::new(2, 2, vec![255; 16])
+      .unwrap()
+      .with_position(-1, -1);
+    let mut frame = vec![0; 16];
+    overlay.blend(&mut frame, 2, 2);
+    assert_eq!(&frame[..4], [255; 4]);
+    assert_eq!(&frame[4..], [0; 12]);
+
+    let outside = Overlay::new(2, 2, vec![255; 16])
+      .unwrap()
+      .with_position(2, 0);
+    let mut frame = vec![0; 16];
+    outside.blend(&mut frame, 2, 2);
+    assert_eq!(frame, [0; 16]);
+  }
+
+  #[test]
+  fn new_rejects_short_buffers() {
+    assert!(matches!(
+      Overlay::new(2, 2, vec![0; 15]),
+      Err(StreamshotError::BufferTooSmall {
+        required: 16,
+        provided: 15
+      })
+    ));
+  }
+}
@@ -0,0 +1,115 @@
+use {
+  crate::{
+    dxgi::monitor_output,
+    wrappers::{HandleWrapper, TimerPeriod},
+    Result,
+  },
+  std::{
+    thread,
+    time::{Duration, Instant},
+  },
+  windows::Win32::{
+    Foundation::WAIT_OBJECT_0,
+    Graphics::{Dwm::DwmFlush, Dxgi::IDXGIOutput, Gdi::HMONITOR},
+    System::Threading::{SetWaitableTimer, WaitForSingleObject, INFINITE},
+  },
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pacing {
+  #[default]
+  Interval,
+  DwmFlush,
+  VBlank,
+}
+
+enum Sleeper {
+  HighResolution(HandleWrapper),
+  Coarse { _period: Option<TimerPeriod> },
+}
+
+impl Sleeper {
+  fn new() -> Self {
+    match HandleWrapper::high_resolution_timer() {
+      Ok(timer) => Sleeper::HighResolution(timer),
+      Err(_) => Sleeper::Coarse {
+        _period: TimerPeriod::begin(1),
+      },
+    }
+  }
+
+  fn sleep(&self, duration: Duration) {
+    if let Sleeper::HighResolution(timer) = self {
+      let due_time = -((duration.as_nanos() / 100) as i64);
+      unsafe {
+        if SetWaitableTimer(timer.inner(), &due_time, 0, None, None, false).as_bool()
+          && WaitForSingleObject(timer.inner(), INFINITE) == WAIT_OBJECT_0
+        {
+          return;
+        }
+      }
+    }
+    thread::sleep(duration);
+  }
+}
+
+enum VSync {
+  None,
+  DwmFlush,
+  VBlank(IDXGIOutput),
+}
+
+pub(crate) struct Pacer {
+  interval: Duration,
+  next: Instant,
+  sleeper: Sleeper,
+  vsync: VSync,
+}
+
+impl Pacer {
+  pub(crate) fn new(fps: u32) -> Self {
+    Self {
+      interval: Duration::from_secs(1) / fps.max(1),
+      next: Instant::now(),
+      sleeper: Sleeper::new(),
+      vsync: VSync::None,
+    }
+  }
+
+  pub(crate) fn with_pacing(fps: u32, pacing: Pacing, monitor: Option<HMONITOR>) -> Result<Self> {
+    let vsync = match pacing {
+      Pacing::Interval => VSync::None,
+      Pacing::DwmFlush => VSync::DwmFlush,
+      Pacing::VBlank => VSync::VBlank(monitor_output(monitor)?),
+    };
+    Ok(Self {
+      vsync,
+      ..Self::new(fps)
+    })
+  }
+
+  pub(crate) fn wait(&mut self) -> u64 {
+    let now = Instant::now();
+    let missed = if self.next > now {
+      self.sleeper.sleep(self.next - now);
+      self.next += self.interval;
+      0
+    } else {
+      let missed = ((now - self.next).as_nanos() / self.interval.as_nanos().max(1)) as u64;
+      self.next = now + self.interval;
+      missed
+    };
+    self.sync();
+    missed
+  }
+
+  fn sync(&self) {
+    unsafe {
+      let _ = match &self.vsync {
+        VSync::None => Ok(()),
+        VSync::DwmFlush => DwmFlush(),
+        VSync::VBlank(output) => output.WaitForVBlank(),
+      };
+    }
+  }
+}
@@ -0,0 +1,58 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+const CHUNK_PIXELS: usize = 16 * 1024;
+
+pub(crate) fn map_pixels<F>(
+  source: &[u8],
+  source_bpp: usize,
+  target: &mut [u8],
+  target_bpp: usize,
+  convert: F,
+) where
+  F: Fn(&[u8], &mut [u8]) + Send + Sync,
+{
+  let run = |(source, target): (&[u8], &mut [u8])| {
+    source
+      .chunks_exact(source_bpp)
+      .zip(target.chunks_exact_mut(target_bpp))
+      .for_each(|(source, target)| convert(source, target));
+  };
+
+  #[cfg(feature = "rayon")]
+  source
+    .par_chunks(CHUNK_PIXELS * source_bpp)
+    .zip(target.par_chunks_mut(CHUNK_PIXELS * target_bpp))
+    .for_each(run);
+  #[cfg(not(feature = "rayon"))]
+  source
+    .chunks(CHUNK_PIXELS * source_bpp)
+    .zip(target.chunks_mut(CHUNK_PIXELS * target_bpp))
+    .for_each(run);
+}
+
+pub(crate) fn for_each_chunk<F>(pixels: &mut [u8], bpp: usize, process: F)
+where
+  F: Fn(&mut [u8]) + Send + Sync,
+{
+  #[cfg(feature = "rayon")]
+  pixels.par_chunks_mut(CHUNK_PIXELS * bpp).for_each(process);
+  #[cfg(not(feature = "rayon"))]
+  pixels.chunks_mut(CHUNK_PIXELS * bpp).for_each(process);
+}
+
+pub(crate) fn for_each_row<F>(target: &mut [u8], row_len: usize, process: F)
+where
+  F: Fn(usize, &mut [u8]) + Send + Sync,
+{
+  #[cfg(feature = "rayon")]
+  target
+    .par_chunks_mut(row_len)
+    .enumerate()
+    .for_each(|(y, row)| process(y, row));
+  #[cfg(not(feature = "rayon"))]
+  target
+    .chunks_mut(row_len)
+    .enumerate()
+    .for_each(|(y, row)| process(y, row));
+}
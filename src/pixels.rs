@@ -0,0 +1,80 @@
+use crate::{PixelFormat, Result, Screenshot, StreamshotError, BGRA, RGBA};
+
+impl<'a, Color: PixelFormat> Screenshot<'a, Color> {
+  pub fn pixel_bytes(&self, x: u32, y: u32) -> Option<&'a [u8]> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    let start = y as usize * self.stride as usize + x as usize * Color::BYTES_PER_PIXEL;
+    self.image.get(start..start + Color::BYTES_PER_PIXEL)
+  }
+
+  pub fn row(&self, y: u32) -> Option<&'a [u8]> {
+    self.region_rows(0, y, self.width, 1).ok()?.next()
+  }
+
+  pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'a [u8]> + ExactSizeIterator {
+    let row_len = self.width as usize * Color::BYTES_PER_PIXEL;
+    let image: &'a [u8] = self.image;
+    image
+      .chunks_exact(self.stride.max(1) as usize)
+      .take(self.height as usize)
+      .map(move |row| &row[..row_len])
+  }
+
+  pub fn region_rows(
+    &self,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+  ) -> Result<impl ExactSizeIterator<Item = &'a [u8]>> {
+    self.check_region(x, y, width, height)?;
+    let start = x as usize * Color::BYTES_PER_PIXEL;
+    let end = start + width as usize * Color::BYTES_PER_PIXEL;
+    Ok(
+      self
+        .rows()
+        .skip(y as usize)
+        .take(height as usize)
+        .map(move |row| &row[start..end]),
+    )
+  }
+
+  pub(crate) fn check_region(&self, x: u32, y: u32, width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: width as i32,
+        height: height as i32,
+      });
+    }
+    let fits = |offset: u32, length: u32, limit: u32| {
+      offset.checked_add(length).is_some_and(|end| end <= limit)
+    };
+    match fits(x, width, self.width) && fits(y, height, self.height) {
+      true => Ok(()),
+      false => Err(StreamshotError::OutOfBounds {
+        x,
+        y,
+        width,
+        height,
+      }),
+    }
+  }
+}
+
+impl Screenshot<'_, BGRA> {
+  pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+    self
+      .pixel_bytes(x, y)
+      .and_then(|pixel| pixel.try_into().ok())
+  }
+}
+
+impl Screenshot<'_, RGBA> {
+  pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+    self
+      .pixel_bytes(x, y)
+      .and_then(|pixel| pixel.try_into().ok())
+  }
+}
@@ -0,0 +1,77 @@
+use {
+  crate::{Screenshot, ScreenshotOwned},
+  std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+  },
+};
+
+const DEFAULT_CAPACITY: usize = 4;
+
+#[derive(Clone, Debug)]
+pub struct FramePool {
+  shared: Arc<PoolShared>,
+}
+
+#[derive(Debug)]
+pub(crate) struct PoolShared {
+  free: Mutex<Vec<Vec<u8>>>,
+  capacity: usize,
+}
+
+impl FramePool {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      shared: Arc::new(PoolShared {
+        free: Mutex::new(Vec::with_capacity(capacity)),
+        capacity,
+      }),
+    }
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.shared.capacity
+  }
+
+  pub fn available(&self) -> usize {
+    self.shared.free.lock().map_or(0, |free| free.len())
+  }
+
+  pub fn take<Color>(&self, screenshot: &Screenshot<'_, Color>) -> ScreenshotOwned<Color> {
+    let mut image = self
+      .shared
+      .free
+      .lock()
+      .ok()
+      .and_then(|mut free| free.pop())
+      .unwrap_or_default();
+    image.clear();
+    image.extend_from_slice(screenshot.image);
+
+    ScreenshotOwned {
+      width: screenshot.width,
+      height: screenshot.height,
+      stride: screenshot.stride,
+      image,
+      meta: screenshot.meta,
+      pool: Some(Arc::downgrade(&self.shared)),
+      marker: PhantomData,
+    }
+  }
+}
+
+impl Default for FramePool {
+  fn default() -> Self {
+    Self::new(DEFAULT_CAPACITY)
+  }
+}
+
+impl PoolShared {
+  pub(crate) fn recycle(&self, image: Vec<u8>) {
+    if let Ok(mut free) = self.free.lock() {
+      if free.len() < self.capacity {
+        free.push(image);
+      }
+    }
+  }
+}
@@ -0,0 +1,34 @@
+use {
+  crate::{Result, StreamshotError, WindowScreenshotBuffer},
+  raw_window_handle::{HasWindowHandle, RawWindowHandle, WindowHandle},
+};
+
+impl WindowScreenshotBuffer {
+  pub fn from_window(window: &impl HasWindowHandle) -> Result<Self> {
+    let handle = window
+      .window_handle()
+      .map_err(|_| StreamshotError::WindowNotFound)?;
+    Self::try_from(handle)
+  }
+}
+
+impl TryFrom<WindowHandle<'_>> for WindowScreenshotBuffer {
+  type Error = StreamshotError;
+
+  fn try_from(handle: WindowHandle<'_>) -> Result<Self> {
+    Self::try_from(handle.as_raw())
+  }
+}
+
+impl TryFrom<RawWindowHandle> for WindowScreenshotBuffer {
+  type Error = StreamshotError;
+
+  fn try_from(handle: RawWindowHandle) -> Result<Self> {
+    match handle {
+      RawWindowHandle::Win32(handle) => Self::from_raw_handle(handle.hwnd.get()),
+      _ => Err(StreamshotError::Unsupported(
+        "only Win32 window handles can be captured",
+      )),
+    }
+  }
+}
@@ -0,0 +1,102 @@
+use {
+  crate::{Capture, Result, StreamshotError, WindowFinder, WindowInfo},
+  std::{
+    thread,
+    time::{Duration, Instant},
+  },
+  windows::Win32::Foundation::HWND,
+};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WindowQuery {
+  Title(String),
+  ExactTitle(String),
+  Class(String),
+  Pid(u32),
+  ProcessName(String),
+}
+
+impl WindowQuery {
+  pub fn matches(&self, window: &WindowInfo) -> bool {
+    match self {
+      Self::Title(title) => window.title().contains(title.as_str()),
+      Self::ExactTitle(title) => window.title() == title,
+      Self::Class(class) => window.class() == class,
+      Self::Pid(pid) => window.pid() == *pid,
+      Self::ProcessName(name) => window
+        .process_name()
+        .is_some_and(|process| process.eq_ignore_ascii_case(name)),
+    }
+  }
+
+  pub fn resolve(&self) -> Result<HWND> {
+    WindowFinder::new()?
+      .windows()
+      .find(|window| self.matches(window))
+      .map(WindowInfo::hwnd)
+      .ok_or(StreamshotError::WindowNotFound)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReacquirePolicy {
+  query: WindowQuery,
+  interval: Duration,
+  timeout: Option<Duration>,
+}
+
+impl ReacquirePolicy {
+  pub fn new(query: WindowQuery) -> Self {
+    Self {
+      query,
+      interval: DEFAULT_INTERVAL,
+      timeout: None,
+    }
+  }
+
+  pub fn query(&self) -> &WindowQuery {
+    &self.query
+  }
+
+  pub fn with_interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  pub fn interval(&self) -> Duration {
+    self.interval
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  pub fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+
+  pub(crate) fn reacquire<C: Capture>(&self, capture: &mut C) -> Result<()> {
+    let start = Instant::now();
+    loop {
+      let retargeted = self
+        .query
+        .resolve()
+        .and_then(|handle| capture.retarget(handle));
+      match retargeted {
+        Ok(()) => return Ok(()),
+        Err(StreamshotError::WindowNotFound | StreamshotError::WindowClosed) => {}
+        Err(error) => return Err(error),
+      }
+      if self
+        .timeout
+        .is_some_and(|timeout| start.elapsed() >= timeout)
+      {
+        return Err(StreamshotError::WindowClosed);
+      }
+      thread::sleep(self.interval);
+    }
+  }
+}
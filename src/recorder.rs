@@ -0,0 +1,53 @@
+#[cfg(feature = "gif")]
+mod gif;
+#[cfg(feature = "mp4")]
+mod mp4;
+
+#[cfg(feature = "gif")]
+pub use self::gif::GifRecorder;
+#[cfg(feature = "mp4")]
+pub use self::mp4::Mp4Recorder;
+
+#[cfg(any(feature = "gif", feature = "mp4", feature = "rtsp"))]
+pub(crate) fn fit(
+  pixels: Vec<u8>,
+  bytes_per_pixel: usize,
+  width: usize,
+  height: usize,
+  canvas_width: usize,
+  canvas_height: usize,
+) -> Vec<u8> {
+  if width == canvas_width && height == canvas_height {
+    return pixels;
+  }
+
+  let mut canvas = vec![0; canvas_width * canvas_height * bytes_per_pixel];
+  let row_len = width.min(canvas_width) * bytes_per_pixel;
+  for y in 0..height.min(canvas_height) {
+    let src = y * width * bytes_per_pixel;
+    let dst = y * canvas_width * bytes_per_pixel;
+    canvas[dst..dst + row_len].copy_from_slice(&pixels[src..src + row_len]);
+  }
+  canvas
+}
+
+#[cfg(all(test, any(feature = "gif", feature = "mp4", feature = "rtsp")))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fit_keeps_matching_frames() {
+    let pixels = vec![1, 2, 3, 4];
+    assert_eq!(fit(pixels.clone(), 1, 2, 2, 2, 2), pixels);
+  }
+
+  #[test]
+  fn fit_pads_and_crops_to_the_canvas() {
+    let pixels = vec![1, 2, 3, 4, 5, 6];
+    assert_eq!(
+      fit(pixels.clone(), 1, 3, 2, 4, 3),
+      [1, 2, 3, 0, 4, 5, 6, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(fit(pixels, 1, 3, 2, 2, 1), [1, 2]);
+  }
+}
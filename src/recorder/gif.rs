@@ -0,0 +1,231 @@
+use {
+  super::fit,
+  crate::{Capture, CaptureSession, PixelFormat, Result, Screenshot, StreamshotError, BGRA},
+  gif::{Encoder, EncodingError, Frame, Repeat},
+  std::{
+    fs::File,
+    io::{BufWriter, Write},
+    ops::ControlFlow,
+    path::Path,
+    time::Duration,
+  },
+};
+
+const DEFAULT_FPS: u32 = 10;
+const DEFAULT_SPEED: i32 = 10;
+
+pub struct GifRecorder<W: Write> {
+  writer: Option<W>,
+  encoder: Option<Encoder<W>>,
+  fps: u32,
+  max_duration: Option<Duration>,
+  speed: i32,
+  width: usize,
+  height: usize,
+  previous: Vec<u8>,
+  pending: Option<Frame<'static>>,
+  frames: u32,
+}
+
+impl GifRecorder<BufWriter<File>> {
+  pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+    Ok(Self::new(BufWriter::new(File::create(path)?)))
+  }
+}
+
+impl<W: Write> GifRecorder<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer: Some(writer),
+      encoder: None,
+      fps: DEFAULT_FPS,
+      max_duration: None,
+      speed: DEFAULT_SPEED,
+      width: 0,
+      height: 0,
+      previous: Vec::new(),
+      pending: None,
+      frames: 0,
+    }
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps.max(1);
+    self
+  }
+
+  pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+    self.max_duration = Some(max_duration);
+    self
+  }
+
+  pub fn with_speed(mut self, speed: i32) -> Self {
+    self.speed = speed.clamp(1, 30);
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    Duration::from_secs(1) / self.fps * self.frames
+  }
+
+  pub fn is_full(&self) -> bool {
+    self
+      .max_duration
+      .is_some_and(|max_duration| self.elapsed() >= max_duration)
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    self.fps = session.fps().max(1);
+
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(flow) => flow,
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+
+  pub fn push_frame<Color: PixelFormat>(
+    &mut self,
+    screenshot: &Screenshot<'_, Color>,
+  ) -> Result<ControlFlow<()>> {
+    if self.is_full() {
+      return Ok(ControlFlow::Break(()));
+    }
+    if self.encoder.is_none() {
+      self.start(screenshot.width(), screenshot.height())?;
+    }
+
+    let current = fit(
+      screenshot.to_rgb(),
+      3,
+      screenshot.width() as usize,
+      screenshot.height() as usize,
+      self.width,
+      self.height,
+    );
+    let delay = self.delay();
+    self.frames += 1;
+
+    let bounds = match self.previous.is_empty() {
+      true => Some((0, 0, self.width, self.height)),
+      false => changed_bounds(&self.previous, &current, self.width),
+    };
+    match (bounds, &mut self.pending) {
+      (None, Some(pending)) => pending.delay = pending.delay.saturating_add(delay),
+      (None, None) => {}
+      (Some((left, top, right, bottom)), _) => {
+        let mut frame = Frame::from_rgb_speed(
+          (right - left) as u16,
+          (bottom - top) as u16,
+          &crop(&current, self.width, left, top, right, bottom),
+          self.speed,
+        );
+        frame.left = left as u16;
+        frame.top = top as u16;
+        frame.delay = delay;
+        self.write_pending()?;
+        self.pending = Some(frame);
+      }
+    }
+    self.previous = current;
+
+    Ok(match self.is_full() {
+      true => ControlFlow::Break(()),
+      false => ControlFlow::Continue(()),
+    })
+  }
+
+  pub fn finish(mut self) -> Result<W> {
+    self.write_pending()?;
+    match (self.encoder.take(), self.writer.take()) {
+      (Some(encoder), _) => encoder.into_inner().map_err(encoding_error),
+      (None, Some(writer)) => Ok(writer),
+      (None, None) => Err(StreamshotError::Unsupported("gif recorder has no writer")),
+    }
+  }
+
+  fn start(&mut self, width: u32, height: u32) -> Result<()> {
+    let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+      return Err(StreamshotError::InvalidSize {
+        width: width as i32,
+        height: height as i32,
+      });
+    };
+    let writer = self
+      .writer
+      .take()
+      .ok_or(StreamshotError::Unsupported("gif recorder has no writer"))?;
+
+    let mut encoder = Encoder::new(writer, gif_width, gif_height, &[]).map_err(encoding_error)?;
+    encoder
+      .set_repeat(Repeat::Infinite)
+      .map_err(encoding_error)?;
+    self.encoder = Some(encoder);
+    self.width = width as usize;
+    self.height = height as usize;
+    Ok(())
+  }
+
+  fn delay(&self) -> u16 {
+    let centiseconds = |frame: u32| (frame as u64 * 100 + self.fps as u64 / 2) / self.fps as u64;
+    (centiseconds(self.frames + 1) - centiseconds(self.frames)).max(1) as u16
+  }
+
+  fn write_pending(&mut self) -> Result<()> {
+    if let (Some(encoder), Some(pending)) = (&mut self.encoder, self.pending.take()) {
+      encoder.write_frame(&pending).map_err(encoding_error)?;
+    }
+    Ok(())
+  }
+}
+
+impl<W: Write> Drop for GifRecorder<W> {
+  fn drop(&mut self) {
+    let _ = self.write_pending();
+  }
+}
+
+fn changed_bounds(
+  previous: &[u8],
+  current: &[u8],
+  width: usize,
+) -> Option<(usize, usize, usize, usize)> {
+  let mut bounds: Option<(usize, usize, usize, usize)> = None;
+  let rows = previous
+    .chunks_exact(width * 3)
+    .zip(current.chunks_exact(width * 3));
+  for (y, (previous_row, current_row)) in rows.enumerate() {
+    let changed = |x: &usize| previous_row[x * 3..x * 3 + 3] != current_row[x * 3..x * 3 + 3];
+    let Some(left) = (0..width).find(changed) else {
+      continue;
+    };
+    let right = (0..width).rev().find(changed).unwrap_or(left) + 1;
+
+    bounds = Some(match bounds {
+      Some((l, t, r, _)) => (l.min(left), t, r.max(right), y + 1),
+      None => (left, y, right, y + 1),
+    });
+  }
+  bounds
+}
+
+fn crop(rgb: &[u8], width: usize, left: usize, top: usize, right: usize, bottom: usize) -> Vec<u8> {
+  (top..bottom)
+    .flat_map(|y| &rgb[(y * width + left) * 3..(y * width + right) * 3])
+    .copied()
+    .collect()
+}
+
+fn encoding_error(error: EncodingError) -> StreamshotError {
+  StreamshotError::Encoding(error.to_string())
+}
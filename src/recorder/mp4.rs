@@ -0,0 +1,224 @@
+use {
+  super::fit,
+  crate::{wrappers::ComScope, Capture, CaptureSession, Result, Screenshot, StreamshotError, BGRA},
+  std::{ops::ControlFlow, path::PathBuf, ptr, time::Duration},
+  windows::{
+    core::HSTRING,
+    Win32::Media::MediaFoundation::{
+      IMFMediaType, IMFSinkWriter, MFCreateMediaType, MFCreateMemoryBuffer, MFCreateSample,
+      MFCreateSinkWriterFromURL, MFMediaType_Video, MFShutdown, MFStartup, MFVideoFormat_H264,
+      MFVideoFormat_RGB32, MFVideoInterlace_Progressive, MFSTARTUP_FULL, MF_MT_AVG_BITRATE,
+      MF_MT_DEFAULT_STRIDE, MF_MT_FRAME_RATE, MF_MT_FRAME_SIZE, MF_MT_INTERLACE_MODE,
+      MF_MT_MAJOR_TYPE, MF_MT_PIXEL_ASPECT_RATIO, MF_MT_SUBTYPE, MF_VERSION,
+    },
+  },
+};
+
+const DEFAULT_FPS: u32 = 30;
+const DEFAULT_BITRATE: u32 = 8_000_000;
+const TICKS_PER_SECOND: u64 = 10_000_000;
+
+pub struct Mp4Recorder {
+  path: PathBuf,
+  fps: u32,
+  bitrate: u32,
+  writer: Option<SinkWriter>,
+  com: Option<ComScope>,
+}
+
+struct SinkWriter {
+  inner: IMFSinkWriter,
+  stream: u32,
+  width: usize,
+  height: usize,
+  frames: u64,
+}
+
+impl Mp4Recorder {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self {
+      path: path.into(),
+      fps: DEFAULT_FPS,
+      bitrate: DEFAULT_BITRATE,
+      writer: None,
+      com: None,
+    }
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps.max(1);
+    self
+  }
+
+  pub fn with_bitrate(mut self, bitrate: u32) -> Self {
+    self.bitrate = bitrate;
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.writer.is_some()
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    let frames = self.writer.as_ref().map_or(0, |writer| writer.frames);
+    Duration::from_nanos(frames * 1_000_000_000 / self.fps as u64)
+  }
+
+  pub fn start(&mut self, width: u32, height: u32) -> Result<()> {
+    if self.writer.is_some() {
+      return Ok(());
+    }
+
+    let width = width & !1;
+    let height = height & !1;
+    if width == 0 || height == 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: width as i32,
+        height: height as i32,
+      });
+    }
+
+    let com = ComScope::enter();
+    unsafe {
+      MFStartup(MF_VERSION, MFSTARTUP_FULL)?;
+    }
+    let writer = self.create_writer(width, height);
+    if writer.is_err() {
+      unsafe {
+        let _ = MFShutdown();
+      }
+    }
+    self.writer = Some(writer?);
+    self.com = Some(com);
+    Ok(())
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    if self.writer.is_none() {
+      self.start(screenshot.width(), screenshot.height())?;
+    }
+    let Some(writer) = &mut self.writer else {
+      return Ok(());
+    };
+
+    let pixels = fit(
+      screenshot.packed().into_owned(),
+      4,
+      screenshot.width() as usize,
+      screenshot.height() as usize,
+      writer.width,
+      writer.height,
+    );
+    writer.write(&pixels, self.fps)
+  }
+
+  pub fn record_for<C: Capture>(
+    &mut self,
+    session: &mut CaptureSession<C>,
+    duration: Duration,
+  ) -> Result<()> {
+    if self.writer.is_none() {
+      self.fps = session.fps().max(1);
+    }
+
+    let mut result = Ok(());
+    session.run(|screenshot: Screenshot<'_, BGRA>| {
+      if let Err(error) = self.push_frame(&screenshot) {
+        result = Err(error);
+        return ControlFlow::Break(());
+      }
+      match self.elapsed() >= duration {
+        true => ControlFlow::Break(()),
+        false => ControlFlow::Continue(()),
+      }
+    })?;
+    result?;
+    self.stop()
+  }
+
+  pub fn stop(&mut self) -> Result<()> {
+    let Some(writer) = self.writer.take() else {
+      return Ok(());
+    };
+    let result = unsafe { writer.inner.Finalize() };
+    drop(writer);
+    unsafe {
+      let _ = MFShutdown();
+    }
+    self.com = None;
+    Ok(result?)
+  }
+
+  fn create_writer(&self, width: u32, height: u32) -> Result<SinkWriter> {
+    let path = HSTRING::from(&*self.path.to_string_lossy());
+    let frame_size = (width as u64) << 32 | height as u64;
+    let frame_rate = (self.fps as u64) << 32 | 1;
+
+    unsafe {
+      let inner = MFCreateSinkWriterFromURL(&path, None, None)?;
+
+      let output = video_type(frame_size, frame_rate)?;
+      output.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_H264)?;
+      output.SetUINT32(&MF_MT_AVG_BITRATE, self.bitrate)?;
+      let stream = inner.AddStream(&output)?;
+
+      let input = video_type(frame_size, frame_rate)?;
+      input.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_RGB32)?;
+      input.SetUINT32(&MF_MT_DEFAULT_STRIDE, width * 4)?;
+      inner.SetInputMediaType(stream, &input, None)?;
+
+      inner.BeginWriting()?;
+      Ok(SinkWriter {
+        inner,
+        stream,
+        width: width as usize,
+        height: height as usize,
+        frames: 0,
+      })
+    }
+  }
+}
+
+impl Drop for Mp4Recorder {
+  fn drop(&mut self) {
+    let _ = self.stop();
+  }
+}
+
+impl SinkWriter {
+  fn write(&mut self, pixels: &[u8], fps: u32) -> Result<()> {
+    let start = self.frames * TICKS_PER_SECOND / fps as u64;
+    let end = (self.frames + 1) * TICKS_PER_SECOND / fps as u64;
+
+    unsafe {
+      let buffer = MFCreateMemoryBuffer(pixels.len() as u32)?;
+      let mut data = ptr::null_mut();
+      buffer.Lock(&mut data, None, None)?;
+      ptr::copy_nonoverlapping(pixels.as_ptr(), data, pixels.len());
+      buffer.Unlock()?;
+      buffer.SetCurrentLength(pixels.len() as u32)?;
+
+      let sample = MFCreateSample()?;
+      sample.AddBuffer(&buffer)?;
+      sample.SetSampleTime(start as i64)?;
+      sample.SetSampleDuration((end - start) as i64)?;
+      self.inner.WriteSample(self.stream, &sample)?;
+    }
+    self.frames += 1;
+    Ok(())
+  }
+}
+
+unsafe fn video_type(frame_size: u64, frame_rate: u64) -> Result<IMFMediaType> {
+  let media_type = MFCreateMediaType()?;
+  media_type.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+  media_type.SetUINT32(&MF_MT_INTERLACE_MODE, MFVideoInterlace_Progressive.0 as u32)?;
+  media_type.SetUINT64(&MF_MT_FRAME_SIZE, frame_size)?;
+  media_type.SetUINT64(&MF_MT_FRAME_RATE, frame_rate)?;
+  media_type.SetUINT64(&MF_MT_PIXEL_ASPECT_RATIO, 1 << 32 | 1)?;
+  Ok(media_type)
+}
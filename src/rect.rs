@@ -0,0 +1,42 @@
+use windows::Win32::Foundation::RECT;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+  pub left: i32,
+  pub top: i32,
+  pub right: i32,
+  pub bottom: i32,
+}
+
+impl Rect {
+  pub fn width(&self) -> i32 {
+    self.right - self.left
+  }
+
+  pub fn height(&self) -> i32 {
+    self.bottom - self.top
+  }
+}
+
+impl From<RECT> for Rect {
+  fn from(rect: RECT) -> Self {
+    Self {
+      left: rect.left,
+      top: rect.top,
+      right: rect.right,
+      bottom: rect.bottom,
+    }
+  }
+}
+
+impl From<Rect> for RECT {
+  fn from(rect: Rect) -> Self {
+    Self {
+      left: rect.left,
+      top: rect.top,
+      right: rect.right,
+      bottom: rect.bottom,
+    }
+  }
+}
@@ -0,0 +1,46 @@
+use crate::{gdi::read_screen, Capture, FrameBuffer, Result, StreamshotError};
+
+pub struct RegionScreenshotBuffer {
+  x: i32,
+  y: i32,
+  cursor: bool,
+  frame: FrameBuffer,
+}
+
+impl RegionScreenshotBuffer {
+  pub fn new(x: i32, y: i32, width: i32, height: i32) -> Result<Self> {
+    if width <= 0 || height <= 0 {
+      return Err(StreamshotError::InvalidSize { width, height });
+    }
+
+    Ok(Self {
+      x,
+      y,
+      cursor: false,
+      frame: FrameBuffer::new(width, height),
+    })
+  }
+
+  pub fn with_cursor(mut self, cursor: bool) -> Self {
+    self.cursor = cursor;
+    self
+  }
+
+  pub fn x(&self) -> i32 {
+    self.x
+  }
+
+  pub fn y(&self) -> i32 {
+    self.y
+  }
+}
+
+impl Capture for RegionScreenshotBuffer {
+  fn read(&mut self) -> Result<()> {
+    read_screen(self.x, self.y, self.cursor, &mut self.frame)
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+}
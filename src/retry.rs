@@ -0,0 +1,97 @@
+use {
+  crate::{trace::event, Result, StreamshotError},
+  std::{
+    thread,
+    time::{Duration, Instant},
+  },
+};
+
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(5);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+  attempts: u32,
+  backoff: Duration,
+  max_backoff: Duration,
+  timeout: Option<Duration>,
+}
+
+impl RetryPolicy {
+  pub fn new(attempts: u32) -> Self {
+    Self {
+      attempts: attempts.max(1),
+      backoff: DEFAULT_BACKOFF,
+      max_backoff: DEFAULT_MAX_BACKOFF,
+      timeout: None,
+    }
+  }
+
+  pub fn none() -> Self {
+    Self::new(1)
+  }
+
+  pub fn attempts(&self) -> u32 {
+    self.attempts
+  }
+
+  pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+    self.backoff = initial;
+    self.max_backoff = max.max(initial);
+    self
+  }
+
+  pub fn backoff(&self) -> (Duration, Duration) {
+    (self.backoff, self.max_backoff)
+  }
+
+  pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  pub fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+
+  pub(crate) fn run<T>(&self, mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+    let start = Instant::now();
+    let mut backoff = self.backoff;
+    let mut remaining = self.attempts;
+    loop {
+      let error = match attempt() {
+        Err(error) if is_transient(&error) => error,
+        result => return result,
+      };
+      remaining -= 1;
+      let elapsed = start.elapsed();
+      if self
+        .timeout
+        .is_some_and(|timeout| elapsed + backoff >= timeout)
+      {
+        return Err(StreamshotError::Timeout(elapsed));
+      }
+      if remaining == 0 {
+        return Err(error);
+      }
+      event!(debug, %error, ?backoff, remaining, "retrying capture");
+      thread::sleep(backoff);
+      backoff = (backoff * 2).min(self.max_backoff);
+    }
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self::none()
+  }
+}
+
+fn is_transient(error: &StreamshotError) -> bool {
+  matches!(
+    error,
+    StreamshotError::GdiFailure(_)
+      | StreamshotError::InvalidSize { .. }
+      | StreamshotError::Windows(_)
+  )
+}
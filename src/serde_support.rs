@@ -0,0 +1,33 @@
+use {
+  serde::{Deserialize, Deserializer, Serializer},
+  windows::Win32::{Foundation::HWND, Graphics::Gdi::HMONITOR},
+};
+
+pub(crate) mod hwnd {
+  use super::*;
+
+  pub(crate) fn serialize<S: Serializer>(handle: &HWND, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(handle.0 as i64)
+  }
+
+  pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HWND, D::Error> {
+    Ok(HWND(i64::deserialize(deserializer)? as isize))
+  }
+}
+
+pub(crate) mod hmonitor {
+  use super::*;
+
+  pub(crate) fn serialize<S: Serializer>(
+    handle: &HMONITOR,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(handle.0 as i64)
+  }
+
+  pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<HMONITOR, D::Error> {
+    Ok(HMONITOR(i64::deserialize(deserializer)? as isize))
+  }
+}
@@ -0,0 +1,19 @@
+#[cfg(any(feature = "http-stream", feature = "rtsp", feature = "ws"))]
+mod accept;
+#[cfg(any(feature = "http-stream", feature = "ws"))]
+mod frames;
+#[cfg(feature = "rtsp")]
+mod h264;
+#[cfg(feature = "http-stream")]
+mod mjpeg;
+#[cfg(feature = "rtsp")]
+mod rtsp;
+#[cfg(feature = "ws")]
+mod websocket;
+
+#[cfg(feature = "http-stream")]
+pub use self::mjpeg::MjpegServer;
+#[cfg(feature = "rtsp")]
+pub use self::rtsp::RtspServer;
+#[cfg(feature = "ws")]
+pub use self::websocket::{FrameEncoding, WebSocketServer};
@@ -0,0 +1,56 @@
+use std::{
+  net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+const WAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(super) struct Acceptor {
+  addr: Option<SocketAddr>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Acceptor {
+  pub(super) fn spawn(
+    listener: TcpListener,
+    closed: impl Fn() -> bool + Send + 'static,
+    mut accept: impl FnMut(TcpStream) + Send + 'static,
+  ) -> Self {
+    let addr = listener.local_addr().ok().map(wake_addr);
+    let thread = thread::spawn(move || {
+      for stream in listener.incoming() {
+        if closed() {
+          return;
+        }
+        if let Ok(stream) = stream {
+          accept(stream);
+        }
+      }
+    });
+    Self {
+      addr,
+      thread: Some(thread),
+    }
+  }
+}
+
+impl Drop for Acceptor {
+  fn drop(&mut self) {
+    let woken = self
+      .addr
+      .is_some_and(|addr| TcpStream::connect_timeout(&addr, WAKE_TIMEOUT).is_ok());
+    if let Some(thread) = self.thread.take().filter(|_| woken) {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn wake_addr(mut addr: SocketAddr) -> SocketAddr {
+  match addr.ip() {
+    IpAddr::V4(ip) if ip.is_unspecified() => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+    IpAddr::V6(ip) if ip.is_unspecified() => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+    _ => {}
+  }
+  addr
+}
@@ -0,0 +1,74 @@
+use std::{
+  ops::Not,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Condvar, Mutex, PoisonError,
+  },
+};
+
+#[derive(Default)]
+pub(super) struct EncodedFrames {
+  latest: Mutex<LatestEncoded>,
+  arrived: Condvar,
+  clients: AtomicUsize,
+}
+
+#[derive(Default)]
+struct LatestEncoded {
+  sequence: u64,
+  data: Arc<Vec<u8>>,
+  closed: bool,
+}
+
+impl EncodedFrames {
+  pub(super) fn publish(&self, data: Vec<u8>) {
+    let mut latest = self.latest.lock().unwrap_or_else(PoisonError::into_inner);
+    latest.sequence += 1;
+    latest.data = Arc::new(data);
+    self.arrived.notify_all();
+  }
+
+  pub(super) fn next(&self, sequence: &mut u64) -> Option<Arc<Vec<u8>>> {
+    let mut latest = self.latest.lock().unwrap_or_else(PoisonError::into_inner);
+    while latest.sequence == *sequence && latest.closed.not() {
+      latest = self
+        .arrived
+        .wait(latest)
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+    if latest.closed {
+      return None;
+    }
+    *sequence = latest.sequence;
+    Some(latest.data.clone())
+  }
+
+  pub(super) fn close(&self) {
+    self
+      .latest
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .closed = true;
+    self.arrived.notify_all();
+  }
+
+  pub(super) fn is_closed(&self) -> bool {
+    self
+      .latest
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .closed
+  }
+
+  pub(super) fn has_clients(&self) -> bool {
+    self.clients.load(Ordering::Relaxed) > 0
+  }
+
+  pub(super) fn connect(&self) {
+    self.clients.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub(super) fn disconnect(&self) {
+    self.clients.fetch_sub(1, Ordering::Relaxed);
+  }
+}
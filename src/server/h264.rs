@@ -0,0 +1,187 @@
+use {
+  crate::{wrappers::ComScope, Result},
+  std::{mem::ManuallyDrop, ptr},
+  windows::{
+    core::ComInterface,
+    Win32::{
+      Media::MediaFoundation::{
+        eAVEncH264VProfile_Base, CLSID_MSH264EncoderMFT, CODECAPI_AVEncMPVGOPSize,
+        CODECAPI_AVEncVideoForceKeyFrame, CODECAPI_AVLowLatencyMode, ICodecAPI, IMFMediaType,
+        IMFSample, IMFTransform, MFCreateMediaType, MFCreateMemoryBuffer, MFCreateSample,
+        MFMediaType_Video, MFShutdown, MFStartup, MFVideoFormat_H264, MFVideoFormat_NV12,
+        MFVideoInterlace_Progressive, MFSTARTUP_FULL, MFT_MESSAGE_NOTIFY_BEGIN_STREAMING,
+        MFT_MESSAGE_NOTIFY_START_OF_STREAM, MFT_OUTPUT_DATA_BUFFER, MF_E_TRANSFORM_NEED_MORE_INPUT,
+        MF_MT_AVG_BITRATE, MF_MT_FRAME_RATE, MF_MT_FRAME_SIZE, MF_MT_INTERLACE_MODE,
+        MF_MT_MAJOR_TYPE, MF_MT_MPEG2_PROFILE, MF_MT_PIXEL_ASPECT_RATIO, MF_MT_SUBTYPE, MF_VERSION,
+      },
+      System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER, VARIANT, VT_BOOL, VT_UI4},
+    },
+  },
+};
+
+const TICKS_PER_SECOND: u64 = 10_000_000;
+
+pub(super) struct H264Encoder {
+  transform: IMFTransform,
+  codec: Option<ICodecAPI>,
+  fps: u32,
+  output_size: u32,
+  frames: u64,
+  _com: ComScope,
+}
+
+impl H264Encoder {
+  pub(super) fn new(width: u32, height: u32, fps: u32, bitrate: u32) -> Result<Self> {
+    let com = ComScope::enter();
+    unsafe {
+      MFStartup(MF_VERSION, MFSTARTUP_FULL)?;
+    }
+    let encoder = Self::create(width, height, fps.max(1), bitrate, com);
+    if encoder.is_err() {
+      unsafe {
+        let _ = MFShutdown();
+      }
+    }
+    encoder
+  }
+
+  fn create(width: u32, height: u32, fps: u32, bitrate: u32, com: ComScope) -> Result<Self> {
+    unsafe {
+      let transform: IMFTransform =
+        CoCreateInstance(&CLSID_MSH264EncoderMFT, None, CLSCTX_INPROC_SERVER)?;
+
+      let output = video_type(width, height, fps)?;
+      output.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_H264)?;
+      output.SetUINT32(&MF_MT_AVG_BITRATE, bitrate)?;
+      output.SetUINT32(&MF_MT_MPEG2_PROFILE, eAVEncH264VProfile_Base.0 as u32)?;
+      transform.SetOutputType(0, &output, 0)?;
+
+      let input = video_type(width, height, fps)?;
+      input.SetGUID(&MF_MT_SUBTYPE, &MFVideoFormat_NV12)?;
+      transform.SetInputType(0, &input, 0)?;
+
+      let codec = transform.cast::<ICodecAPI>().ok();
+      if let Some(codec) = &codec {
+        let _ = codec.SetValue(&CODECAPI_AVLowLatencyMode, &variant_bool(true));
+        let _ = codec.SetValue(&CODECAPI_AVEncMPVGOPSize, &variant_u32(2 * fps));
+      }
+
+      transform.ProcessMessage(MFT_MESSAGE_NOTIFY_BEGIN_STREAMING, 0)?;
+      transform.ProcessMessage(MFT_MESSAGE_NOTIFY_START_OF_STREAM, 0)?;
+      let output_size = transform.GetOutputStreamInfo(0)?.cbSize;
+
+      Ok(Self {
+        transform,
+        codec,
+        fps,
+        output_size,
+        frames: 0,
+        _com: com,
+      })
+    }
+  }
+
+  pub(super) fn force_key_frame(&self) {
+    if let Some(codec) = &self.codec {
+      unsafe {
+        let _ = codec.SetValue(&CODECAPI_AVEncVideoForceKeyFrame, &variant_u32(1));
+      }
+    }
+  }
+
+  pub(super) fn encode(&mut self, nv12: &[u8]) -> Result<Vec<u8>> {
+    let duration = (TICKS_PER_SECOND / self.fps as u64) as i64;
+    unsafe {
+      let sample = sample_with_buffer(nv12.len() as u32)?;
+      let buffer = sample.GetBufferByIndex(0)?;
+      let mut data = ptr::null_mut();
+      buffer.Lock(&mut data, None, None)?;
+      ptr::copy_nonoverlapping(nv12.as_ptr(), data, nv12.len());
+      buffer.Unlock()?;
+      buffer.SetCurrentLength(nv12.len() as u32)?;
+      sample.SetSampleTime(self.frames as i64 * duration)?;
+      sample.SetSampleDuration(duration)?;
+      self.transform.ProcessInput(0, &sample, 0)?;
+    }
+    self.frames += 1;
+
+    let mut encoded = Vec::new();
+    while let Some(sample) = self.next_output()? {
+      unsafe {
+        let buffer = sample.ConvertToContiguousBuffer()?;
+        let mut data = ptr::null_mut();
+        let mut len = 0;
+        buffer.Lock(&mut data, None, Some(&mut len))?;
+        encoded.extend_from_slice(std::slice::from_raw_parts(data, len as usize));
+        buffer.Unlock()?;
+      }
+    }
+    Ok(encoded)
+  }
+
+  fn next_output(&self) -> Result<Option<IMFSample>> {
+    unsafe {
+      let sample = sample_with_buffer(self.output_size)?;
+      let mut buffers = [MFT_OUTPUT_DATA_BUFFER {
+        dwStreamID: 0,
+        pSample: ManuallyDrop::new(Some(sample)),
+        dwStatus: 0,
+        pEvents: ManuallyDrop::new(None),
+      }];
+      let mut status = 0;
+      let result = self.transform.ProcessOutput(0, &mut buffers, &mut status);
+      let [mut buffer] = buffers;
+      let sample = ManuallyDrop::take(&mut buffer.pSample);
+      ManuallyDrop::drop(&mut buffer.pEvents);
+      match result {
+        Ok(()) => Ok(sample),
+        Err(error) if error.code() == MF_E_TRANSFORM_NEED_MORE_INPUT => Ok(None),
+        Err(error) => Err(error.into()),
+      }
+    }
+  }
+}
+
+impl Drop for H264Encoder {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = MFShutdown();
+    }
+  }
+}
+
+unsafe fn video_type(width: u32, height: u32, fps: u32) -> Result<IMFMediaType> {
+  let media_type = MFCreateMediaType()?;
+  media_type.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+  media_type.SetUINT64(&MF_MT_FRAME_SIZE, (width as u64) << 32 | height as u64)?;
+  media_type.SetUINT64(&MF_MT_FRAME_RATE, (fps as u64) << 32 | 1)?;
+  media_type.SetUINT64(&MF_MT_PIXEL_ASPECT_RATIO, 1 << 32 | 1)?;
+  media_type.SetUINT32(&MF_MT_INTERLACE_MODE, MFVideoInterlace_Progressive.0 as u32)?;
+  Ok(media_type)
+}
+
+unsafe fn sample_with_buffer(len: u32) -> Result<IMFSample> {
+  let sample = MFCreateSample()?;
+  sample.AddBuffer(&MFCreateMemoryBuffer(len)?)?;
+  Ok(sample)
+}
+
+fn variant_u32(value: u32) -> VARIANT {
+  let mut variant = VARIANT::default();
+  unsafe {
+    let inner = &mut *variant.Anonymous.Anonymous;
+    inner.vt = VT_UI4;
+    inner.Anonymous.ulVal = value;
+  }
+  variant
+}
+
+fn variant_bool(value: bool) -> VARIANT {
+  let mut variant = VARIANT::default();
+  unsafe {
+    let inner = &mut *variant.Anonymous.Anonymous;
+    inner.vt = VT_BOOL;
+    inner.Anonymous.boolVal.0 = if value { -1 } else { 0 };
+  }
+  variant
+}
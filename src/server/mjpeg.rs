@@ -0,0 +1,105 @@
+use {
+  super::{accept::Acceptor, frames::EncodedFrames},
+  crate::{Capture, CaptureSession, Result, Screenshot, BGRA},
+  std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::ControlFlow,
+    sync::Arc,
+    thread,
+  },
+};
+
+const DEFAULT_QUALITY: u8 = 75;
+const BOUNDARY: &str = "streamshot-frame";
+
+pub struct MjpegServer {
+  listener: TcpListener,
+  quality: u8,
+}
+
+impl MjpegServer {
+  pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+    Ok(Self {
+      listener: TcpListener::bind(addr)?,
+      quality: DEFAULT_QUALITY,
+    })
+  }
+
+  pub fn serve<C: Capture>(addr: impl ToSocketAddrs, session: CaptureSession<C>) -> Result<()> {
+    Self::bind(addr)?.run(session)
+  }
+
+  pub fn with_quality(mut self, quality: u8) -> Self {
+    self.quality = quality;
+    self
+  }
+
+  pub fn quality(&self) -> u8 {
+    self.quality
+  }
+
+  pub fn local_addr(&self) -> Result<SocketAddr> {
+    Ok(self.listener.local_addr()?)
+  }
+
+  pub fn run<C: Capture>(self, mut session: CaptureSession<C>) -> Result<()> {
+    let frames = Arc::new(EncodedFrames::default());
+    let accepted = frames.clone();
+    let closed = frames.clone();
+    let acceptor = Acceptor::spawn(
+      self.listener,
+      move || closed.is_closed(),
+      move |stream| {
+        let frames = accepted.clone();
+        thread::spawn(move || {
+          frames.connect();
+          let _ = stream_client(stream, &frames);
+          frames.disconnect();
+        });
+      },
+    );
+
+    let mut encoded = Ok(());
+    let result = session.run(|screenshot: Screenshot<'_, BGRA>| {
+      if frames.has_clients() {
+        match screenshot.encode_jpeg(self.quality) {
+          Ok(jpeg) => frames.publish(jpeg),
+          Err(error) => {
+            encoded = Err(error);
+            return ControlFlow::Break(());
+          }
+        }
+      }
+      ControlFlow::Continue(())
+    });
+    frames.close();
+    drop(acceptor);
+    result.and(encoded)
+  }
+}
+
+fn stream_client(mut stream: TcpStream, frames: &EncodedFrames) -> Result<()> {
+  let mut request = [0; 1024];
+  let _ = stream.read(&mut request)?;
+  write!(
+    stream,
+    "HTTP/1.1 200 OK\r\n\
+     Content-Type: multipart/x-mixed-replace; boundary={BOUNDARY}\r\n\
+     Cache-Control: no-cache\r\n\
+     Connection: close\r\n\r\n"
+  )?;
+
+  let mut sequence = 0;
+  while let Some(jpeg) = frames.next(&mut sequence) {
+    write!(
+      stream,
+      "--{BOUNDARY}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+      jpeg.len()
+    )?;
+    stream.write_all(&jpeg)?;
+    stream.write_all(b"\r\n")?;
+    stream.flush()?;
+  }
+  Ok(())
+}
@@ -0,0 +1,619 @@
+use {
+  super::{accept::Acceptor, h264::H264Encoder},
+  crate::{
+    recorder::fit, Capture, CaptureSession, Result, Screenshot, YuvFormat, YuvFrame, YuvMatrix,
+    BGRA,
+  },
+  std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::{ControlFlow, Not},
+    sync::{
+      atomic::{AtomicBool, Ordering},
+      mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+      Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+  },
+};
+
+const DEFAULT_BITRATE: u32 = 4_000_000;
+const CLIENT_QUEUE: usize = 8;
+const RTP_PAYLOAD_TYPE: u8 = 96;
+const RTP_CLOCK_RATE: f64 = 90_000.0;
+const MAX_RTP_PAYLOAD: usize = 1400;
+const NAL_FU_A: u8 = 28;
+const NAL_IDR: u8 = 5;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct RtspServer {
+  listener: TcpListener,
+  bitrate: u32,
+}
+
+impl RtspServer {
+  pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+    Ok(Self {
+      listener: TcpListener::bind(addr)?,
+      bitrate: DEFAULT_BITRATE,
+    })
+  }
+
+  pub fn serve<C: Capture>(addr: impl ToSocketAddrs, session: CaptureSession<C>) -> Result<()> {
+    Self::bind(addr)?.run(session)
+  }
+
+  pub fn with_bitrate(mut self, bitrate: u32) -> Self {
+    self.bitrate = bitrate;
+    self
+  }
+
+  pub fn bitrate(&self) -> u32 {
+    self.bitrate
+  }
+
+  pub fn local_addr(&self) -> Result<SocketAddr> {
+    Ok(self.listener.local_addr()?)
+  }
+
+  pub fn run<C: Capture>(self, mut session: CaptureSession<C>) -> Result<()> {
+    let clients = Arc::new(Clients::default());
+    let accepted = clients.clone();
+    let closed = clients.clone();
+    let acceptor = Acceptor::spawn(
+      self.listener,
+      move || closed.closed.load(Ordering::Relaxed),
+      move |stream| {
+        let clients = accepted.clone();
+        thread::spawn(move || {
+          let _ = handle_client(stream, &clients);
+        });
+      },
+    );
+
+    let fps = session.fps().max(1);
+    let start = Instant::now();
+    let mut encoder: Option<(H264Encoder, u32, u32)> = None;
+    let mut encoded = Ok(());
+    let result = session.run(|screenshot: Screenshot<'_, BGRA>| {
+      if clients.is_empty() {
+        return ControlFlow::Continue(());
+      }
+      let unit = encode(&mut encoder, &screenshot, fps, self.bitrate, &clients).map(|data| {
+        let elapsed = screenshot
+          .meta()
+          .captured_at()
+          .saturating_duration_since(start);
+        AccessUnit::new(data, (elapsed.as_secs_f64() * RTP_CLOCK_RATE) as u64 as u32)
+      });
+      match unit {
+        Ok(unit) => clients.publish(Arc::new(unit)),
+        Err(error) => {
+          encoded = Err(error);
+          return ControlFlow::Break(());
+        }
+      }
+      ControlFlow::Continue(())
+    });
+    clients.closed.store(true, Ordering::Relaxed);
+    drop(acceptor);
+    result.and(encoded)
+  }
+}
+
+fn encode(
+  encoder: &mut Option<(H264Encoder, u32, u32)>,
+  screenshot: &Screenshot<'_, BGRA>,
+  fps: u32,
+  bitrate: u32,
+  clients: &Clients,
+) -> Result<Vec<u8>> {
+  let width = screenshot.width() & !1;
+  let height = screenshot.height() & !1;
+  let encoder = match encoder {
+    Some((encoder, w, h)) if *w == width && *h == height => encoder,
+    _ => {
+      &mut encoder
+        .insert((
+          H264Encoder::new(width, height, fps, bitrate)?,
+          width,
+          height,
+        ))
+        .0
+    }
+  };
+  if clients.key_frame_requested.swap(false, Ordering::Relaxed) {
+    encoder.force_key_frame();
+  }
+
+  let nv12 = match width == screenshot.width() && height == screenshot.height() {
+    true => YuvFrame::from_screenshot(screenshot, YuvFormat::Nv12, YuvMatrix::Bt601),
+    false => {
+      let image = fit(
+        screenshot.packed().into_owned(),
+        4,
+        screenshot.width() as usize,
+        screenshot.height() as usize,
+        width as usize,
+        height as usize,
+      );
+      let even = Screenshot {
+        width,
+        height,
+        stride: 4 * width,
+        image: &image,
+        meta: screenshot.meta(),
+        marker: PhantomData,
+      };
+      YuvFrame::from_screenshot(&even, YuvFormat::Nv12, YuvMatrix::Bt601)
+    }
+  };
+  encoder.encode(nv12.data())
+}
+
+struct AccessUnit {
+  nal_units: Vec<Vec<u8>>,
+  timestamp: u32,
+  key: bool,
+}
+
+impl AccessUnit {
+  fn new(annex_b: Vec<u8>, timestamp: u32) -> Self {
+    let nal_units: Vec<Vec<u8>> = split_nal_units(&annex_b)
+      .into_iter()
+      .map(<[u8]>::to_vec)
+      .collect();
+    let key = nal_units.iter().any(|nal| nal[0] & 0x1F == NAL_IDR);
+    Self {
+      nal_units,
+      timestamp,
+      key,
+    }
+  }
+}
+
+fn split_nal_units(data: &[u8]) -> Vec<&[u8]> {
+  let mut starts = Vec::new();
+  let mut index = 0;
+  while index + 3 <= data.len() {
+    if data[index..index + 3] == [0, 0, 1] {
+      starts.push(index + 3);
+      index += 3;
+    } else {
+      index += 1;
+    }
+  }
+
+  let mut units = Vec::with_capacity(starts.len());
+  for (position, &start) in starts.iter().enumerate() {
+    let mut end = starts.get(position + 1).map_or(data.len(), |next| next - 3);
+    while end > start && data[end - 1] == 0 {
+      end -= 1;
+    }
+    if end > start {
+      units.push(&data[start..end]);
+    }
+  }
+  units
+}
+
+struct Subscriber {
+  sender: SyncSender<Arc<AccessUnit>>,
+  waiting_for_key: bool,
+}
+
+#[derive(Default)]
+struct Clients {
+  subscribers: Mutex<Vec<Subscriber>>,
+  key_frame_requested: AtomicBool,
+  closed: AtomicBool,
+}
+
+impl Clients {
+  fn subscribe(&self) -> Receiver<Arc<AccessUnit>> {
+    let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE);
+    lock(&self.subscribers).push(Subscriber {
+      sender,
+      waiting_for_key: true,
+    });
+    self.key_frame_requested.store(true, Ordering::Relaxed);
+    receiver
+  }
+
+  fn is_empty(&self) -> bool {
+    lock(&self.subscribers).is_empty()
+  }
+
+  fn publish(&self, unit: Arc<AccessUnit>) {
+    let mut request_key_frame = false;
+    lock(&self.subscribers).retain_mut(|subscriber| {
+      if subscriber.waiting_for_key && unit.key.not() {
+        return true;
+      }
+      match subscriber.sender.try_send(unit.clone()) {
+        Ok(()) => {
+          subscriber.waiting_for_key = false;
+          true
+        }
+        Err(TrySendError::Full(_)) => {
+          subscriber.waiting_for_key = true;
+          request_key_frame = true;
+          true
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+      }
+    });
+    if request_key_frame {
+      self.key_frame_requested.store(true, Ordering::Relaxed);
+    }
+  }
+}
+
+struct Request {
+  method: String,
+  uri: String,
+  cseq: String,
+  transport: Option<String>,
+}
+
+fn handle_client(stream: TcpStream, clients: &Clients) -> Result<()> {
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let writer = Arc::new(Mutex::new(stream));
+  let session = format!("{:08X}", random_u32());
+
+  while let Some(request) = read_request(&mut reader)? {
+    let mut stream = lock(&writer);
+    match request.method.as_str() {
+      "OPTIONS" => respond(
+        &mut *stream,
+        &request,
+        "200 OK",
+        &["Public: OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN".to_owned()],
+        "",
+      )?,
+      "DESCRIBE" => respond(
+        &mut *stream,
+        &request,
+        "200 OK",
+        &[
+          format!("Content-Base: {}/", request.uri.trim_end_matches('/')),
+          "Content-Type: application/sdp".to_owned(),
+        ],
+        SDP,
+      )?,
+      "SETUP" => match request.transport.as_deref() {
+        Some(transport) if transport.contains("TCP") => respond(
+          &mut *stream,
+          &request,
+          "200 OK",
+          &[
+            "Transport: RTP/AVP/TCP;unicast;interleaved=0-1".to_owned(),
+            format!("Session: {session}"),
+          ],
+          "",
+        )?,
+        _ => respond(&mut *stream, &request, "461 Unsupported Transport", &[], "")?,
+      },
+      "PLAY" => {
+        respond(
+          &mut *stream,
+          &request,
+          "200 OK",
+          &[format!("Session: {session}")],
+          "",
+        )?;
+        drop(stream);
+        return play(reader, writer, clients, &session);
+      }
+      "TEARDOWN" => {
+        respond(
+          &mut *stream,
+          &request,
+          "200 OK",
+          &[format!("Session: {session}")],
+          "",
+        )?;
+        return Ok(());
+      }
+      _ => respond(&mut *stream, &request, "405 Method Not Allowed", &[], "")?,
+    }
+  }
+  Ok(())
+}
+
+const SDP: &str = "v=0\r\n\
+  o=- 0 0 IN IP4 0.0.0.0\r\n\
+  s=win-streamshot\r\n\
+  t=0 0\r\n\
+  m=video 0 RTP/AVP 96\r\n\
+  c=IN IP4 0.0.0.0\r\n\
+  a=rtpmap:96 H264/90000\r\n\
+  a=fmtp:96 packetization-mode=1\r\n\
+  a=control:track0\r\n";
+
+fn play(
+  mut reader: BufReader<TcpStream>,
+  writer: Arc<Mutex<TcpStream>>,
+  clients: &Clients,
+  session: &str,
+) -> Result<()> {
+  let receiver = clients.subscribe();
+  let stopped = Arc::new(AtomicBool::new(false));
+  let control = {
+    let stopped = stopped.clone();
+    let writer = writer.clone();
+    let headers = [format!("Session: {session}")];
+    thread::spawn(move || {
+      while let Ok(Some(request)) = read_request(&mut reader) {
+        let _ = respond(&mut *lock(&writer), &request, "200 OK", &headers, "");
+        if request.method == "TEARDOWN" {
+          break;
+        }
+      }
+      stopped.store(true, Ordering::Relaxed);
+    })
+  };
+
+  let mut packetizer = RtpPacketizer::new();
+  let result = loop {
+    if stopped.load(Ordering::Relaxed) || clients.closed.load(Ordering::Relaxed) {
+      break Ok(());
+    }
+    match receiver.recv_timeout(POLL_INTERVAL) {
+      Ok(unit) => {
+        if let Err(error) = packetizer.send(&unit, &mut *lock(&writer)) {
+          break Err(error.into());
+        }
+      }
+      Err(RecvTimeoutError::Timeout) => continue,
+      Err(RecvTimeoutError::Disconnected) => break Ok(()),
+    }
+  };
+  let _ = lock(&writer).shutdown(Shutdown::Both);
+  let _ = control.join();
+  result
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+  let mut line = String::new();
+  loop {
+    let buffered = reader.fill_buf()?;
+    if buffered.is_empty() {
+      return Ok(None);
+    }
+    if buffered[0] == b'$' {
+      let mut header = [0; 4];
+      reader.read_exact(&mut header)?;
+      let length = u16::from_be_bytes([header[2], header[3]]);
+      io::copy(
+        &mut Read::take(&mut *reader, length as u64),
+        &mut io::sink(),
+      )?;
+      continue;
+    }
+    line.clear();
+    if reader.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    if line.trim().is_empty().not() {
+      break;
+    }
+  }
+
+  let mut parts = line.split_whitespace();
+  let mut request = Request {
+    method: parts.next().unwrap_or_default().to_owned(),
+    uri: parts.next().unwrap_or_default().to_owned(),
+    cseq: String::new(),
+    transport: None,
+  };
+  let mut content_length = 0;
+  loop {
+    line.clear();
+    if reader.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    let header = line.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = header.split_once(':') {
+      let value = value.trim();
+      match name.trim().to_ascii_lowercase().as_str() {
+        "cseq" => request.cseq = value.to_owned(),
+        "transport" => request.transport = Some(value.to_owned()),
+        "content-length" => content_length = value.parse().unwrap_or(0),
+        _ => {}
+      }
+    }
+  }
+  io::copy(
+    &mut Read::take(&mut *reader, content_length),
+    &mut io::sink(),
+  )?;
+  Ok(Some(request))
+}
+
+fn respond(
+  writer: &mut impl Write,
+  request: &Request,
+  status: &str,
+  headers: &[String],
+  body: &str,
+) -> io::Result<()> {
+  let mut response = format!("RTSP/1.0 {status}\r\nCSeq: {}\r\n", request.cseq);
+  for header in headers {
+    response.push_str(header);
+    response.push_str("\r\n");
+  }
+  if body.is_empty().not() {
+    response.push_str(&format!("Content-Length: {}\r\n", body.len()));
+  }
+  response.push_str("\r\n");
+  response.push_str(body);
+  writer.write_all(response.as_bytes())
+}
+
+struct RtpPacketizer {
+  sequence: u16,
+  ssrc: u32,
+  packet: Vec<u8>,
+}
+
+impl RtpPacketizer {
+  fn new() -> Self {
+    Self {
+      sequence: random_u32() as u16,
+      ssrc: random_u32(),
+      packet: Vec::with_capacity(MAX_RTP_PAYLOAD + 16),
+    }
+  }
+
+  fn send(&mut self, unit: &AccessUnit, writer: &mut impl Write) -> io::Result<()> {
+    for (index, nal) in unit.nal_units.iter().enumerate() {
+      let last_nal = index + 1 == unit.nal_units.len();
+      if nal.len() <= MAX_RTP_PAYLOAD {
+        self.write_packet(writer, unit.timestamp, last_nal, &[], nal)?;
+        continue;
+      }
+
+      let indicator = nal[0] & 0xE0 | NAL_FU_A;
+      let nal_type = nal[0] & 0x1F;
+      let fragments = nal[1..].chunks(MAX_RTP_PAYLOAD - 2);
+      let count = fragments.len();
+      for (position, fragment) in fragments.enumerate() {
+        let start = position == 0;
+        let end = position + 1 == count;
+        let header = nal_type | if start { 0x80 } else { 0 } | if end { 0x40 } else { 0 };
+        self.write_packet(
+          writer,
+          unit.timestamp,
+          last_nal && end,
+          &[indicator, header],
+          fragment,
+        )?;
+      }
+    }
+    writer.flush()
+  }
+
+  fn write_packet(
+    &mut self,
+    writer: &mut impl Write,
+    timestamp: u32,
+    marker: bool,
+    prefix: &[u8],
+    payload: &[u8],
+  ) -> io::Result<()> {
+    let length = (12 + prefix.len() + payload.len()) as u16;
+    let marker = if marker { 0x80 } else { 0 };
+    self.packet.clear();
+    self.packet.extend_from_slice(&[b'$', 0]);
+    self.packet.extend_from_slice(&length.to_be_bytes());
+    self
+      .packet
+      .extend_from_slice(&[0x80, RTP_PAYLOAD_TYPE | marker]);
+    self.packet.extend_from_slice(&self.sequence.to_be_bytes());
+    self.packet.extend_from_slice(&timestamp.to_be_bytes());
+    self.packet.extend_from_slice(&self.ssrc.to_be_bytes());
+    self.packet.extend_from_slice(prefix);
+    self.packet.extend_from_slice(payload);
+    self.sequence = self.sequence.wrapping_add(1);
+    writer.write_all(&self.packet)
+  }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn random_u32() -> u32 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |elapsed| {
+      elapsed.subsec_nanos() ^ elapsed.as_secs() as u32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn packets(stream: &[u8]) -> Vec<&[u8]> {
+    let mut packets = Vec::new();
+    let mut rest = stream;
+    while rest.is_empty().not() {
+      assert_eq!(&rest[..2], [b'$', 0]);
+      let length = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+      packets.push(&rest[4..4 + length]);
+      rest = &rest[4 + length..];
+    }
+    packets
+  }
+
+  #[test]
+  fn splits_annex_b_into_nal_units() {
+    let data = [
+      0, 0, 0, 1, 0x67, 0xAA, 0, 0, 1, 0x68, 0xBB, 0, 0, 0, 1, 0x65, 0xCC, 0xDD, 0,
+    ];
+    assert_eq!(
+      split_nal_units(&data),
+      [&[0x67, 0xAA][..], &[0x68, 0xBB], &[0x65, 0xCC, 0xDD]]
+    );
+    assert!(split_nal_units(&[0x65, 1, 2]).is_empty());
+    assert!(split_nal_units(&[0, 0, 1, 0, 0, 1]).is_empty());
+  }
+
+  #[test]
+  fn detects_key_frames() {
+    assert!(AccessUnit::new(vec![0, 0, 1, 0x67, 1, 0, 0, 1, 0x65, 2], 0).key);
+    assert!(AccessUnit::new(vec![0, 0, 1, 0x41, 1], 0).key.not());
+  }
+
+  #[test]
+  fn sends_small_nal_units_in_single_packets() {
+    let unit = AccessUnit::new(vec![0, 0, 1, 0x67, 1, 2, 0, 0, 1, 0x65, 3], 1234);
+    let mut packetizer = RtpPacketizer::new();
+    let mut stream = Vec::new();
+    packetizer.send(&unit, &mut stream).unwrap();
+
+    let packets = packets(&stream);
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0][1], RTP_PAYLOAD_TYPE);
+    assert_eq!(packets[1][1], RTP_PAYLOAD_TYPE | 0x80);
+    assert_eq!(&packets[0][4..8], 1234u32.to_be_bytes());
+    assert_eq!(&packets[0][12..], [0x67, 1, 2]);
+    assert_eq!(&packets[1][12..], [0x65, 3]);
+    let sequence = |packet: &[u8]| u16::from_be_bytes([packet[2], packet[3]]);
+    assert_eq!(sequence(packets[1]), sequence(packets[0]).wrapping_add(1));
+  }
+
+  #[test]
+  fn fragments_large_nal_units_as_fu_a() {
+    let nal: Vec<u8> = [0x65]
+      .into_iter()
+      .chain((0..3000).map(|value| (value % 251) as u8 + 1))
+      .collect();
+    let unit = AccessUnit::new([&[0, 0, 1][..], &nal].concat(), 0);
+    let mut packetizer = RtpPacketizer::new();
+    let mut stream = Vec::new();
+    packetizer.send(&unit, &mut stream).unwrap();
+
+    let packets = packets(&stream);
+    assert_eq!(packets.len(), 3);
+    let mut payload = Vec::new();
+    for (index, packet) in packets.iter().enumerate() {
+      let last = index + 1 == packets.len();
+      assert!(packet.len() - 12 <= MAX_RTP_PAYLOAD);
+      assert_eq!(packet[1] & 0x80 != 0, last);
+      assert_eq!(packet[12], 0x60 | NAL_FU_A);
+      assert_eq!(packet[13] & 0x80 != 0, index == 0);
+      assert_eq!(packet[13] & 0x40 != 0, last);
+      assert_eq!(packet[13] & 0x1F, NAL_IDR);
+      payload.extend_from_slice(&packet[14..]);
+    }
+    assert_eq!(payload, nal[1..]);
+  }
+}
@@ -0,0 +1,189 @@
+use {
+  super::{accept::Acceptor, frames::EncodedFrames},
+  crate::{Capture, CaptureSession, Result, Screenshot, BGRA},
+  std::{
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    ops::ControlFlow,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+  },
+  tungstenite::{
+    handshake::server::{Callback, ErrorResponse, Request, Response},
+    Message,
+  },
+};
+
+const DEFAULT_CLIENT_FPS: u32 = 30;
+const HEADER_LEN: usize = 28;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameEncoding {
+  Raw,
+  Jpeg(u8),
+  Png,
+}
+
+impl FrameEncoding {
+  fn tag(self) -> u8 {
+    match self {
+      FrameEncoding::Raw => 0,
+      FrameEncoding::Jpeg(_) => 1,
+      FrameEncoding::Png => 2,
+    }
+  }
+
+  fn encode(self, screenshot: &Screenshot<'_, BGRA>) -> Result<Vec<u8>> {
+    let meta = screenshot.meta();
+    let timestamp = meta
+      .wall_clock()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_micros() as u64;
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + screenshot.image.len());
+    frame.extend_from_slice(&[self.tag(), 0, 0, 0]);
+    frame.extend_from_slice(&screenshot.width().to_le_bytes());
+    frame.extend_from_slice(&screenshot.height().to_le_bytes());
+    frame.extend_from_slice(&meta.seq().to_le_bytes());
+    frame.extend_from_slice(&timestamp.to_le_bytes());
+    match self {
+      FrameEncoding::Raw => frame.extend_from_slice(&screenshot.packed()),
+      FrameEncoding::Jpeg(quality) => frame.extend(screenshot.encode_jpeg(quality)?),
+      FrameEncoding::Png => frame.extend(screenshot.encode_png()?),
+    }
+    Ok(frame)
+  }
+}
+
+impl Default for FrameEncoding {
+  fn default() -> Self {
+    FrameEncoding::Jpeg(75)
+  }
+}
+
+pub struct WebSocketServer {
+  listener: TcpListener,
+  encoding: FrameEncoding,
+  client_fps: u32,
+}
+
+impl WebSocketServer {
+  pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+    Ok(Self {
+      listener: TcpListener::bind(addr)?,
+      encoding: FrameEncoding::default(),
+      client_fps: DEFAULT_CLIENT_FPS,
+    })
+  }
+
+  pub fn serve<C: Capture>(addr: impl ToSocketAddrs, session: CaptureSession<C>) -> Result<()> {
+    Self::bind(addr)?.run(session)
+  }
+
+  pub fn with_encoding(mut self, encoding: FrameEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn encoding(&self) -> FrameEncoding {
+    self.encoding
+  }
+
+  pub fn with_client_fps(mut self, fps: u32) -> Self {
+    self.client_fps = fps;
+    self
+  }
+
+  pub fn client_fps(&self) -> u32 {
+    self.client_fps
+  }
+
+  pub fn local_addr(&self) -> Result<SocketAddr> {
+    Ok(self.listener.local_addr()?)
+  }
+
+  pub fn run<C: Capture>(self, mut session: CaptureSession<C>) -> Result<()> {
+    let frames = Arc::new(EncodedFrames::default());
+    let accepted = frames.clone();
+    let closed = frames.clone();
+    let client_fps = self.client_fps;
+    let acceptor = Acceptor::spawn(
+      self.listener,
+      move || closed.is_closed(),
+      move |stream| {
+        let frames = accepted.clone();
+        thread::spawn(move || {
+          frames.connect();
+          let _ = stream_client(stream, &frames, client_fps);
+          frames.disconnect();
+        });
+      },
+    );
+
+    let mut encoded = Ok(());
+    let result = session.run(|screenshot: Screenshot<'_, BGRA>| {
+      if frames.has_clients() {
+        match self.encoding.encode(&screenshot) {
+          Ok(frame) => frames.publish(frame),
+          Err(error) => {
+            encoded = Err(error);
+            return ControlFlow::Break(());
+          }
+        }
+      }
+      ControlFlow::Continue(())
+    });
+    frames.close();
+    drop(acceptor);
+    result.and(encoded)
+  }
+}
+
+fn stream_client(stream: TcpStream, frames: &EncodedFrames, default_fps: u32) -> Result<()> {
+  let mut fps = default_fps;
+  let mut socket = match tungstenite::accept_hdr(stream, ClientFps(&mut fps)) {
+    Ok(socket) => socket,
+    Err(_) => return Ok(()),
+  };
+
+  let interval = Duration::from_secs(1) / fps.max(1);
+  let mut last_sent: Option<Instant> = None;
+  let mut sequence = 0;
+  while let Some(frame) = frames.next(&mut sequence) {
+    if last_sent.is_some_and(|sent| sent.elapsed() < interval) {
+      continue;
+    }
+    if socket.send(Message::binary(frame.to_vec())).is_err() {
+      break;
+    }
+    last_sent = Some(Instant::now());
+  }
+  let _ = socket.close(None);
+  Ok(())
+}
+
+struct ClientFps<'a>(&'a mut u32);
+
+impl Callback for ClientFps<'_> {
+  fn on_request(
+    self,
+    request: &Request,
+    response: Response,
+  ) -> std::result::Result<Response, ErrorResponse> {
+    if let Some(fps) = requested_fps(request) {
+      *self.0 = fps;
+    }
+    Ok(response)
+  }
+}
+
+fn requested_fps(request: &Request) -> Option<u32> {
+  request
+    .uri()
+    .query()?
+    .split('&')
+    .find_map(|pair| pair.strip_prefix("fps="))?
+    .parse()
+    .ok()
+}
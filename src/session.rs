@@ -0,0 +1,215 @@
+use {
+  crate::{
+    broadcast::Broadcast,
+    pacer::{Pacer, Pacing},
+    reacquire::ReacquirePolicy,
+    stats::{CaptureStats, CaptureStatsHandle, TimedCapture},
+    trace::{event, span},
+    Capture, DesktopState, FrameBuffer, OcclusionMode, PixelFormat, Result, Screenshot,
+    StreamshotError,
+  },
+  std::{
+    cell::Cell,
+    ops::{ControlFlow, Not},
+    time::{Duration, Instant},
+  },
+};
+
+const DEFAULT_FPS: u32 = 30;
+
+pub struct CaptureSession<C> {
+  capture: C,
+  fps: u32,
+  skip_unchanged: bool,
+  pacing: Pacing,
+  reacquire: Option<ReacquirePolicy>,
+  occlusion: OcclusionMode,
+  stats: CaptureStatsHandle,
+}
+
+impl<C: Capture> CaptureSession<C> {
+  pub fn new(capture: C) -> Self {
+    Self {
+      capture,
+      fps: DEFAULT_FPS,
+      skip_unchanged: false,
+      pacing: Pacing::default(),
+      reacquire: None,
+      occlusion: OcclusionMode::default(),
+      stats: CaptureStatsHandle::default(),
+    }
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps;
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn with_skip_unchanged(mut self, skip_unchanged: bool) -> Self {
+    self.skip_unchanged = skip_unchanged;
+    self
+  }
+
+  pub fn skip_unchanged(&self) -> bool {
+    self.skip_unchanged
+  }
+
+  pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+    self.pacing = pacing;
+    self
+  }
+
+  pub fn pacing(&self) -> Pacing {
+    self.pacing
+  }
+
+  pub fn with_reacquire(mut self, policy: ReacquirePolicy) -> Self {
+    self.reacquire = Some(policy);
+    self
+  }
+
+  pub fn reacquire_policy(&self) -> Option<&ReacquirePolicy> {
+    self.reacquire.as_ref()
+  }
+
+  pub fn with_occlusion(mut self, occlusion: OcclusionMode) -> Self {
+    self.occlusion = occlusion;
+    self
+  }
+
+  pub fn occlusion(&self) -> OcclusionMode {
+    self.occlusion
+  }
+
+  pub fn stats(&self) -> CaptureStats {
+    self.stats.get()
+  }
+
+  pub fn stats_handle(&self) -> CaptureStatsHandle {
+    self.stats.clone()
+  }
+
+  pub fn capture(&mut self) -> &mut C {
+    &mut self.capture
+  }
+
+  pub fn into_inner(self) -> C {
+    self.capture
+  }
+
+  pub fn run<Color, F>(&mut self, mut on_frame: F) -> Result<()>
+  where
+    Color: PixelFormat,
+    F: FnMut(Screenshot<'_, Color>) -> ControlFlow<()>,
+  {
+    let mut pacer = Pacer::with_pacing(self.fps, self.pacing, self.capture.monitor())?;
+    let mut last_hash = None;
+    let mut last_desktop = DesktopState::Available;
+    let _span = span!("capture_session", fps = self.fps);
+    event!(info, "capture session started");
+    loop {
+      self.stats.record_dropped(pacer.wait());
+      let desktop = DesktopState::current();
+      if desktop != last_desktop {
+        event!(info, ?desktop, "input desktop changed");
+        last_desktop = desktop;
+      }
+      self.stats.record_desktop(desktop);
+      if desktop.is_available().not() {
+        last_hash = None;
+        continue;
+      }
+      let hidden = self.occlusion != OcclusionMode::Capture
+        && self
+          .capture
+          .visibility()
+          .is_ok_and(|visibility| visibility.is_visible().not());
+      if hidden && self.occlusion == OcclusionMode::Pause {
+        self.stats.record_skipped();
+        continue;
+      }
+      let stale = hidden && self.capture.frame_buffer().meta().seq() > 0;
+      let read = Cell::new(Duration::ZERO);
+      let start = Instant::now();
+      let mut source = SessionFrame {
+        capture: &mut self.capture,
+        read: &read,
+        stale,
+      };
+      let screenshot = match source.get_screenshot::<Color>() {
+        Ok(screenshot) => screenshot,
+        Err(StreamshotError::WindowClosed) => match &self.reacquire {
+          Some(policy) => {
+            event!(info, "window closed, reacquiring");
+            policy.reacquire(&mut self.capture)?;
+            last_hash = None;
+            continue;
+          }
+          None => {
+            event!(info, "window closed, capture session stopped");
+            return Err(StreamshotError::WindowClosed);
+          }
+        },
+        Err(error) => {
+          event!(error, %error, "capture session failed");
+          return Err(error);
+        }
+      };
+      self
+        .stats
+        .record(read.get(), start.elapsed().saturating_sub(read.get()));
+      if self.skip_unchanged {
+        let hash = Some(screenshot.content_hash());
+        if hash == last_hash {
+          self.stats.record_skipped();
+          continue;
+        }
+        last_hash = hash;
+      }
+      if on_frame(screenshot).is_break() {
+        event!(info, "capture session stopped");
+        return Ok(());
+      }
+    }
+  }
+
+  pub fn run_broadcast<Color: PixelFormat>(&mut self, broadcast: &Broadcast<Color>) -> Result<()> {
+    let mut attached = false;
+    self.run(
+      |screenshot: Screenshot<'_, Color>| match broadcast.publish(&screenshot) {
+        0 if attached => ControlFlow::Break(()),
+        0 => ControlFlow::Continue(()),
+        _ => {
+          attached = true;
+          ControlFlow::Continue(())
+        }
+      },
+    )
+  }
+}
+
+struct SessionFrame<'a, C> {
+  capture: &'a mut C,
+  read: &'a Cell<Duration>,
+  stale: bool,
+}
+
+impl<C: Capture> Capture for SessionFrame<'_, C> {
+  fn read(&mut self) -> Result<()> {
+    match self.stale {
+      true => {
+        self.capture.frame_buffer().mark_stale();
+        Ok(())
+      }
+      false => TimedCapture::new(&mut *self.capture, self.read).read(),
+    }
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    self.capture.frame_buffer()
+  }
+}
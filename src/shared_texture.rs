@@ -0,0 +1,183 @@
+use {
+  crate::{wrappers::HandleWrapper, Result},
+  std::ops::Not,
+  windows::{
+    core::{ComInterface, Error, Interface, HRESULT},
+    Win32::{
+      Foundation::{E_FAIL, HANDLE, WAIT_TIMEOUT},
+      Graphics::{
+        Direct3D11::{
+          ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D, D3D11_BIND_RENDER_TARGET,
+          D3D11_BIND_SHADER_RESOURCE, D3D11_BOX, D3D11_CPU_ACCESS_FLAG,
+          D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX, D3D11_RESOURCE_MISC_SHARED_NTHANDLE,
+          D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT,
+        },
+        Dxgi::{
+          Common::DXGI_FORMAT, IDXGIKeyedMutex, IDXGIResource, IDXGIResource1,
+          DXGI_SHARED_RESOURCE_READ, DXGI_SHARED_RESOURCE_WRITE,
+        },
+      },
+    },
+  },
+};
+
+const ACQUIRE_TIMEOUT_MS: u32 = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SharedTextureKind {
+  #[default]
+  KeyedMutex,
+  NtHandle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SharedTexture {
+  handle: HANDLE,
+  width: u32,
+  height: u32,
+  format: DXGI_FORMAT,
+  kind: SharedTextureKind,
+}
+
+impl SharedTexture {
+  pub const KEY: u64 = 0;
+
+  pub fn handle(&self) -> HANDLE {
+    self.handle
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn format(&self) -> DXGI_FORMAT {
+    self.format
+  }
+
+  pub fn kind(&self) -> SharedTextureKind {
+    self.kind
+  }
+}
+
+pub(crate) struct SharedTextureExport {
+  kind: SharedTextureKind,
+  texture: Option<(ID3D11Texture2D, IDXGIKeyedMutex)>,
+  nt_handle: Option<HandleWrapper>,
+  info: Option<SharedTexture>,
+}
+
+impl SharedTextureExport {
+  pub(crate) fn new(kind: SharedTextureKind) -> Self {
+    Self {
+      kind,
+      texture: None,
+      nt_handle: None,
+      info: None,
+    }
+  }
+
+  pub(crate) fn info(&self) -> Option<SharedTexture> {
+    self.info
+  }
+
+  pub(crate) fn reset(&mut self) {
+    self.texture = None;
+    self.nt_handle = None;
+    self.info = None;
+  }
+
+  pub(crate) fn export(
+    &mut self,
+    device: &ID3D11Device,
+    context: &ID3D11DeviceContext,
+    source: &ID3D11Texture2D,
+    region: &D3D11_BOX,
+  ) -> Result<()> {
+    let mut desc = D3D11_TEXTURE2D_DESC::default();
+    unsafe {
+      source.GetDesc(&mut desc);
+    }
+    let (width, height) = (region.right - region.left, region.bottom - region.top);
+    let current = self
+      .info
+      .is_some_and(|info| (info.width, info.height, info.format) == (width, height, desc.Format));
+    if current.not() {
+      self.reset();
+      self.create(device, desc, width, height)?;
+    }
+    let (texture, mutex) = match &self.texture {
+      Some(texture) => texture,
+      None => return Ok(()),
+    };
+
+    let acquired: HRESULT = unsafe {
+      (mutex.vtable().AcquireSync)(mutex.as_raw(), SharedTexture::KEY, ACQUIRE_TIMEOUT_MS)
+    };
+    if acquired == HRESULT(WAIT_TIMEOUT.0 as i32) {
+      return Ok(());
+    }
+    acquired.ok()?;
+    unsafe {
+      context.CopySubresourceRegion(texture, 0, 0, 0, 0, source, 0, Some(region));
+      mutex.ReleaseSync(SharedTexture::KEY)?;
+    }
+    Ok(())
+  }
+
+  fn create(
+    &mut self,
+    device: &ID3D11Device,
+    mut desc: D3D11_TEXTURE2D_DESC,
+    width: u32,
+    height: u32,
+  ) -> Result<()> {
+    desc.Width = width;
+    desc.Height = height;
+    desc.MipLevels = 1;
+    desc.ArraySize = 1;
+    desc.Usage = D3D11_USAGE_DEFAULT;
+    desc.BindFlags = D3D11_BIND_SHADER_RESOURCE | D3D11_BIND_RENDER_TARGET;
+    desc.CPUAccessFlags = D3D11_CPU_ACCESS_FLAG(0);
+    desc.MiscFlags = match self.kind {
+      SharedTextureKind::KeyedMutex => D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX,
+      SharedTextureKind::NtHandle => {
+        D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX | D3D11_RESOURCE_MISC_SHARED_NTHANDLE
+      }
+    };
+
+    let mut texture = None;
+    unsafe {
+      device.CreateTexture2D(&desc, None, Some(&mut texture))?;
+    }
+    let texture: ID3D11Texture2D =
+      texture.ok_or_else(|| Error::new(E_FAIL, "CreateTexture2D error".into()))?;
+    let handle = unsafe {
+      match self.kind {
+        SharedTextureKind::KeyedMutex => texture.cast::<IDXGIResource>()?.GetSharedHandle()?,
+        SharedTextureKind::NtHandle => {
+          let handle = texture.cast::<IDXGIResource1>()?.CreateSharedHandle(
+            None,
+            DXGI_SHARED_RESOURCE_READ | DXGI_SHARED_RESOURCE_WRITE,
+            None,
+          )?;
+          self.nt_handle = Some(HandleWrapper::from_handle(handle)?);
+          handle
+        }
+      }
+    };
+    let mutex = texture.cast::<IDXGIKeyedMutex>()?;
+    self.texture = Some((texture, mutex));
+    self.info = Some(SharedTexture {
+      handle,
+      width,
+      height,
+      format: desc.Format,
+      kind: self.kind,
+    });
+    Ok(())
+  }
+}
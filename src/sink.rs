@@ -0,0 +1,118 @@
+mod ffmpeg;
+#[cfg(feature = "ndi")]
+mod ndi;
+mod pipe;
+mod shm;
+#[cfg(feature = "webcam")]
+mod webcam;
+
+use {
+  crate::{FrameMeta, Screenshot},
+  std::time::{Duration, UNIX_EPOCH},
+};
+
+#[cfg(feature = "ndi")]
+pub use self::ndi::NdiSender;
+#[cfg(feature = "webcam")]
+pub use self::webcam::{VirtualWebcam, WebcamFormat};
+pub use self::{
+  ffmpeg::FfmpegPipe,
+  pipe::PipeSink,
+  shm::{SharedMemoryReader, SharedMemorySink},
+};
+
+pub const FRAME_HEADER_LEN: usize = 32;
+const FRAME_MAGIC: &[u8; 4] = b"SSHT";
+
+fn frame_header<Color>(screenshot: &Screenshot<'_, Color>) -> [u8; FRAME_HEADER_LEN] {
+  let meta = screenshot.meta();
+  let timestamp = meta
+    .wall_clock()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_micros() as u64;
+
+  let mut header = [0; FRAME_HEADER_LEN];
+  header[0..4].copy_from_slice(FRAME_MAGIC);
+  header[4..8].copy_from_slice(&screenshot.width().to_le_bytes());
+  header[8..12].copy_from_slice(&screenshot.height().to_le_bytes());
+  header[12..16].copy_from_slice(&screenshot.stride().to_le_bytes());
+  header[16..24].copy_from_slice(&timestamp.to_le_bytes());
+  header[24..32].copy_from_slice(&meta.seq().to_le_bytes());
+  header
+}
+
+struct FrameHeader {
+  width: u32,
+  height: u32,
+  stride: u32,
+  meta: FrameMeta,
+}
+
+fn parse_frame_header(header: &[u8; FRAME_HEADER_LEN]) -> Option<FrameHeader> {
+  if &header[0..4] != FRAME_MAGIC {
+    return None;
+  }
+  let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+  let u64_at = |offset: usize| u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+  let wall_clock = UNIX_EPOCH + Duration::from_micros(u64_at(16));
+  Some(FrameHeader {
+    width: u32_at(4),
+    height: u32_at(8),
+    stride: u32_at(12),
+    meta: FrameMeta::with_wall_clock(u64_at(24), wall_clock),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::FrameBuffer};
+
+  #[test]
+  fn frame_header_layout() {
+    let mut frame = FrameBuffer::new(3, 2);
+    frame.stamp();
+    frame.stamp();
+    let screenshot = frame.bgr_screenshot();
+    let header = frame_header(&screenshot);
+    let timestamp = screenshot
+      .meta()
+      .wall_clock()
+      .duration_since(UNIX_EPOCH)
+      .unwrap()
+      .as_micros() as u64;
+    assert_eq!(&header[0..4], b"SSHT");
+    assert_eq!(&header[4..8], 3u32.to_le_bytes());
+    assert_eq!(&header[8..12], 2u32.to_le_bytes());
+    assert_eq!(&header[12..16], 12u32.to_le_bytes());
+    assert_eq!(&header[16..24], timestamp.to_le_bytes());
+    assert_eq!(&header[24..32], 2u64.to_le_bytes());
+  }
+
+  #[test]
+  fn frame_header_round_trips() {
+    let mut frame = FrameBuffer::new(3, 2);
+    frame.stamp();
+    frame.stamp();
+    let screenshot = frame.bgr_screenshot();
+    let parsed = parse_frame_header(&frame_header(&screenshot)).unwrap();
+    assert_eq!((parsed.width, parsed.height, parsed.stride), (3, 2, 12));
+    assert_eq!(parsed.meta.seq(), 2);
+    let micros = |meta: FrameMeta| {
+      meta
+        .wall_clock()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros()
+    };
+    assert_eq!(micros(parsed.meta), micros(screenshot.meta()));
+  }
+
+  #[test]
+  fn parse_frame_header_rejects_bad_magic() {
+    let mut header = [0; FRAME_HEADER_LEN];
+    assert!(parse_frame_header(&header).is_none());
+    header[0..4].copy_from_slice(FRAME_MAGIC);
+    assert!(parse_frame_header(&header).is_some());
+  }
+}
@@ -0,0 +1,121 @@
+use {
+  crate::{Capture, CaptureSession, Result, Screenshot, StreamshotError, BGRA},
+  std::{
+    ffi::OsStr,
+    io::{ErrorKind, Write},
+    ops::ControlFlow,
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+  },
+};
+
+const FFMPEG: &str = "ffmpeg";
+
+pub struct FfmpegPipe {
+  child: Child,
+  stdin: Option<ChildStdin>,
+  width: u32,
+  height: u32,
+}
+
+impl FfmpegPipe {
+  pub fn spawn<I, S>(args: I, width: u32, height: u32, fps: u32) -> Result<Self>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+  {
+    Self::spawn_with_program(FFMPEG, args, width, height, fps)
+  }
+
+  pub fn spawn_with_program<I, S>(
+    program: impl AsRef<OsStr>,
+    args: I,
+    width: u32,
+    height: u32,
+    fps: u32,
+  ) -> Result<Self>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+  {
+    let mut child = Command::new(program)
+      .args(["-hide_banner", "-loglevel", "error", "-y"])
+      .args(["-f", "rawvideo", "-pixel_format", "bgra"])
+      .args(["-video_size", &format!("{width}x{height}")])
+      .args(["-framerate", &fps.max(1).to_string()])
+      .args(["-i", "-"])
+      .args(args)
+      .stdin(Stdio::piped())
+      .spawn()?;
+    Ok(Self {
+      stdin: child.stdin.take(),
+      child,
+      width,
+      height,
+    })
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn is_running(&mut self) -> Result<bool> {
+    Ok(self.child.try_wait()?.is_none())
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    if screenshot.width() != self.width || screenshot.height() != self.height {
+      return Err(StreamshotError::InvalidSize {
+        width: screenshot.width() as i32,
+        height: screenshot.height() as i32,
+      });
+    }
+    let Some(stdin) = self.stdin.as_mut() else {
+      return Err(self.exited());
+    };
+    match stdin.write_all(&screenshot.packed()) {
+      Ok(()) => Ok(()),
+      Err(error) if error.kind() == ErrorKind::BrokenPipe => {
+        self.stdin = None;
+        Err(self.exited())
+      }
+      Err(error) => Err(error.into()),
+    }
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+
+  pub fn finish(mut self) -> Result<ExitStatus> {
+    self.stdin = None;
+    Ok(self.child.wait()?)
+  }
+
+  fn exited(&mut self) -> StreamshotError {
+    match self.child.wait() {
+      Ok(status) => StreamshotError::Encoding(format!("ffmpeg exited with {status}")),
+      Err(error) => error.into(),
+    }
+  }
+}
+
+impl Drop for FfmpegPipe {
+  fn drop(&mut self) {
+    self.stdin = None;
+    let _ = self.child.wait();
+  }
+}
@@ -0,0 +1,205 @@
+use {
+  crate::{
+    wrappers::LibraryWrapper, Capture, CaptureSession, Result, Screenshot, StreamshotError, BGRA,
+  },
+  std::{
+    env,
+    ffi::{c_char, c_void, CStr, CString},
+    ops::{ControlFlow, Not},
+    path::PathBuf,
+    ptr,
+  },
+};
+
+#[cfg(target_pointer_width = "64")]
+const NDI_LIBRARY: &str = "Processing.NDI.Lib.x64.dll";
+#[cfg(target_pointer_width = "32")]
+const NDI_LIBRARY: &str = "Processing.NDI.Lib.x86.dll";
+const NDI_RUNTIME_DIRS: [&str; 3] = [
+  "NDI_RUNTIME_DIR_V6",
+  "NDI_RUNTIME_DIR_V5",
+  "NDI_RUNTIME_DIR_V4",
+];
+
+const FOURCC_BGRX: u32 = u32::from_le_bytes(*b"BGRX");
+const FRAME_FORMAT_PROGRESSIVE: i32 = 1;
+const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+const DEFAULT_FPS: u32 = 30;
+
+#[repr(C)]
+struct SendCreate {
+  name: *const c_char,
+  groups: *const c_char,
+  clock_video: bool,
+  clock_audio: bool,
+}
+
+#[repr(C)]
+struct VideoFrame {
+  xres: i32,
+  yres: i32,
+  fourcc: u32,
+  frame_rate_n: i32,
+  frame_rate_d: i32,
+  picture_aspect_ratio: f32,
+  frame_format_type: i32,
+  timecode: i64,
+  data: *const u8,
+  line_stride_in_bytes: i32,
+  metadata: *const c_char,
+  timestamp: i64,
+}
+
+type Initialize = unsafe extern "C" fn() -> bool;
+type Destroy = unsafe extern "C" fn();
+type SendCreateFn = unsafe extern "C" fn(*const SendCreate) -> *mut c_void;
+type SendDestroy = unsafe extern "C" fn(*mut c_void);
+type SendVideo = unsafe extern "C" fn(*mut c_void, *const VideoFrame);
+
+struct NdiLibrary {
+  destroy: Destroy,
+  send_create: SendCreateFn,
+  send_destroy: SendDestroy,
+  send_video: SendVideo,
+  _module: LibraryWrapper,
+}
+
+impl NdiLibrary {
+  fn load() -> Result<Self> {
+    let module = library_paths()
+      .find_map(|path| LibraryWrapper::load(&path).ok())
+      .ok_or(StreamshotError::Unsupported(
+        "the NDI runtime is not installed",
+      ))?;
+
+    unsafe {
+      let initialize: Initialize = symbol(&module, c"NDIlib_initialize")?;
+      let library = Self {
+        destroy: symbol(&module, c"NDIlib_destroy")?,
+        send_create: symbol(&module, c"NDIlib_send_create")?,
+        send_destroy: symbol(&module, c"NDIlib_send_destroy")?,
+        send_video: symbol(&module, c"NDIlib_send_send_video_v2")?,
+        _module: module,
+      };
+      if initialize().not() {
+        return Err(StreamshotError::Unsupported(
+          "the NDI runtime does not support this CPU",
+        ));
+      }
+      Ok(library)
+    }
+  }
+}
+
+impl Drop for NdiLibrary {
+  fn drop(&mut self) {
+    unsafe {
+      (self.destroy)();
+    }
+  }
+}
+
+unsafe fn symbol<T: Copy>(module: &LibraryWrapper, name: &CStr) -> Result<T> {
+  module.symbol(name).ok_or(StreamshotError::Unsupported(
+    "the NDI runtime is missing a required export",
+  ))
+}
+
+fn library_paths() -> impl Iterator<Item = PathBuf> {
+  NDI_RUNTIME_DIRS
+    .iter()
+    .filter_map(env::var_os)
+    .map(|dir| PathBuf::from(dir).join(NDI_LIBRARY))
+    .chain([PathBuf::from(NDI_LIBRARY)])
+}
+
+pub struct NdiSender {
+  name: String,
+  instance: *mut c_void,
+  fps: u32,
+  library: NdiLibrary,
+}
+
+impl NdiSender {
+  pub fn new(name: &str) -> Result<Self> {
+    let library = NdiLibrary::load()?;
+    let source_name = CString::new(name)
+      .map_err(|_| StreamshotError::Unsupported("NDI source names cannot contain NUL"))?;
+    let create = SendCreate {
+      name: source_name.as_ptr(),
+      groups: ptr::null(),
+      clock_video: false,
+      clock_audio: false,
+    };
+    let instance = unsafe { (library.send_create)(&create) };
+    if instance.is_null() {
+      return Err(StreamshotError::Unsupported(
+        "the NDI runtime failed to create a sender",
+      ));
+    }
+    Ok(Self {
+      name: name.to_owned(),
+      instance,
+      fps: DEFAULT_FPS,
+      library,
+    })
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn with_fps(mut self, fps: u32) -> Self {
+    self.fps = fps.max(1);
+    self
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    let frame = VideoFrame {
+      xres: screenshot.width() as i32,
+      yres: screenshot.height() as i32,
+      fourcc: FOURCC_BGRX,
+      frame_rate_n: self.fps as i32,
+      frame_rate_d: 1,
+      picture_aspect_ratio: 0.0,
+      frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+      timecode: TIMECODE_SYNTHESIZE,
+      data: screenshot.image.as_ptr(),
+      line_stride_in_bytes: screenshot.stride() as i32,
+      metadata: ptr::null(),
+      timestamp: 0,
+    };
+    unsafe {
+      (self.library.send_video)(self.instance, &frame);
+    }
+    Ok(())
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    self.fps = session.fps().max(1);
+
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+}
+
+impl Drop for NdiSender {
+  fn drop(&mut self) {
+    unsafe {
+      (self.library.send_destroy)(self.instance);
+    }
+  }
+}
@@ -0,0 +1,122 @@
+use {
+  super::frame_header,
+  crate::{
+    wrappers::HandleWrapper, Capture, CaptureSession, Result, Screenshot, StreamshotError, BGRA,
+  },
+  std::ops::{ControlFlow, Not},
+  windows::{
+    core::{Error, HSTRING},
+    Win32::{
+      Foundation::{ERROR_BROKEN_PIPE, ERROR_NO_DATA, ERROR_PIPE_CONNECTED},
+      Storage::FileSystem::{WriteFile, PIPE_ACCESS_OUTBOUND},
+      System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_WAIT,
+      },
+    },
+  },
+};
+
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+const PIPE_BUFFER_SIZE: u32 = 1 << 20;
+
+pub struct PipeSink {
+  name: String,
+  pipe: HandleWrapper,
+  connected: bool,
+}
+
+impl PipeSink {
+  pub fn create(name: &str) -> Result<Self> {
+    let name = match name.starts_with(PIPE_PREFIX) {
+      true => name.to_owned(),
+      false => format!("{PIPE_PREFIX}{name}"),
+    };
+    let handle = unsafe {
+      CreateNamedPipeW(
+        &HSTRING::from(name.as_str()),
+        PIPE_ACCESS_OUTBOUND,
+        PIPE_TYPE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+        1,
+        PIPE_BUFFER_SIZE,
+        0,
+        0,
+        None,
+      )
+    };
+    Ok(Self {
+      name,
+      pipe: HandleWrapper::from_handle(handle)?,
+      connected: false,
+    })
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn is_connected(&self) -> bool {
+    self.connected
+  }
+
+  pub fn wait_for_client(&mut self) -> Result<()> {
+    if self.connected {
+      return Ok(());
+    }
+    unsafe {
+      if ConnectNamedPipe(self.pipe.inner(), None) == false {
+        let error = Error::from_win32();
+        if error.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+          return Err(error.into());
+        }
+      }
+    }
+    self.connected = true;
+    Ok(())
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    self.wait_for_client()?;
+    let result = self
+      .write_all(&frame_header(screenshot))
+      .and_then(|()| self.write_all(screenshot.image));
+    if let Err(StreamshotError::Windows(error)) = &result {
+      if error.code() == ERROR_BROKEN_PIPE.to_hresult()
+        || error.code() == ERROR_NO_DATA.to_hresult()
+      {
+        unsafe {
+          DisconnectNamedPipe(self.pipe.inner());
+        }
+        self.connected = false;
+      }
+    }
+    result
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+
+  fn write_all(&self, mut bytes: &[u8]) -> Result<()> {
+    while bytes.is_empty().not() {
+      let mut written = 0;
+      unsafe {
+        if WriteFile(self.pipe.inner(), Some(bytes), Some(&mut written), None) == false {
+          return Err(Error::from_win32().into());
+        }
+      }
+      bytes = &bytes[written as usize..];
+    }
+    Ok(())
+  }
+}
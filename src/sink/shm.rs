@@ -0,0 +1,240 @@
+use {
+  super::{frame_header, parse_frame_header, FRAME_HEADER_LEN},
+  crate::{
+    wrappers::{HandleWrapper, MappedView},
+    Capture, CaptureSession, Result, Screenshot, ScreenshotOwned, StreamshotError, BGRA,
+  },
+  std::{
+    marker::PhantomData,
+    ops::ControlFlow,
+    ptr,
+    sync::atomic::{fence, AtomicU64, Ordering},
+    thread,
+  },
+  windows::{
+    core::HSTRING,
+    Win32::{
+      Foundation::{GetLastError, ERROR_ALREADY_EXISTS, INVALID_HANDLE_VALUE},
+      System::Memory::{
+        CreateFileMappingW, OpenFileMappingW, FILE_MAP_ALL_ACCESS, FILE_MAP_READ, PAGE_READWRITE,
+      },
+    },
+  },
+};
+
+const MAPPING_MAGIC: &[u8; 4] = b"SSHM";
+const CAPACITY_OFFSET: usize = 4;
+const SEQUENCE_OFFSET: usize = 8;
+const HEADER_OFFSET: usize = 16;
+const DATA_OFFSET: usize = HEADER_OFFSET + FRAME_HEADER_LEN;
+const READ_ATTEMPTS: usize = 64;
+
+struct Mapping {
+  _handle: HandleWrapper,
+  view: MappedView,
+}
+
+impl Mapping {
+  fn sequence(&self) -> &AtomicU64 {
+    unsafe { &*(self.view.as_ptr().add(SEQUENCE_OFFSET) as *const AtomicU64) }
+  }
+
+  fn capacity(&self) -> usize {
+    let mut capacity = [0; 4];
+    unsafe {
+      ptr::copy_nonoverlapping(
+        self.view.as_ptr().add(CAPACITY_OFFSET),
+        capacity.as_mut_ptr(),
+        capacity.len(),
+      );
+    }
+    u32::from_le_bytes(capacity) as usize
+  }
+}
+
+pub struct SharedMemorySink {
+  name: String,
+  mapping: Mapping,
+  capacity: usize,
+}
+
+impl SharedMemorySink {
+  pub fn create(name: &str, max_width: u32, max_height: u32) -> Result<Self> {
+    let capacity = u32::try_from(4 * max_width as u64 * max_height as u64).map_err(|_| {
+      StreamshotError::InvalidSize {
+        width: max_width as i32,
+        height: max_height as i32,
+      }
+    })? as usize;
+    let size = (DATA_OFFSET + capacity) as u64;
+    let handle = unsafe {
+      CreateFileMappingW(
+        INVALID_HANDLE_VALUE,
+        None,
+        PAGE_READWRITE,
+        (size >> 32) as u32,
+        size as u32,
+        &HSTRING::from(name),
+      )?
+    };
+    let existed = unsafe { GetLastError() } == ERROR_ALREADY_EXISTS;
+    let handle = HandleWrapper::from_handle(handle)?;
+    let view = MappedView::map(&handle, FILE_MAP_ALL_ACCESS)?;
+    if existed && view.len() < size as usize {
+      return Err(StreamshotError::BufferTooSmall {
+        required: size as usize,
+        provided: view.len(),
+      });
+    }
+    unsafe {
+      let base = view.as_ptr();
+      ptr::copy_nonoverlapping(MAPPING_MAGIC.as_ptr(), base, MAPPING_MAGIC.len());
+      ptr::copy_nonoverlapping(
+        (capacity as u32).to_le_bytes().as_ptr(),
+        base.add(CAPACITY_OFFSET),
+        4,
+      );
+    }
+    Ok(Self {
+      name: name.to_owned(),
+      mapping: Mapping {
+        _handle: handle,
+        view,
+      },
+      capacity,
+    })
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    if screenshot.image.len() > self.capacity {
+      return Err(StreamshotError::BufferTooSmall {
+        required: screenshot.image.len(),
+        provided: self.capacity,
+      });
+    }
+
+    let header = frame_header(screenshot);
+    let sequence = self.mapping.sequence();
+    let current = sequence.load(Ordering::Relaxed);
+    sequence.store(current + 1, Ordering::Relaxed);
+    fence(Ordering::Release);
+    unsafe {
+      let base = self.mapping.view.as_ptr();
+      ptr::copy_nonoverlapping(header.as_ptr(), base.add(HEADER_OFFSET), header.len());
+      ptr::copy_nonoverlapping(
+        screenshot.image.as_ptr(),
+        base.add(DATA_OFFSET),
+        screenshot.image.len(),
+      );
+    }
+    sequence.store(current + 2, Ordering::Release);
+    Ok(())
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+}
+
+pub struct SharedMemoryReader {
+  mapping: Mapping,
+  last_sequence: u64,
+}
+
+impl SharedMemoryReader {
+  pub fn open(name: &str) -> Result<Self> {
+    let handle = unsafe { OpenFileMappingW(FILE_MAP_READ.0, false, &HSTRING::from(name))? };
+    let handle = HandleWrapper::from_handle(handle)?;
+    let view = MappedView::map(&handle, FILE_MAP_READ)?;
+    let mut magic = [0; 4];
+    unsafe {
+      ptr::copy_nonoverlapping(view.as_ptr(), magic.as_mut_ptr(), magic.len());
+    }
+    if &magic != MAPPING_MAGIC {
+      return Err(StreamshotError::Unsupported(
+        "file mapping was not created by SharedMemorySink",
+      ));
+    }
+    let mapping = Mapping {
+      _handle: handle,
+      view,
+    };
+    let required = DATA_OFFSET + mapping.capacity();
+    if mapping.view.len() < required {
+      return Err(StreamshotError::BufferTooSmall {
+        required,
+        provided: mapping.view.len(),
+      });
+    }
+    Ok(Self {
+      mapping,
+      last_sequence: 0,
+    })
+  }
+
+  pub fn sequence(&self) -> u64 {
+    self.mapping.sequence().load(Ordering::Acquire) / 2
+  }
+
+  pub fn read(&mut self) -> Result<Option<ScreenshotOwned<BGRA>>> {
+    let capacity = self.mapping.capacity();
+    for _ in 0..READ_ATTEMPTS {
+      let before = self.mapping.sequence().load(Ordering::Acquire);
+      if before == self.last_sequence {
+        return Ok(None);
+      }
+      if before % 2 == 1 {
+        thread::yield_now();
+        continue;
+      }
+
+      let mut header = [0; FRAME_HEADER_LEN];
+      let base = self.mapping.view.as_ptr();
+      unsafe {
+        ptr::copy_nonoverlapping(base.add(HEADER_OFFSET), header.as_mut_ptr(), header.len());
+      }
+      let Some(frame) = parse_frame_header(&header) else {
+        thread::yield_now();
+        continue;
+      };
+      let len = (frame.stride as usize * frame.height as usize).min(capacity);
+      let mut image = vec![0; len];
+      unsafe {
+        ptr::copy_nonoverlapping(base.add(DATA_OFFSET), image.as_mut_ptr(), len);
+      }
+
+      fence(Ordering::Acquire);
+      if self.mapping.sequence().load(Ordering::Relaxed) == before {
+        self.last_sequence = before;
+        return Ok(Some(ScreenshotOwned {
+          width: frame.width,
+          height: frame.height,
+          stride: frame.stride,
+          image,
+          meta: frame.meta,
+          pool: None,
+          marker: PhantomData,
+        }));
+      }
+    }
+    Ok(None)
+  }
+}
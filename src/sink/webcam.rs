@@ -0,0 +1,203 @@
+use {
+  crate::{
+    wrappers::LibraryWrapper, Capture, CaptureSession, Result, Screenshot, StreamshotError, BGRA,
+  },
+  std::{
+    env,
+    ffi::{c_void, CStr},
+    ops::ControlFlow,
+    path::PathBuf,
+    time::Duration,
+  },
+};
+
+const SOFTCAM_LIBRARY: &str = "softcam.dll";
+const SOFTCAM_LIBRARY_VAR: &str = "SOFTCAM_DLL";
+const STANDARD_SIZES: [(u32, u32); 4] = [(640, 480), (1280, 720), (1920, 1080), (3840, 2160)];
+const MAX_FPS: u32 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebcamFormat {
+  width: u32,
+  height: u32,
+  fps: u32,
+}
+
+impl WebcamFormat {
+  pub fn new(width: u32, height: u32, fps: u32) -> Self {
+    Self {
+      width: (width.max(2) + 1) & !1,
+      height: (height.max(2) + 1) & !1,
+      fps: fps.clamp(1, MAX_FPS),
+    }
+  }
+
+  pub fn negotiate(source_width: u32, source_height: u32, fps: u32) -> Self {
+    let (width, height) = STANDARD_SIZES
+      .into_iter()
+      .find(|&(width, height)| width >= source_width && height >= source_height)
+      .unwrap_or(STANDARD_SIZES[STANDARD_SIZES.len() - 1]);
+    Self::new(width, height, fps)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+}
+
+type CreateCamera = unsafe extern "C" fn(i32, i32, f32) -> *mut c_void;
+type DeleteCamera = unsafe extern "C" fn(*mut c_void);
+type SendFrame = unsafe extern "C" fn(*mut c_void, *const c_void);
+type WaitForConnection = unsafe extern "C" fn(*mut c_void, f32) -> bool;
+
+struct Softcam {
+  delete_camera: DeleteCamera,
+  send_frame: SendFrame,
+  wait_for_connection: WaitForConnection,
+  _module: LibraryWrapper,
+}
+
+pub struct VirtualWebcam {
+  camera: *mut c_void,
+  format: WebcamFormat,
+  frame: Vec<u8>,
+  softcam: Softcam,
+}
+
+impl VirtualWebcam {
+  pub fn new(format: WebcamFormat) -> Result<Self> {
+    let module = library_paths()
+      .find_map(|path| LibraryWrapper::load(&path).ok())
+      .ok_or(StreamshotError::Unsupported(
+        "the softcam virtual camera is not installed",
+      ))?;
+
+    let (create_camera, softcam) = unsafe {
+      let create_camera: CreateCamera = symbol(&module, c"scCreateCamera")?;
+      let softcam = Softcam {
+        delete_camera: symbol(&module, c"scDeleteCamera")?,
+        send_frame: symbol(&module, c"scSendFrame")?,
+        wait_for_connection: symbol(&module, c"scWaitForConnection")?,
+        _module: module,
+      };
+      (create_camera, softcam)
+    };
+
+    let camera =
+      unsafe { create_camera(format.width as i32, format.height as i32, format.fps as f32) };
+    if camera.is_null() {
+      return Err(StreamshotError::Unsupported(
+        "the virtual camera is already in use by another process",
+      ));
+    }
+    Ok(Self {
+      camera,
+      format,
+      frame: vec![0; 3 * format.width as usize * format.height as usize],
+      softcam,
+    })
+  }
+
+  pub fn for_session<C: Capture>(session: &mut CaptureSession<C>) -> Result<Self> {
+    let fps = session.fps();
+    let frame = session.capture().frame_buffer();
+    Self::new(WebcamFormat::negotiate(frame.width(), frame.height(), fps))
+  }
+
+  pub fn format(&self) -> WebcamFormat {
+    self.format
+  }
+
+  pub fn wait_for_connection(&self, timeout: Duration) -> bool {
+    unsafe { (self.softcam.wait_for_connection)(self.camera, timeout.as_secs_f32()) }
+  }
+
+  pub fn push_frame(&mut self, screenshot: &Screenshot<'_, BGRA>) -> Result<()> {
+    letterbox(
+      &screenshot.packed(),
+      screenshot.width() as usize,
+      screenshot.height() as usize,
+      &mut self.frame,
+      self.format.width as usize,
+      self.format.height as usize,
+    );
+    unsafe {
+      (self.softcam.send_frame)(self.camera, self.frame.as_ptr() as *const c_void);
+    }
+    Ok(())
+  }
+
+  pub fn record<C: Capture>(&mut self, session: &mut CaptureSession<C>) -> Result<()> {
+    let mut result = Ok(());
+    session.run(
+      |screenshot: Screenshot<'_, BGRA>| match self.push_frame(&screenshot) {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(error) => {
+          result = Err(error);
+          ControlFlow::Break(())
+        }
+      },
+    )?;
+    result
+  }
+}
+
+impl Drop for VirtualWebcam {
+  fn drop(&mut self) {
+    unsafe {
+      (self.softcam.delete_camera)(self.camera);
+    }
+  }
+}
+
+unsafe fn symbol<T: Copy>(module: &LibraryWrapper, name: &CStr) -> Result<T> {
+  module.symbol(name).ok_or(StreamshotError::Unsupported(
+    "the softcam library is missing a required export",
+  ))
+}
+
+fn library_paths() -> impl Iterator<Item = PathBuf> {
+  env::var_os(SOFTCAM_LIBRARY_VAR)
+    .map(PathBuf::from)
+    .into_iter()
+    .chain([PathBuf::from(SOFTCAM_LIBRARY)])
+}
+
+fn letterbox(
+  source: &[u8],
+  source_width: usize,
+  source_height: usize,
+  target: &mut [u8],
+  target_width: usize,
+  target_height: usize,
+) {
+  target.fill(0);
+  if source_width == 0 || source_height == 0 {
+    return;
+  }
+
+  let (scaled_width, scaled_height) =
+    match source_width * target_height <= target_width * source_height {
+      true => (source_width * target_height / source_height, target_height),
+      false => (target_width, source_height * target_width / source_width),
+    };
+  let left = (target_width - scaled_width) / 2;
+  let top = (target_height - scaled_height) / 2;
+
+  for y in 0..scaled_height {
+    let source_row = y * source_height / scaled_height * source_width;
+    let target_row = (top + y) * target_width + left;
+    for x in 0..scaled_width {
+      let source = &source[4 * (source_row + x * source_width / scaled_width)..][..3];
+      target[3 * (target_row + x)..][..3].copy_from_slice(source);
+    }
+  }
+}
@@ -0,0 +1,194 @@
+use {
+  crate::{Capture, DesktopState, FrameBuffer, Result, Visibility},
+  std::{
+    cell::Cell,
+    collections::VecDeque,
+    ops::Not,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+  },
+  windows::Win32::Graphics::Gdi::HMONITOR,
+};
+
+const WINDOW_SAMPLES: usize = 120;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureStats {
+  fps: f64,
+  frames: u64,
+  dropped_frames: u64,
+  skipped_frames: u64,
+  capture_avg: Duration,
+  capture_p50: Duration,
+  capture_p95: Duration,
+  capture_p99: Duration,
+  conversion_avg: Duration,
+  desktop: DesktopState,
+}
+
+impl CaptureStats {
+  pub fn fps(&self) -> f64 {
+    self.fps
+  }
+
+  pub fn frames(&self) -> u64 {
+    self.frames
+  }
+
+  pub fn dropped_frames(&self) -> u64 {
+    self.dropped_frames
+  }
+
+  pub fn skipped_frames(&self) -> u64 {
+    self.skipped_frames
+  }
+
+  pub fn capture_avg(&self) -> Duration {
+    self.capture_avg
+  }
+
+  pub fn capture_p50(&self) -> Duration {
+    self.capture_p50
+  }
+
+  pub fn capture_p95(&self) -> Duration {
+    self.capture_p95
+  }
+
+  pub fn capture_p99(&self) -> Duration {
+    self.capture_p99
+  }
+
+  pub fn conversion_avg(&self) -> Duration {
+    self.conversion_avg
+  }
+
+  pub fn desktop_state(&self) -> DesktopState {
+    self.desktop
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CaptureStatsHandle {
+  shared: Arc<Mutex<StatsRecorder>>,
+}
+
+impl CaptureStatsHandle {
+  pub fn get(&self) -> CaptureStats {
+    self.shared.lock().unwrap().snapshot()
+  }
+
+  pub(crate) fn record(&self, capture: Duration, conversion: Duration) {
+    self.shared.lock().unwrap().record(capture, conversion);
+  }
+
+  pub(crate) fn record_dropped(&self, dropped: u64) {
+    self.shared.lock().unwrap().dropped_frames += dropped;
+  }
+
+  pub(crate) fn record_skipped(&self) {
+    self.shared.lock().unwrap().skipped_frames += 1;
+  }
+
+  pub(crate) fn record_desktop(&self, desktop: DesktopState) {
+    self.shared.lock().unwrap().desktop = desktop;
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+  at: Instant,
+  capture: Duration,
+  conversion: Duration,
+}
+
+#[derive(Debug, Default)]
+struct StatsRecorder {
+  samples: VecDeque<Sample>,
+  frames: u64,
+  dropped_frames: u64,
+  skipped_frames: u64,
+  desktop: DesktopState,
+}
+
+impl StatsRecorder {
+  fn record(&mut self, capture: Duration, conversion: Duration) {
+    if self.samples.len() == WINDOW_SAMPLES {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(Sample {
+      at: Instant::now(),
+      capture,
+      conversion,
+    });
+    self.frames += 1;
+  }
+
+  fn snapshot(&self) -> CaptureStats {
+    let mut stats = CaptureStats {
+      frames: self.frames,
+      dropped_frames: self.dropped_frames,
+      skipped_frames: self.skipped_frames,
+      desktop: self.desktop,
+      ..CaptureStats::default()
+    };
+    let (first, last) = match (self.samples.front(), self.samples.back()) {
+      (Some(first), Some(last)) => (first, last),
+      _ => return stats,
+    };
+
+    let span = last.at - first.at;
+    if span.is_zero().not() {
+      stats.fps = (self.samples.len() - 1) as f64 / span.as_secs_f64();
+    }
+
+    let count = self.samples.len() as u32;
+    let mut captures: Vec<Duration> = self.samples.iter().map(|sample| sample.capture).collect();
+    captures.sort_unstable();
+    let percentile = |p: usize| captures[(captures.len() - 1) * p / 100];
+    stats.capture_avg = captures.iter().sum::<Duration>() / count;
+    stats.capture_p50 = percentile(50);
+    stats.capture_p95 = percentile(95);
+    stats.capture_p99 = percentile(99);
+    stats.conversion_avg = self
+      .samples
+      .iter()
+      .map(|sample| sample.conversion)
+      .sum::<Duration>()
+      / count;
+    stats
+  }
+}
+
+pub(crate) struct TimedCapture<'a, C> {
+  capture: &'a mut C,
+  read: &'a Cell<Duration>,
+}
+
+impl<'a, C> TimedCapture<'a, C> {
+  pub(crate) fn new(capture: &'a mut C, read: &'a Cell<Duration>) -> Self {
+    Self { capture, read }
+  }
+}
+
+impl<C: Capture> Capture for TimedCapture<'_, C> {
+  fn read(&mut self) -> Result<()> {
+    let start = Instant::now();
+    let result = self.capture.read();
+    self.read.set(self.read.get() + start.elapsed());
+    result
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    self.capture.frame_buffer()
+  }
+
+  fn visibility(&self) -> Result<Visibility> {
+    self.capture.visibility()
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    self.capture.monitor()
+  }
+}
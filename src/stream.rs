@@ -0,0 +1,58 @@
+use {
+  crate::{Capture, FramePool, PixelFormat, Result, ScreenshotOwned},
+  futures::{channel::mpsc, executor::block_on, SinkExt, Stream, StreamExt},
+  std::{
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+  },
+};
+
+pub struct FrameStream<Color> {
+  receiver: mpsc::Receiver<Result<ScreenshotOwned<Color>>>,
+}
+
+impl<Color: PixelFormat + Send + 'static> FrameStream<Color> {
+  pub fn new<C>(capture: C) -> Self
+  where
+    C: Capture + Send + 'static,
+  {
+    Self::spawn_with(move || Ok(capture))
+  }
+
+  pub fn spawn_with<C, F>(make_capture: F) -> Self
+  where
+    C: Capture,
+    F: FnOnce() -> Result<C> + Send + 'static,
+  {
+    let (mut sender, receiver) = mpsc::channel(1);
+    thread::spawn(move || {
+      let mut capture = match make_capture() {
+        Ok(capture) => capture,
+        Err(error) => {
+          let _ = block_on(sender.send(Err(error)));
+          return;
+        }
+      };
+      let pool = FramePool::default();
+      loop {
+        let frame = capture
+          .get_screenshot::<Color>()
+          .map(|screenshot| pool.take(&screenshot));
+        let failed = frame.is_err();
+        if block_on(sender.send(frame)).is_err() || failed {
+          break;
+        }
+      }
+    });
+    Self { receiver }
+  }
+}
+
+impl<Color> Stream for FrameStream<Color> {
+  type Item = Result<ScreenshotOwned<Color>>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.receiver.poll_next_unpin(cx)
+  }
+}
@@ -0,0 +1,104 @@
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::{vld4q_u8, vst4q_u8};
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{
+  __m128i, __m256i, _mm256_loadu_si256, _mm256_setr_epi8, _mm256_shuffle_epi8, _mm256_storeu_si256,
+  _mm_and_si128, _mm_loadu_si128, _mm_or_si128, _mm_set1_epi32, _mm_slli_epi32, _mm_srli_epi32,
+  _mm_storeu_si128,
+};
+
+use crate::parallel::for_each_chunk;
+
+pub(crate) fn swap_red_blue(pixels: &mut [u8]) {
+  for_each_chunk(pixels, 4, swap_chunk);
+}
+
+fn swap_chunk(pixels: &mut [u8]) {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if is_x86_feature_detected!("avx2") {
+      return unsafe { swap_avx2(pixels) };
+    }
+    unsafe { swap_sse2(pixels) }
+  }
+  #[cfg(target_arch = "aarch64")]
+  unsafe {
+    swap_neon(pixels)
+  }
+  #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+  swap_scalar(pixels)
+}
+
+fn swap_scalar(pixels: &mut [u8]) {
+  pixels
+    .chunks_exact_mut(4)
+    .for_each(|pixel| pixel.swap(0, 2));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn swap_avx2(pixels: &mut [u8]) {
+  let mask = _mm256_setr_epi8(
+    2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15, 2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14,
+    13, 12, 15,
+  );
+  let mut chunks = pixels.chunks_exact_mut(32);
+  for chunk in &mut chunks {
+    let ptr = chunk.as_mut_ptr() as *mut __m256i;
+    _mm256_storeu_si256(ptr, _mm256_shuffle_epi8(_mm256_loadu_si256(ptr), mask));
+  }
+  swap_scalar(chunks.into_remainder());
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn swap_sse2(pixels: &mut [u8]) {
+  let green_alpha = _mm_set1_epi32(0xff00ff00u32 as i32);
+  let low = _mm_set1_epi32(0x0000_00ff);
+  let high = _mm_set1_epi32(0x00ff_0000);
+  let mut chunks = pixels.chunks_exact_mut(16);
+  for chunk in &mut chunks {
+    let ptr = chunk.as_mut_ptr() as *mut __m128i;
+    let pixel = _mm_loadu_si128(ptr);
+    let red = _mm_and_si128(_mm_srli_epi32(pixel, 16), low);
+    let blue = _mm_and_si128(_mm_slli_epi32(pixel, 16), high);
+    let swapped = _mm_or_si128(_mm_and_si128(pixel, green_alpha), _mm_or_si128(red, blue));
+    _mm_storeu_si128(ptr, swapped);
+  }
+  swap_scalar(chunks.into_remainder());
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn swap_neon(pixels: &mut [u8]) {
+  let mut chunks = pixels.chunks_exact_mut(64);
+  for chunk in &mut chunks {
+    let ptr = chunk.as_mut_ptr();
+    let mut pixel = vld4q_u8(ptr);
+    std::mem::swap(&mut pixel.0, &mut pixel.2);
+    vst4q_u8(ptr, pixel);
+  }
+  swap_scalar(chunks.into_remainder());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn swaps_red_and_blue_across_simd_and_remainder() {
+    let mut pixels: Vec<u8> = (0..4 * 37).map(|value| value as u8).collect();
+    let mut expected = pixels.clone();
+    swap_scalar(&mut expected);
+    swap_red_blue(&mut pixels);
+    assert_eq!(pixels, expected);
+    assert_eq!(&pixels[..8], &[2, 1, 0, 3, 6, 5, 4, 7]);
+  }
+
+  #[test]
+  fn swapping_twice_restores_the_pixels() {
+    let original: Vec<u8> = (0..4 * 100).map(|value| (value * 7) as u8).collect();
+    let mut pixels = original.clone();
+    swap_red_blue(&mut pixels);
+    swap_red_blue(&mut pixels);
+    assert_eq!(pixels, original);
+  }
+}
@@ -0,0 +1,33 @@
+use {
+  crate::{
+    pacer::Pacer, Capture, FramePool, PixelFormat, Result, ScreenshotOwned, WindowScreenshotBuffer,
+  },
+  tokio::{
+    sync::mpsc::{self, Receiver},
+    task::{self, JoinHandle},
+  },
+};
+
+impl WindowScreenshotBuffer {
+  pub fn spawn_capture<Color>(
+    mut self,
+    fps: u32,
+  ) -> (JoinHandle<Result<()>>, Receiver<ScreenshotOwned<Color>>)
+  where
+    Color: PixelFormat + Send + 'static,
+  {
+    let (sender, receiver) = mpsc::channel(1);
+    let handle = task::spawn_blocking(move || {
+      let mut pacer = Pacer::new(fps);
+      let pool = FramePool::default();
+      loop {
+        pacer.wait();
+        let frame = pool.take(&self.get_screenshot::<Color>()?);
+        if sender.blocking_send(frame).is_err() {
+          return Ok(());
+        }
+      }
+    });
+    (handle, receiver)
+  }
+}
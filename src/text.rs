@@ -0,0 +1,227 @@
+use {
+  crate::{
+    gdi::{get_dib_bits_into, select_bitmap},
+    wrappers::{CreatedHdcWrapper, HbitmapWrapper, HdcWrapper, HfontWrapper},
+    FrameMeta, Overlay, Result,
+  },
+  std::time::{SystemTime, UNIX_EPOCH},
+  windows::Win32::{
+    Foundation::{COLORREF, HWND, RECT},
+    Graphics::Gdi::{
+      DrawTextW, SelectObject, SetBkColor, SetBkMode, SetTextColor, DT_CALCRECT, DT_NOCLIP,
+      DT_NOPREFIX, OPAQUE,
+    },
+    UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW},
+  },
+};
+
+const DEFAULT_FONT: &str = "Segoe UI";
+const DEFAULT_FONT_HEIGHT: i32 = 16;
+const PADDING: i32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TextContent {
+  Label(String),
+  Timestamp,
+  WindowTitle(HWND),
+}
+
+#[derive(Clone, Debug)]
+pub struct TextOverlay {
+  content: TextContent,
+  x: i32,
+  y: i32,
+  color: [u8; 4],
+  background: Option<[u8; 4]>,
+  font: String,
+  font_height: i32,
+  rendered: Option<(String, Overlay)>,
+}
+
+impl TextOverlay {
+  pub fn label(text: impl Into<String>) -> Self {
+    Self::new(TextContent::Label(text.into()))
+  }
+
+  pub fn timestamp() -> Self {
+    Self::new(TextContent::Timestamp)
+  }
+
+  pub fn window_title(handle: HWND) -> Self {
+    Self::new(TextContent::WindowTitle(handle))
+  }
+
+  fn new(content: TextContent) -> Self {
+    Self {
+      content,
+      x: 0,
+      y: 0,
+      color: [255, 255, 255, 255],
+      background: Some([0, 0, 0, 160]),
+      font: DEFAULT_FONT.to_owned(),
+      font_height: DEFAULT_FONT_HEIGHT,
+      rendered: None,
+    }
+  }
+
+  pub fn with_position(mut self, x: i32, y: i32) -> Self {
+    self.x = x;
+    self.y = y;
+    self.rendered = None;
+    self
+  }
+
+  pub fn position(&self) -> (i32, i32) {
+    (self.x, self.y)
+  }
+
+  pub fn with_color(mut self, rgba: [u8; 4]) -> Self {
+    self.color = rgba;
+    self.rendered = None;
+    self
+  }
+
+  pub fn color(&self) -> [u8; 4] {
+    self.color
+  }
+
+  pub fn with_background(mut self, rgba: Option<[u8; 4]>) -> Self {
+    self.background = rgba;
+    self.rendered = None;
+    self
+  }
+
+  pub fn background(&self) -> Option<[u8; 4]> {
+    self.background
+  }
+
+  pub fn with_font(mut self, face: impl Into<String>, height: i32) -> Self {
+    self.font = face.into();
+    self.font_height = height;
+    self.rendered = None;
+    self
+  }
+
+  pub fn font(&self) -> (&str, i32) {
+    (&self.font, self.font_height)
+  }
+
+  pub(crate) fn blend(&mut self, frame: &mut [u8], width: u32, height: u32, meta: FrameMeta) {
+    let text = match &self.content {
+      TextContent::Label(text) => text.clone(),
+      TextContent::Timestamp => format_timestamp(meta.wall_clock()),
+      TextContent::WindowTitle(handle) => window_title(*handle),
+    };
+    let stale = self
+      .rendered
+      .as_ref()
+      .is_none_or(|(rendered, _)| *rendered != text);
+    if stale {
+      let Ok(overlay) = self.render(&text) else {
+        return;
+      };
+      self.rendered = Some((text, overlay));
+    }
+    if let Some((_, overlay)) = &self.rendered {
+      overlay.blend(frame, width, height);
+    }
+  }
+
+  fn render(&self, text: &str) -> Result<Overlay> {
+    let mut wide: Vec<u16> = text.encode_utf16().collect();
+    if wide.is_empty() {
+      return Overlay::new(0, 0, Vec::new());
+    }
+
+    let screen = HdcWrapper::get_dc(HWND(0))?;
+    let font = HfontWrapper::create(&self.font, self.font_height)?;
+    let hdc = CreatedHdcWrapper::create_compatible_dc(screen.inner())?;
+    let mut bounds = RECT::default();
+    unsafe {
+      SelectObject(hdc.inner(), font.inner());
+      DrawTextW(
+        hdc.inner(),
+        &mut wide,
+        &mut bounds,
+        DT_CALCRECT | DT_NOPREFIX,
+      );
+    }
+    let width = bounds.right + 2 * PADDING;
+    let height = bounds.bottom + 2 * PADDING;
+
+    let hbitmap = HbitmapWrapper::create_compatible_bitmap(screen.inner(), width, height)?;
+    select_bitmap(&hdc, &hbitmap)?;
+    let mut area = RECT {
+      left: PADDING,
+      top: PADDING,
+      right: width - PADDING,
+      bottom: height - PADDING,
+    };
+    unsafe {
+      SetTextColor(hdc.inner(), COLORREF(0x00ff_ffff));
+      SetBkColor(hdc.inner(), COLORREF(0));
+      SetBkMode(hdc.inner(), OPAQUE);
+      DrawTextW(hdc.inner(), &mut wide, &mut area, DT_NOPREFIX | DT_NOCLIP);
+    }
+    let mut coverage = vec![0; 4 * width as usize * height as usize];
+    get_dib_bits_into(&hdc, &hbitmap, width, height, &mut coverage)?;
+
+    let mut image = Vec::with_capacity(coverage.len());
+    for pixel in coverage.chunks_exact(4) {
+      let ink = pixel[0].max(pixel[1]).max(pixel[2]) as u32;
+      let mix =
+        |over: u8, under: u8| ((over as u32 * ink + under as u32 * (255 - ink)) / 255) as u8;
+      match self.background {
+        Some(background) => image.extend([
+          mix(self.color[0], background[0]),
+          mix(self.color[1], background[1]),
+          mix(self.color[2], background[2]),
+          mix(self.color[3], background[3]),
+        ]),
+        None => image.extend([
+          self.color[0],
+          self.color[1],
+          self.color[2],
+          mix(self.color[3], 0),
+        ]),
+      }
+    }
+    Ok(Overlay::new(width as u32, height as u32, image)?.with_position(self.x, self.y))
+  }
+}
+
+fn window_title(handle: HWND) -> String {
+  unsafe {
+    let mut title = vec![0; GetWindowTextLengthW(handle) as usize + 1];
+    let len = GetWindowTextW(handle, &mut title) as usize;
+    String::from_utf16_lossy(&title[..len])
+  }
+}
+
+fn format_timestamp(time: SystemTime) -> String {
+  let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+  let seconds = since_epoch.as_secs();
+  let (days, seconds_of_day) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+  let shifted = days + 719_468;
+  let era = shifted.div_euclid(146_097);
+  let day_of_era = shifted.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = match month_index < 10 {
+    true => month_index + 3,
+    false => month_index - 9,
+  };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+  format!(
+    "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:03} UTC",
+    seconds_of_day / 3600,
+    seconds_of_day / 60 % 60,
+    seconds_of_day % 60,
+    since_epoch.subsec_millis(),
+  )
+}
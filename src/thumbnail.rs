@@ -0,0 +1,118 @@
+use {
+  crate::{check_window, Rect, Result},
+  windows::Win32::{
+    Foundation::{BOOL, HWND},
+    Graphics::Dwm::{
+      DwmQueryThumbnailSourceSize, DwmRegisterThumbnail, DwmUnregisterThumbnail,
+      DwmUpdateThumbnailProperties, DWM_THUMBNAIL_PROPERTIES, DWM_TNP_OPACITY,
+      DWM_TNP_RECTDESTINATION, DWM_TNP_SOURCECLIENTAREAONLY, DWM_TNP_VISIBLE,
+    },
+  },
+};
+
+pub struct WindowThumbnail {
+  thumbnail: isize,
+  source: HWND,
+  destination: HWND,
+  opacity: u8,
+  client_area_only: bool,
+}
+
+impl WindowThumbnail {
+  pub fn register(destination: HWND, source: HWND) -> Result<Self> {
+    check_window(source)?;
+    let thumbnail = unsafe { DwmRegisterThumbnail(destination, source)? };
+    Ok(Self {
+      thumbnail,
+      source,
+      destination,
+      opacity: 255,
+      client_area_only: false,
+    })
+  }
+
+  pub fn source(&self) -> HWND {
+    self.source
+  }
+
+  pub fn destination(&self) -> HWND {
+    self.destination
+  }
+
+  pub fn with_opacity(mut self, opacity: u8) -> Self {
+    self.opacity = opacity;
+    self
+  }
+
+  pub fn opacity(&self) -> u8 {
+    self.opacity
+  }
+
+  pub fn with_client_area_only(mut self, client_area_only: bool) -> Self {
+    self.client_area_only = client_area_only;
+    self
+  }
+
+  pub fn client_area_only(&self) -> bool {
+    self.client_area_only
+  }
+
+  pub fn source_size(&self) -> Result<(u32, u32)> {
+    let size = unsafe { DwmQueryThumbnailSourceSize(self.thumbnail)? };
+    Ok((size.cx.max(0) as u32, size.cy.max(0) as u32))
+  }
+
+  pub fn show(&self, area: Rect) -> Result<()> {
+    self.update(area, true)
+  }
+
+  pub fn show_fitted(&self, area: Rect) -> Result<Rect> {
+    let (width, height) = self.source_size()?;
+    let fitted = match width == 0 || height == 0 {
+      true => area,
+      false => {
+        let scale = (area.width() as f64 / width as f64).min(area.height() as f64 / height as f64);
+        let fitted_width = (width as f64 * scale).round() as i32;
+        let fitted_height = (height as f64 * scale).round() as i32;
+        let left = area.left + (area.width() - fitted_width) / 2;
+        let top = area.top + (area.height() - fitted_height) / 2;
+        Rect {
+          left,
+          top,
+          right: left + fitted_width,
+          bottom: top + fitted_height,
+        }
+      }
+    };
+    self.update(fitted, true)?;
+    Ok(fitted)
+  }
+
+  pub fn hide(&self) -> Result<()> {
+    self.update(Rect::default(), false)
+  }
+
+  fn update(&self, area: Rect, visible: bool) -> Result<()> {
+    let properties = DWM_THUMBNAIL_PROPERTIES {
+      dwFlags: DWM_TNP_RECTDESTINATION
+        | DWM_TNP_VISIBLE
+        | DWM_TNP_OPACITY
+        | DWM_TNP_SOURCECLIENTAREAONLY,
+      rcDestination: area.into(),
+      opacity: self.opacity,
+      fVisible: BOOL::from(visible),
+      fSourceClientAreaOnly: BOOL::from(self.client_area_only),
+      ..Default::default()
+    };
+    unsafe { DwmUpdateThumbnailProperties(self.thumbnail, &properties)? };
+    Ok(())
+  }
+}
+
+impl Drop for WindowThumbnail {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = DwmUnregisterThumbnail(self.thumbnail);
+    }
+  }
+}
@@ -0,0 +1,21 @@
+macro_rules! span {
+  ($($arg:tt)*) => {{
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!($($arg)*).entered();
+    #[cfg(not(feature = "tracing"))]
+    let span = $crate::trace::NoSpan;
+    span
+  }};
+}
+
+macro_rules! event {
+  ($level:ident, $($arg:tt)*) => {
+    #[cfg(feature = "tracing")]
+    tracing::$level!($($arg)*);
+  };
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+pub(crate) use {event, span};
@@ -0,0 +1,419 @@
+use {
+  crate::{
+    color::{f16_channel, f32_to_f16},
+    parallel::for_each_row,
+    PixelFormat, Result, Screenshot, ScreenshotOwned, StreamshotError,
+  },
+  std::{marker::PhantomData, ops::Not},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+  Nearest,
+  #[default]
+  Bilinear,
+  Box,
+}
+
+impl<Color: PixelFormat> Screenshot<'_, Color> {
+  pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<ScreenshotOwned<Color>> {
+    let mut image = Vec::with_capacity(width as usize * height as usize * Color::BYTES_PER_PIXEL);
+    for row in self.region_rows(x, y, width, height)? {
+      image.extend_from_slice(row);
+    }
+    Ok(owned(width, height, image, self))
+  }
+
+  pub fn resize(
+    &self,
+    width: u32,
+    height: u32,
+    filter: ResizeFilter,
+  ) -> Result<ScreenshotOwned<Color>> {
+    if width == 0 || height == 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: width as i32,
+        height: height as i32,
+      });
+    }
+    if width == self.width && height == self.height {
+      return Ok(self.to_owned());
+    }
+
+    let source = Source {
+      image: self.image,
+      width: self.width as usize,
+      height: self.height as usize,
+      stride: self.stride as usize,
+      bytes_per_pixel: Color::BYTES_PER_PIXEL,
+      half_float: Color::HALF_FLOAT,
+    };
+    let (width, height) = (width as usize, height as usize);
+    let mut image = vec![0; width * height * Color::BYTES_PER_PIXEL];
+    let integer_ratio = source.width.is_multiple_of(width) && source.height.is_multiple_of(height);
+    match filter {
+      ResizeFilter::Nearest => source.nearest(&mut image, width, height),
+      ResizeFilter::Box | ResizeFilter::Bilinear if integer_ratio && Color::HALF_FLOAT.not() => {
+        source.box_integer(&mut image, width, height)
+      }
+      ResizeFilter::Box => source.box_area(&mut image, width, height),
+      ResizeFilter::Bilinear => source.bilinear(&mut image, width, height),
+    }
+    Ok(owned(width as u32, height as u32, image, self))
+  }
+
+  pub fn flip_vertical(&self) -> ScreenshotOwned<Color> {
+    let row_len = self.width as usize * Color::BYTES_PER_PIXEL;
+    let mut image = Vec::with_capacity(row_len * self.height as usize);
+    for row in self.image.chunks_exact(self.stride as usize).rev() {
+      image.extend_from_slice(&row[..row_len]);
+    }
+    owned(self.width, self.height, image, self)
+  }
+
+  pub fn flip_horizontal(&self) -> ScreenshotOwned<Color> {
+    let width = self.width;
+    self.remap(width, self.height, move |x, y| (width - 1 - x, y))
+  }
+
+  pub fn rotate90(&self) -> ScreenshotOwned<Color> {
+    let height = self.height;
+    self.remap(height, self.width, move |x, y| (y, height - 1 - x))
+  }
+
+  pub fn rotate180(&self) -> ScreenshotOwned<Color> {
+    let packed = self.packed();
+    let mut image = Vec::with_capacity(packed.len());
+    for pixel in packed.chunks_exact(Color::BYTES_PER_PIXEL).rev() {
+      image.extend_from_slice(pixel);
+    }
+    owned(self.width, self.height, image, self)
+  }
+
+  pub fn rotate270(&self) -> ScreenshotOwned<Color> {
+    let width = self.width;
+    self.remap(self.height, width, move |x, y| (width - 1 - y, x))
+  }
+
+  fn remap(
+    &self,
+    width: u32,
+    height: u32,
+    source: impl Fn(u32, u32) -> (u32, u32) + Sync,
+  ) -> ScreenshotOwned<Color> {
+    let bpp = Color::BYTES_PER_PIXEL;
+    let (pixels, stride) = (self.image, self.stride as usize);
+    let mut image = vec![0; width as usize * height as usize * bpp];
+    for_each_row(&mut image, width as usize * bpp, |y, row| {
+      for (x, target) in row.chunks_exact_mut(bpp).enumerate() {
+        let (source_x, source_y) = source(x as u32, y as u32);
+        let start = source_y as usize * stride + source_x as usize * bpp;
+        target.copy_from_slice(&pixels[start..start + bpp]);
+      }
+    });
+    owned(width, height, image, self)
+  }
+}
+
+impl<Color: PixelFormat> ScreenshotOwned<Color> {
+  pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<ScreenshotOwned<Color>> {
+    self.as_screenshot().crop(x, y, width, height)
+  }
+
+  pub fn resize(
+    &self,
+    width: u32,
+    height: u32,
+    filter: ResizeFilter,
+  ) -> Result<ScreenshotOwned<Color>> {
+    self.as_screenshot().resize(width, height, filter)
+  }
+
+  pub fn flip_vertical(&self) -> ScreenshotOwned<Color> {
+    self.as_screenshot().flip_vertical()
+  }
+
+  pub fn flip_horizontal(&self) -> ScreenshotOwned<Color> {
+    self.as_screenshot().flip_horizontal()
+  }
+
+  pub fn rotate90(&self) -> ScreenshotOwned<Color> {
+    self.as_screenshot().rotate90()
+  }
+
+  pub fn rotate180(&self) -> ScreenshotOwned<Color> {
+    self.as_screenshot().rotate180()
+  }
+
+  pub fn rotate270(&self) -> ScreenshotOwned<Color> {
+    self.as_screenshot().rotate270()
+  }
+}
+
+struct Source<'a> {
+  image: &'a [u8],
+  width: usize,
+  height: usize,
+  stride: usize,
+  bytes_per_pixel: usize,
+  half_float: bool,
+}
+
+impl Source<'_> {
+  fn channels(&self) -> usize {
+    match self.half_float {
+      true => 4,
+      false => self.bytes_per_pixel,
+    }
+  }
+
+  fn pixel(&self, x: usize, y: usize) -> &[u8] {
+    let start = y * self.stride + x * self.bytes_per_pixel;
+    &self.image[start..start + self.bytes_per_pixel]
+  }
+
+  fn channel(&self, pixel: &[u8], index: usize) -> f32 {
+    match self.half_float {
+      true => f16_channel(pixel, index),
+      false => pixel[index] as f32,
+    }
+  }
+
+  fn store(&self, pixel: &mut [u8], index: usize, value: f32) {
+    match self.half_float {
+      true => pixel[2 * index..2 * index + 2].copy_from_slice(&f32_to_f16(value).to_le_bytes()),
+      false => pixel[index] = value.round().clamp(0.0, 255.0) as u8,
+    }
+  }
+
+  fn nearest(&self, image: &mut [u8], width: usize, height: usize) {
+    let columns: Vec<usize> = (0..width)
+      .map(|x| (2 * x + 1) * self.width / (2 * width))
+      .collect();
+    for_each_row(image, width * self.bytes_per_pixel, |y, row| {
+      let source_y = (2 * y + 1) * self.height / (2 * height);
+      for (target, &source_x) in row.chunks_exact_mut(self.bytes_per_pixel).zip(&columns) {
+        target.copy_from_slice(self.pixel(source_x, source_y));
+      }
+    });
+  }
+
+  fn box_integer(&self, image: &mut [u8], width: usize, height: usize) {
+    let (step_x, step_y) = (self.width / width, self.height / height);
+    let area = (step_x * step_y) as u32;
+    let bpp = self.bytes_per_pixel;
+    for_each_row(image, width * bpp, |y, row| {
+      let mut sums = vec![0u32; width * bpp];
+      for source_y in y * step_y..(y + 1) * step_y {
+        let start = source_y * self.stride;
+        let line = &self.image[start..start + self.width * bpp];
+        for (sum, pixels) in sums
+          .chunks_exact_mut(bpp)
+          .zip(line.chunks_exact(step_x * bpp))
+        {
+          for pixel in pixels.chunks_exact(bpp) {
+            for (sum, &value) in sum.iter_mut().zip(pixel) {
+              *sum += value as u32;
+            }
+          }
+        }
+      }
+      for (target, sum) in row.iter_mut().zip(sums) {
+        *target = ((sum + area / 2) / area) as u8;
+      }
+    });
+  }
+
+  fn box_area(&self, image: &mut [u8], width: usize, height: usize) {
+    let span = |index: usize, target: usize, source: usize| {
+      let start = index * source / target;
+      let end = ((index + 1) * source).div_ceil(target).max(start + 1);
+      start..end.min(source)
+    };
+    let channels = self.channels();
+    for_each_row(image, width * self.bytes_per_pixel, |y, row| {
+      let rows = span(y, height, self.height);
+      for (x, target) in row.chunks_exact_mut(self.bytes_per_pixel).enumerate() {
+        let columns = span(x, width, self.width);
+        let count = (rows.len() * columns.len()) as f32;
+        for index in 0..channels {
+          let mut sum = 0.0;
+          for source_y in rows.clone() {
+            for source_x in columns.clone() {
+              sum += self.channel(self.pixel(source_x, source_y), index);
+            }
+          }
+          self.store(target, index, sum / count);
+        }
+      }
+    });
+  }
+
+  fn bilinear(&self, image: &mut [u8], width: usize, height: usize) {
+    let sample = |index: usize, target: usize, source: usize| {
+      let position = ((index as f32 + 0.5) * source as f32 / target as f32 - 0.5)
+        .clamp(0.0, (source - 1) as f32);
+      let low = position as usize;
+      (low, (low + 1).min(source - 1), position - low as f32)
+    };
+    let columns: Vec<_> = (0..width).map(|x| sample(x, width, self.width)).collect();
+    let channels = self.channels();
+    for_each_row(image, width * self.bytes_per_pixel, |y, row| {
+      let (top, bottom, weight_y) = sample(y, height, self.height);
+      for (target, &(left, right, weight_x)) in
+        row.chunks_exact_mut(self.bytes_per_pixel).zip(&columns)
+      {
+        let corners = [
+          self.pixel(left, top),
+          self.pixel(right, top),
+          self.pixel(left, bottom),
+          self.pixel(right, bottom),
+        ];
+        for index in 0..channels {
+          let [a, b, c, d] = corners.map(|pixel| self.channel(pixel, index));
+          let upper = a + (b - a) * weight_x;
+          let lower = c + (d - c) * weight_x;
+          self.store(target, index, upper + (lower - upper) * weight_y);
+        }
+      }
+    });
+  }
+}
+
+fn owned<Color: PixelFormat>(
+  width: u32,
+  height: u32,
+  image: Vec<u8>,
+  source: &Screenshot<'_, Color>,
+) -> ScreenshotOwned<Color> {
+  ScreenshotOwned {
+    width,
+    height,
+    stride: width * Color::BYTES_PER_PIXEL as u32,
+    image,
+    meta: source.meta,
+    pool: None,
+    marker: PhantomData,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{FrameBuffer, ResizeFilter, ScreenshotOwned, BGRA};
+
+  fn labeled(width: i32, height: i32, labels: &[u8]) -> FrameBuffer {
+    let mut frame = FrameBuffer::new(width, height);
+    for (pixel, &label) in frame.buffer_mut().chunks_exact_mut(4).zip(labels) {
+      pixel.copy_from_slice(&[label, label, label, 255]);
+    }
+    frame
+  }
+
+  fn labels(screenshot: &ScreenshotOwned<BGRA>) -> (u32, u32, Vec<u8>) {
+    let labels = screenshot.chunks_exact(4).map(|pixel| pixel[0]).collect();
+    (screenshot.width(), screenshot.height(), labels)
+  }
+
+  #[test]
+  fn crops_inside_the_frame() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let screenshot = frame.bgr_screenshot();
+    let cropped = screenshot.crop(1, 0, 2, 2).unwrap();
+    assert_eq!(labels(&cropped), (2, 2, vec![2, 3, 5, 6]));
+    assert_eq!(labels(&cropped.crop(1, 1, 1, 1).unwrap()), (1, 1, vec![6]));
+  }
+
+  #[test]
+  fn crop_rejects_regions_outside_the_frame() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let screenshot = frame.bgr_screenshot();
+    assert!(screenshot.crop(2, 1, 2, 1).is_err());
+    assert!(screenshot.crop(0, 0, 0, 1).is_err());
+    assert!(screenshot.crop(u32::MAX, 0, 2, 1).is_err());
+  }
+
+  #[test]
+  fn nearest_upscales_by_repeating_pixels() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let resized = frame
+      .bgr_screenshot()
+      .resize(6, 4, ResizeFilter::Nearest)
+      .unwrap();
+    assert_eq!(
+      labels(&resized),
+      (
+        6,
+        4,
+        vec![1, 1, 2, 2, 3, 3, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 4, 4, 5, 5, 6, 6]
+      )
+    );
+  }
+
+  #[test]
+  fn box_averages_integer_downscales() {
+    let mut frame = labeled(4, 2, &[10, 20, 30, 40, 50, 60, 70, 80]);
+    let screenshot = frame.bgr_screenshot();
+    for filter in [ResizeFilter::Box, ResizeFilter::Bilinear] {
+      let resized = screenshot.resize(2, 1, filter).unwrap();
+      assert_eq!(labels(&resized), (2, 1, vec![35, 55]));
+    }
+  }
+
+  #[test]
+  fn bilinear_keeps_flat_colors() {
+    let mut frame = labeled(3, 3, &[100; 9]);
+    let resized = frame
+      .bgr_screenshot()
+      .resize(2, 5, ResizeFilter::Bilinear)
+      .unwrap();
+    assert_eq!(labels(&resized), (2, 5, vec![100; 10]));
+  }
+
+  #[test]
+  fn resize_rejects_empty_sizes() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let screenshot = frame.bgr_screenshot();
+    assert!(screenshot.resize(0, 1, ResizeFilter::Bilinear).is_err());
+    let same = screenshot.resize(3, 2, ResizeFilter::Box).unwrap();
+    assert_eq!(labels(&same), (3, 2, vec![1, 2, 3, 4, 5, 6]));
+  }
+
+  #[test]
+  fn flips_and_rotates() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let screenshot = frame.bgr_screenshot();
+    assert_eq!(
+      labels(&screenshot.flip_vertical()),
+      (3, 2, vec![4, 5, 6, 1, 2, 3])
+    );
+    assert_eq!(
+      labels(&screenshot.flip_horizontal()),
+      (3, 2, vec![3, 2, 1, 6, 5, 4])
+    );
+    assert_eq!(
+      labels(&screenshot.rotate90()),
+      (2, 3, vec![4, 1, 5, 2, 6, 3])
+    );
+    assert_eq!(
+      labels(&screenshot.rotate180()),
+      (3, 2, vec![6, 5, 4, 3, 2, 1])
+    );
+    assert_eq!(
+      labels(&screenshot.rotate270()),
+      (2, 3, vec![3, 6, 2, 5, 1, 4])
+    );
+  }
+
+  #[test]
+  fn opposite_rotations_cancel_out() {
+    let mut frame = labeled(3, 2, &[1, 2, 3, 4, 5, 6]);
+    let screenshot = frame.bgr_screenshot();
+    let original = (3, 2, vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(labels(&screenshot.rotate90().rotate270()), original);
+    assert_eq!(labels(&screenshot.rotate180().rotate180()), original);
+    assert_eq!(
+      labels(&screenshot.flip_horizontal().flip_horizontal()),
+      original
+    );
+  }
+}
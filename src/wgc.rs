@@ -0,0 +1,267 @@
+use {
+  crate::{
+    check_affinity, check_window, d3d::D3dReadback, occlusion::window_visibility, window_monitor,
+    Capture, FrameBuffer, GpuCapture, GpuFrame, Result, SharedTexture, SharedTextureKind,
+    StreamshotError, Visibility, YuvFormat, YuvFrame, YuvMatrix,
+  },
+  std::{
+    ops::Not,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+  },
+  windows::{
+    core::{factory, ComInterface},
+    Foundation::TypedEventHandler,
+    Graphics::{
+      Capture::{
+        Direct3D11CaptureFrame, Direct3D11CaptureFramePool, GraphicsCaptureItem,
+        GraphicsCaptureSession,
+      },
+      DirectX::{Direct3D11::IDirect3DDevice, DirectXPixelFormat},
+      SizeInt32,
+    },
+    Win32::{
+      Foundation::HWND,
+      Graphics::{Direct3D11::ID3D11Texture2D, Dxgi::IDXGIDevice, Gdi::HMONITOR},
+      System::WinRT::{
+        Direct3D11::{CreateDirect3D11DeviceFromDXGIDevice, IDirect3DDxgiInterfaceAccess},
+        Graphics::Capture::IGraphicsCaptureItemInterop,
+      },
+    },
+  },
+};
+
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+const FRAME_POOL_BUFFERS: i32 = 2;
+const SDR_PIXEL_FORMAT: DirectXPixelFormat = DirectXPixelFormat::B8G8R8A8UIntNormalized;
+const HDR_PIXEL_FORMAT: DirectXPixelFormat = DirectXPixelFormat::R16G16B16A16Float;
+
+pub struct WgcCaptureBuffer {
+  handle: HWND,
+  readback: D3dReadback,
+  device: IDirect3DDevice,
+  frame_pool: Direct3D11CaptureFramePool,
+  session: GraphicsCaptureSession,
+  pixel_format: DirectXPixelFormat,
+  arrived: Receiver<()>,
+  yuv_size: Option<(u32, u32)>,
+  has_frame: bool,
+  frame: FrameBuffer,
+}
+
+impl WgcCaptureBuffer {
+  pub fn new(handle: HWND) -> Result<Self> {
+    if GraphicsCaptureSession::IsSupported()?.not() {
+      return Err(StreamshotError::Unsupported(
+        "Windows.Graphics.Capture is not available on this system",
+      ));
+    }
+
+    let readback = D3dReadback::new(None)?;
+    let dxgi_device: IDXGIDevice = readback.device().cast()?;
+    let device: IDirect3DDevice =
+      unsafe { CreateDirect3D11DeviceFromDXGIDevice(&dxgi_device)? }.cast()?;
+
+    let interop = factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
+    let item: GraphicsCaptureItem = unsafe { interop.CreateForWindow(handle)? };
+    let size = item.Size()?;
+    if size.Width <= 0 || size.Height <= 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: size.Width,
+        height: size.Height,
+      });
+    }
+
+    let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
+      &device,
+      SDR_PIXEL_FORMAT,
+      FRAME_POOL_BUFFERS,
+      size,
+    )?;
+    let (sender, arrived) = mpsc::channel();
+    frame_pool.FrameArrived(&TypedEventHandler::new(move |_, _| {
+      let _ = sender.send(());
+      Ok(())
+    }))?;
+
+    let session = frame_pool.CreateCaptureSession(&item)?;
+    let _ = session.SetIsCursorCaptureEnabled(false);
+    session.StartCapture()?;
+
+    Ok(Self {
+      handle,
+      readback,
+      device,
+      frame_pool,
+      session,
+      pixel_format: SDR_PIXEL_FORMAT,
+      arrived,
+      yuv_size: None,
+      has_frame: false,
+      frame: FrameBuffer::new(size.Width, size.Height),
+    })
+  }
+}
+
+impl WgcCaptureBuffer {
+  pub fn with_cursor(self, cursor: bool) -> Result<Self> {
+    self.session.SetIsCursorCaptureEnabled(cursor)?;
+    Ok(self)
+  }
+
+  pub fn with_hdr(mut self, hdr: bool) -> Result<Self> {
+    self.pixel_format = match hdr {
+      true => HDR_PIXEL_FORMAT,
+      false => SDR_PIXEL_FORMAT,
+    };
+    let size = SizeInt32 {
+      Width: self.frame.width() as i32,
+      Height: self.frame.height() as i32,
+    };
+    self
+      .frame_pool
+      .Recreate(&self.device, self.pixel_format, FRAME_POOL_BUFFERS, size)?;
+    self.readback.reset();
+    self.has_frame = false;
+    Ok(self)
+  }
+
+  pub fn is_hdr(&self) -> bool {
+    self.pixel_format == HDR_PIXEL_FORMAT
+  }
+
+  pub fn with_shared_texture(mut self, kind: SharedTextureKind) -> Self {
+    self.readback.set_shared(Some(kind));
+    self
+  }
+
+  pub fn shared_texture(&self) -> Option<SharedTexture> {
+    self.readback.shared_texture()
+  }
+
+  pub fn with_yuv_size(mut self, width: u32, height: u32) -> Self {
+    self.yuv_size = Some((width, height));
+    self
+  }
+
+  pub fn yuv_size(&self) -> Option<(u32, u32)> {
+    self.yuv_size
+  }
+
+  fn read_yuv(&mut self, format: YuvFormat, matrix: YuvMatrix) -> Result<YuvFrame> {
+    if self.is_hdr() {
+      return YuvFrame::from_bgra(self, format, matrix);
+    }
+    self.read_texture()?;
+    self.readback.convert_yuv(self.yuv_size, format, matrix)
+  }
+
+  fn next_frame(&mut self) -> Result<Option<(Direct3D11CaptureFrame, ID3D11Texture2D)>> {
+    check_window(self.handle)?;
+    check_affinity(self.handle)?;
+    if self.has_frame.not() {
+      let _ = self.arrived.recv_timeout(FIRST_FRAME_TIMEOUT);
+    }
+    while self.arrived.try_recv().is_ok() {}
+
+    let mut latest = None;
+    while let Ok(frame) = self.frame_pool.TryGetNextFrame() {
+      latest = Some(frame);
+    }
+    let frame = match latest {
+      Some(frame) => frame,
+      None => return Ok(None),
+    };
+
+    let size = frame.ContentSize()?;
+    if size.Width <= 0 || size.Height <= 0 {
+      return Err(StreamshotError::InvalidSize {
+        width: size.Width,
+        height: size.Height,
+      });
+    }
+    if size.Width != self.frame.width() as i32 || size.Height != self.frame.height() as i32 {
+      self
+        .frame_pool
+        .Recreate(&self.device, self.pixel_format, FRAME_POOL_BUFFERS, size)?;
+      self.frame.resize(size.Width, size.Height);
+    }
+
+    let access: IDirect3DDxgiInterfaceAccess = frame.Surface()?.cast()?;
+    let texture: ID3D11Texture2D = unsafe { access.GetInterface()? };
+    Ok(Some((frame, texture)))
+  }
+}
+
+impl Capture for WgcCaptureBuffer {
+  fn read(&mut self) -> Result<()> {
+    let (frame, texture) = match (self.next_frame()?, self.has_frame) {
+      (Some(next), _) => next,
+      (None, true) => {
+        self.frame.mark_stale();
+        return Ok(());
+      }
+      (None, false) => return Err(StreamshotError::Timeout(FIRST_FRAME_TIMEOUT)),
+    };
+    let (width, height) = (self.frame.width() as i32, self.frame.height() as i32);
+    self.readback.export_shared(&texture, 0, 0, width, height)?;
+    self
+      .readback
+      .copy_to_frame(&texture, 0, 0, &mut self.frame)?;
+    frame.Close()?;
+    self.frame.set_monitor(window_monitor(self.handle));
+
+    self.has_frame = true;
+    Ok(())
+  }
+
+  fn frame_buffer(&mut self) -> &mut FrameBuffer {
+    &mut self.frame
+  }
+
+  fn visibility(&self) -> Result<Visibility> {
+    window_visibility(self.handle)
+  }
+
+  fn monitor(&self) -> Option<HMONITOR> {
+    Some(window_monitor(self.handle))
+  }
+
+  fn get_nv12_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    self.read_yuv(YuvFormat::Nv12, matrix)
+  }
+
+  fn get_i420_frame(&mut self, matrix: YuvMatrix) -> Result<YuvFrame> {
+    self.read_yuv(YuvFormat::I420, matrix)
+  }
+}
+
+impl GpuCapture for WgcCaptureBuffer {
+  fn read_texture(&mut self) -> Result<GpuFrame<'_>> {
+    if let Some((frame, texture)) = self.next_frame()? {
+      let (width, height) = (self.frame.width() as i32, self.frame.height() as i32);
+      self.readback.export_shared(&texture, 0, 0, width, height)?;
+      self
+        .readback
+        .copy_to_texture(&texture, 0, 0, width, height)?;
+      frame.Close()?;
+      self.has_frame = true;
+      self.frame.stamp();
+    }
+    self
+      .readback
+      .gpu_frame(self.frame.meta())
+      .ok_or(StreamshotError::Timeout(FIRST_FRAME_TIMEOUT))
+  }
+}
+
+// SAFETY: the frame pool is free-threaded, the WinRT objects are agile and the D3D11
+// device and immediate context are only ever used by the thread that owns the buffer.
+unsafe impl Send for WgcCaptureBuffer {}
+
+impl Drop for WgcCaptureBuffer {
+  fn drop(&mut self) {
+    let _ = self.session.Close();
+    let _ = self.frame_pool.Close();
+  }
+}
@@ -1,24 +1,78 @@
-use windows::{
-  core::Error,
-  Win32::{
-    Foundation::HWND,
-    Graphics::Gdi::{
-      CreateCompatibleBitmap, CreateCompatibleDC, CreatedHDC, DeleteDC, DeleteObject, GetDC,
-      ReleaseDC, HBITMAP, HDC,
+use {
+  crate::{Result, StreamshotError},
+  std::{marker::PhantomData, mem::size_of, ops::Not, ptr},
+  windows::{
+    core::{Error, HSTRING},
+    Win32::{
+      Foundation::{CloseHandle, COLORREF, HANDLE, HWND, RECT},
+      Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, CreateDIBSection, CreateFontW,
+        CreateRectRgnIndirect, CreatedHDC, DeleteDC, DeleteObject, GetDC, GetWindowDC, ReleaseDC,
+        ANTIALIASED_QUALITY, BITMAPINFO, CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DEFAULT_PITCH,
+        DIB_RGB_COLORS, FF_DONTCARE, FW_NORMAL, HBITMAP, HDC, HFONT, HRGN, OUT_DEFAULT_PRECIS,
+      },
+      Media::{timeBeginPeriod, timeEndPeriod, TIMERR_NOERROR},
+      System::{
+        Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED},
+        Memory::{
+          MapViewOfFile, UnmapViewOfFile, VirtualQuery, FILE_MAP, MEMORYMAPPEDVIEW_HANDLE,
+          MEMORY_BASIC_INFORMATION,
+        },
+        StationsAndDesktops::{
+          CloseDesktop, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS, HDESK,
+        },
+        Threading::{
+          CreateWaitableTimerExW, OpenProcess, CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+          PROCESS_ACCESS_RIGHTS, TIMER_ALL_ACCESS,
+        },
+      },
+      UI::{
+        Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK, WINEVENTPROC},
+        HiDpi::{
+          SetThreadDpiAwarenessContext, DPI_AWARENESS_CONTEXT,
+          DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+        },
+        WindowsAndMessaging::{
+          GetLayeredWindowAttributes, GetWindowLongW, SetLayeredWindowAttributes, SetWindowLongW,
+          ShowWindow, GWL_EXSTYLE, LAYERED_WINDOW_ATTRIBUTES_FLAGS, LWA_ALPHA, SW_SHOWMINNOACTIVE,
+          SW_SHOWNOACTIVATE, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS, WS_EX_LAYERED,
+        },
+      },
+    },
+  },
+};
+#[cfg(any(feature = "ndi", feature = "webcam"))]
+use {
+  std::{ffi::CStr, mem, path::Path},
+  windows::{
+    core::PCSTR,
+    Win32::{
+      Foundation::HMODULE,
+      System::LibraryLoader::{FreeLibrary, GetProcAddress, LoadLibraryW},
     },
   },
 };
 
 pub(crate) struct HdcWrapper {
+  hwnd: HWND,
   inner: HDC,
 }
 
 impl HdcWrapper {
-  pub(crate) fn get_dc(hwnd: HWND) -> Result<HdcWrapper, Error> {
+  pub(crate) fn get_dc(hwnd: HWND) -> Result<HdcWrapper> {
     unsafe {
       match GetDC(hwnd) {
-        e if e.is_invalid() => Err(Error::from_win32()),
-        hdc => Ok(HdcWrapper { inner: hdc }),
+        e if e.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
+        hdc => Ok(HdcWrapper { hwnd, inner: hdc }),
+      }
+    }
+  }
+
+  pub(crate) fn get_window_dc(hwnd: HWND) -> Result<HdcWrapper> {
+    unsafe {
+      match GetWindowDC(hwnd) {
+        e if e.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
+        hdc => Ok(HdcWrapper { hwnd, inner: hdc }),
       }
     }
   }
@@ -31,7 +85,7 @@ impl HdcWrapper {
 impl Drop for HdcWrapper {
   fn drop(&mut self) {
     unsafe {
-      ReleaseDC(HWND::default(), self.inner);
+      ReleaseDC(self.hwnd, self.inner);
     }
   }
 }
@@ -41,10 +95,10 @@ pub(crate) struct CreatedHdcWrapper {
 }
 
 impl CreatedHdcWrapper {
-  pub(crate) fn create_compatible_dc(hdc: HDC) -> Result<CreatedHdcWrapper, Error> {
+  pub(crate) fn create_compatible_dc(hdc: HDC) -> Result<CreatedHdcWrapper> {
     unsafe {
       match CreateCompatibleDC(hdc) {
-        error if error.is_invalid() => Err(Error::from_win32()),
+        error if error.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
         hdc => Ok(CreatedHdcWrapper { inner: hdc }),
       }
     }
@@ -68,19 +122,38 @@ pub(crate) struct HbitmapWrapper {
 }
 
 impl HbitmapWrapper {
-  pub(crate) fn create_compatible_bitmap(
-    hdc: HDC,
-    w: i32,
-    h: i32,
-  ) -> Result<HbitmapWrapper, Error> {
+  pub(crate) fn create_compatible_bitmap(hdc: HDC, w: i32, h: i32) -> Result<HbitmapWrapper> {
     unsafe {
       match CreateCompatibleBitmap(hdc, w, h) {
-        e if e.is_invalid() => Err(Error::from_win32()),
+        e if e.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
         hbitmap => Ok(HbitmapWrapper { inner: hbitmap }),
       }
     }
   }
 
+  pub(crate) fn create_dib_section(
+    hdc: HDC,
+    info: &BITMAPINFO,
+  ) -> Result<(HbitmapWrapper, *mut u8)> {
+    let mut bits = ptr::null_mut();
+    unsafe {
+      match CreateDIBSection(hdc, info, DIB_RGB_COLORS, &mut bits, HANDLE::default(), 0) {
+        Ok(hbitmap) if bits.is_null().not() => {
+          Ok((HbitmapWrapper { inner: hbitmap }, bits as *mut u8))
+        }
+        Ok(hbitmap) => {
+          DeleteObject(hbitmap);
+          Err(StreamshotError::GdiFailure(Error::from_win32()))
+        }
+        Err(error) => Err(StreamshotError::GdiFailure(error)),
+      }
+    }
+  }
+
+  pub(crate) fn from_raw(hbitmap: HBITMAP) -> HbitmapWrapper {
+    HbitmapWrapper { inner: hbitmap }
+  }
+
   pub(crate) fn inner(&self) -> HBITMAP {
     self.inner
   }
@@ -93,3 +166,382 @@ impl Drop for HbitmapWrapper {
     }
   }
 }
+
+pub(crate) struct HrgnWrapper {
+  inner: HRGN,
+}
+
+impl HrgnWrapper {
+  pub(crate) fn create(rect: &RECT) -> Result<HrgnWrapper> {
+    unsafe {
+      match CreateRectRgnIndirect(rect) {
+        e if e.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
+        hrgn => Ok(HrgnWrapper { inner: hrgn }),
+      }
+    }
+  }
+
+  pub(crate) fn inner(&self) -> HRGN {
+    self.inner
+  }
+}
+
+impl Drop for HrgnWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      DeleteObject(self.inner);
+    }
+  }
+}
+
+pub(crate) struct HfontWrapper {
+  inner: HFONT,
+}
+
+impl HfontWrapper {
+  pub(crate) fn create(face: &str, height: i32) -> Result<HfontWrapper> {
+    unsafe {
+      let font = CreateFontW(
+        -height,
+        0,
+        0,
+        0,
+        FW_NORMAL.0 as i32,
+        0,
+        0,
+        0,
+        DEFAULT_CHARSET.0 as u32,
+        OUT_DEFAULT_PRECIS.0 as u32,
+        CLIP_DEFAULT_PRECIS.0 as u32,
+        ANTIALIASED_QUALITY.0 as u32,
+        (DEFAULT_PITCH.0 | FF_DONTCARE.0) as u32,
+        &HSTRING::from(face),
+      );
+      match font {
+        e if e.is_invalid() => Err(StreamshotError::GdiFailure(Error::from_win32())),
+        font => Ok(HfontWrapper { inner: font }),
+      }
+    }
+  }
+
+  pub(crate) fn inner(&self) -> HFONT {
+    self.inner
+  }
+}
+
+impl Drop for HfontWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      DeleteObject(self.inner);
+    }
+  }
+}
+
+pub(crate) struct PerMonitorDpiScope {
+  previous: DPI_AWARENESS_CONTEXT,
+}
+
+impl PerMonitorDpiScope {
+  pub(crate) fn enter() -> PerMonitorDpiScope {
+    let previous =
+      unsafe { SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+    PerMonitorDpiScope { previous }
+  }
+}
+
+impl Drop for PerMonitorDpiScope {
+  fn drop(&mut self) {
+    if self.previous.0 != 0 {
+      unsafe {
+        SetThreadDpiAwarenessContext(self.previous);
+      }
+    }
+  }
+}
+
+pub(crate) struct ComScope {
+  initialized: bool,
+  _thread: PhantomData<*const ()>,
+}
+
+impl ComScope {
+  pub(crate) fn enter() -> ComScope {
+    let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+    ComScope {
+      initialized,
+      _thread: PhantomData,
+    }
+  }
+}
+
+impl Drop for ComScope {
+  fn drop(&mut self) {
+    if self.initialized {
+      unsafe {
+        CoUninitialize();
+      }
+    }
+  }
+}
+
+pub(crate) struct HandleWrapper {
+  inner: HANDLE,
+}
+
+impl HandleWrapper {
+  pub(crate) fn open_process(access: PROCESS_ACCESS_RIGHTS, pid: u32) -> Result<HandleWrapper> {
+    unsafe {
+      let handle = OpenProcess(access, false, pid)?;
+      Ok(HandleWrapper { inner: handle })
+    }
+  }
+
+  pub(crate) fn from_handle(handle: HANDLE) -> Result<HandleWrapper> {
+    match handle.is_invalid() {
+      true => Err(Error::from_win32().into()),
+      false => Ok(HandleWrapper { inner: handle }),
+    }
+  }
+
+  pub(crate) fn high_resolution_timer() -> Result<HandleWrapper> {
+    unsafe {
+      let handle = CreateWaitableTimerExW(
+        None,
+        None,
+        CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+        TIMER_ALL_ACCESS.0,
+      )?;
+      Ok(HandleWrapper { inner: handle })
+    }
+  }
+
+  pub(crate) fn inner(&self) -> HANDLE {
+    self.inner
+  }
+}
+
+impl Drop for HandleWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      CloseHandle(self.inner);
+    }
+  }
+}
+
+pub(crate) struct MappedView {
+  inner: MEMORYMAPPEDVIEW_HANDLE,
+}
+
+impl MappedView {
+  pub(crate) fn map(mapping: &HandleWrapper, access: FILE_MAP) -> Result<MappedView> {
+    unsafe {
+      let view = MapViewOfFile(mapping.inner(), access, 0, 0, 0)?;
+      Ok(MappedView { inner: view })
+    }
+  }
+
+  pub(crate) fn as_ptr(&self) -> *mut u8 {
+    self.inner.0 as *mut u8
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    let mut info = MEMORY_BASIC_INFORMATION::default();
+    let written = unsafe {
+      VirtualQuery(
+        Some(self.inner.0 as *const _),
+        &mut info,
+        size_of::<MEMORY_BASIC_INFORMATION>(),
+      )
+    };
+    match written {
+      0 => 0,
+      _ => info.RegionSize,
+    }
+  }
+}
+
+impl Drop for MappedView {
+  fn drop(&mut self) {
+    unsafe {
+      UnmapViewOfFile(self.inner);
+    }
+  }
+}
+
+#[cfg(any(feature = "ndi", feature = "webcam"))]
+pub(crate) struct LibraryWrapper {
+  inner: HMODULE,
+}
+
+#[cfg(any(feature = "ndi", feature = "webcam"))]
+impl LibraryWrapper {
+  pub(crate) fn load(path: &Path) -> Result<LibraryWrapper> {
+    unsafe {
+      let module = LoadLibraryW(&HSTRING::from(&*path.to_string_lossy()))?;
+      Ok(LibraryWrapper { inner: module })
+    }
+  }
+
+  pub(crate) unsafe fn symbol<T: Copy>(&self, name: &CStr) -> Option<T> {
+    let address = GetProcAddress(self.inner, PCSTR(name.as_ptr() as *const u8))?;
+    Some(mem::transmute_copy(&address))
+  }
+}
+
+#[cfg(any(feature = "ndi", feature = "webcam"))]
+impl Drop for LibraryWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      FreeLibrary(self.inner);
+    }
+  }
+}
+
+pub(crate) struct TimerPeriod {
+  period: u32,
+}
+
+impl TimerPeriod {
+  pub(crate) fn begin(period: u32) -> Option<TimerPeriod> {
+    unsafe {
+      match timeBeginPeriod(period) {
+        TIMERR_NOERROR => Some(TimerPeriod { period }),
+        _ => None,
+      }
+    }
+  }
+}
+
+impl Drop for TimerPeriod {
+  fn drop(&mut self) {
+    unsafe {
+      timeEndPeriod(self.period);
+    }
+  }
+}
+
+pub(crate) struct RestoredWindow {
+  hwnd: HWND,
+  ex_style: i32,
+  attributes: Option<(COLORREF, u8, LAYERED_WINDOW_ATTRIBUTES_FLAGS)>,
+}
+
+impl RestoredWindow {
+  pub(crate) fn restore(hwnd: HWND) -> Result<RestoredWindow> {
+    unsafe {
+      let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+      let restored = match ex_style as u32 & WS_EX_LAYERED.0 {
+        0 => {
+          SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_LAYERED.0 as i32);
+          if SetLayeredWindowAttributes(hwnd, COLORREF(0), 1, LWA_ALPHA) == false {
+            SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style);
+            return Err(Error::from_win32().into());
+          }
+          RestoredWindow {
+            hwnd,
+            ex_style,
+            attributes: None,
+          }
+        }
+        _ => {
+          let mut key = COLORREF(0);
+          let mut alpha = 255;
+          let mut flags = LAYERED_WINDOW_ATTRIBUTES_FLAGS(0);
+          let attributes =
+            GetLayeredWindowAttributes(hwnd, Some(&mut key), Some(&mut alpha), Some(&mut flags))
+              .as_bool()
+              .then_some((key, alpha, flags));
+          if attributes.is_some()
+            && SetLayeredWindowAttributes(hwnd, key, 1, flags | LWA_ALPHA) == false
+          {
+            return Err(Error::from_win32().into());
+          }
+          RestoredWindow {
+            hwnd,
+            ex_style,
+            attributes,
+          }
+        }
+      };
+      ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+      Ok(restored)
+    }
+  }
+}
+
+impl Drop for RestoredWindow {
+  fn drop(&mut self) {
+    unsafe {
+      ShowWindow(self.hwnd, SW_SHOWMINNOACTIVE);
+      match self.attributes {
+        Some((key, alpha, flags)) => {
+          SetLayeredWindowAttributes(self.hwnd, key, alpha, flags);
+        }
+        None => {
+          SetWindowLongW(self.hwnd, GWL_EXSTYLE, self.ex_style);
+        }
+      }
+    }
+  }
+}
+
+pub(crate) struct WinEventHookWrapper {
+  inner: HWINEVENTHOOK,
+}
+
+impl WinEventHookWrapper {
+  pub(crate) fn set(
+    min: u32,
+    max: u32,
+    callback: WINEVENTPROC,
+    pid: u32,
+  ) -> Result<WinEventHookWrapper> {
+    unsafe {
+      match SetWinEventHook(
+        min,
+        max,
+        None,
+        callback,
+        pid,
+        0,
+        WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+      ) {
+        hook if hook.is_invalid() => Err(Error::from_win32().into()),
+        hook => Ok(WinEventHookWrapper { inner: hook }),
+      }
+    }
+  }
+}
+
+impl Drop for WinEventHookWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      UnhookWinEvent(self.inner);
+    }
+  }
+}
+
+pub(crate) struct HdeskWrapper {
+  inner: HDESK,
+}
+
+impl HdeskWrapper {
+  pub(crate) fn open_input() -> Result<HdeskWrapper> {
+    unsafe {
+      let desktop = OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS)?;
+      Ok(HdeskWrapper { inner: desktop })
+    }
+  }
+
+  pub(crate) fn inner(&self) -> HDESK {
+    self.inner
+  }
+}
+
+impl Drop for HdeskWrapper {
+  fn drop(&mut self) {
+    unsafe {
+      CloseDesktop(self.inner);
+    }
+  }
+}
@@ -0,0 +1,235 @@
+use crate::{parallel::map_pixels, Capture, Result, Screenshot, BGRA};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum YuvMatrix {
+  #[default]
+  Bt601,
+  Bt709,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YuvFormat {
+  Nv12,
+  I420,
+}
+
+#[derive(Clone, Debug)]
+pub struct YuvFrame {
+  width: u32,
+  height: u32,
+  format: YuvFormat,
+  matrix: YuvMatrix,
+  data: Vec<u8>,
+}
+
+impl YuvFrame {
+  pub(crate) fn new(
+    width: u32,
+    height: u32,
+    format: YuvFormat,
+    matrix: YuvMatrix,
+    data: Vec<u8>,
+  ) -> Self {
+    Self {
+      width,
+      height,
+      format,
+      matrix,
+      data,
+    }
+  }
+
+  pub(crate) fn from_bgra<C: Capture + ?Sized>(
+    capture: &mut C,
+    format: YuvFormat,
+    matrix: YuvMatrix,
+  ) -> Result<Self> {
+    capture.read()?;
+    let screenshot = capture.frame_buffer().bgr_screenshot();
+    Ok(Self::from_screenshot(&screenshot, format, matrix))
+  }
+
+  pub(crate) fn from_screenshot(
+    screenshot: &Screenshot<'_, BGRA>,
+    format: YuvFormat,
+    matrix: YuvMatrix,
+  ) -> Self {
+    let width = screenshot.width() as usize;
+    let height = screenshot.height() as usize;
+    let pixels = screenshot.packed();
+    let chroma_width = width.div_ceil(2);
+    let chroma_height = height.div_ceil(2);
+    let luma_len = width * height;
+    let chroma_len = chroma_width * chroma_height;
+    let [y_row, u_row, v_row] = matrix.coefficients();
+
+    let mut data = vec![0; luma_len + 2 * chroma_len];
+    let (luma, chroma) = data.split_at_mut(luma_len);
+    map_pixels(&pixels, 4, luma, 1, |pixel, luma| {
+      luma[0] = convert(y_row, [pixel[2], pixel[1], pixel[0]], 16);
+    });
+
+    for cy in 0..chroma_height {
+      for cx in 0..chroma_width {
+        let rgb = average(&pixels, width, height, cx * 2, cy * 2);
+        let u = convert(u_row, rgb, 128);
+        let v = convert(v_row, rgb, 128);
+        let index = cy * chroma_width + cx;
+        match format {
+          YuvFormat::Nv12 => {
+            chroma[2 * index] = u;
+            chroma[2 * index + 1] = v;
+          }
+          YuvFormat::I420 => {
+            chroma[index] = u;
+            chroma[chroma_len + index] = v;
+          }
+        }
+      }
+    }
+
+    Self {
+      width: width as u32,
+      height: height as u32,
+      format,
+      matrix,
+      data,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn format(&self) -> YuvFormat {
+    self.format
+  }
+
+  pub fn matrix(&self) -> YuvMatrix {
+    self.matrix
+  }
+
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  pub fn into_data(self) -> Vec<u8> {
+    self.data
+  }
+
+  pub fn y_plane(&self) -> &[u8] {
+    &self.data[..self.luma_len()]
+  }
+
+  pub fn uv_plane(&self) -> Option<&[u8]> {
+    match self.format {
+      YuvFormat::Nv12 => Some(&self.data[self.luma_len()..]),
+      YuvFormat::I420 => None,
+    }
+  }
+
+  pub fn u_plane(&self) -> Option<&[u8]> {
+    let start = self.luma_len();
+    match self.format {
+      YuvFormat::Nv12 => None,
+      YuvFormat::I420 => Some(&self.data[start..start + self.chroma_len()]),
+    }
+  }
+
+  pub fn v_plane(&self) -> Option<&[u8]> {
+    let start = self.luma_len() + self.chroma_len();
+    match self.format {
+      YuvFormat::Nv12 => None,
+      YuvFormat::I420 => Some(&self.data[start..]),
+    }
+  }
+
+  fn luma_len(&self) -> usize {
+    (self.width * self.height) as usize
+  }
+
+  fn chroma_len(&self) -> usize {
+    (self.width.div_ceil(2) * self.height.div_ceil(2)) as usize
+  }
+}
+
+impl YuvMatrix {
+  pub(crate) fn coefficients(self) -> [[i32; 3]; 3] {
+    match self {
+      Self::Bt601 => [[66, 129, 25], [-38, -74, 112], [112, -94, -18]],
+      Self::Bt709 => [[47, 157, 16], [-26, -87, 112], [112, -102, -10]],
+    }
+  }
+}
+
+fn convert(row: [i32; 3], [r, g, b]: [u8; 3], offset: i32) -> u8 {
+  let value = (row[0] * r as i32 + row[1] * g as i32 + row[2] * b as i32 + 128) >> 8;
+  (value + offset).clamp(0, 255) as u8
+}
+
+fn average(bgra: &[u8], width: usize, height: usize, x: usize, y: usize) -> [u8; 3] {
+  let mut sum = [0u32; 3];
+  let mut count = 0;
+  for py in y..(y + 2).min(height) {
+    for px in x..(x + 2).min(width) {
+      let pixel = &bgra[(py * width + px) * 4..];
+      sum[0] += pixel[2] as u32;
+      sum[1] += pixel[1] as u32;
+      sum[2] += pixel[0] as u32;
+      count += 1;
+    }
+  }
+  sum.map(|channel| ((channel + count / 2) / count) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::FrameBuffer};
+
+  fn solid(width: i32, height: i32, bgra: [u8; 4]) -> FrameBuffer {
+    let mut frame = FrameBuffer::new(width, height);
+    frame
+      .buffer_mut()
+      .chunks_exact_mut(4)
+      .for_each(|pixel| pixel.copy_from_slice(&bgra));
+    frame
+  }
+
+  #[test]
+  fn converts_red_to_bt601_nv12() {
+    let mut frame = solid(3, 3, [0, 0, 255, 255]);
+    let yuv = YuvFrame::from_screenshot(&frame.bgr_screenshot(), YuvFormat::Nv12, YuvMatrix::Bt601);
+    assert_eq!(yuv.y_plane(), [82; 9]);
+    assert_eq!(
+      yuv.uv_plane(),
+      Some(&[90, 240, 90, 240, 90, 240, 90, 240][..])
+    );
+    assert_eq!(yuv.u_plane(), None);
+  }
+
+  #[test]
+  fn converts_white_and_black_to_i420() {
+    let mut frame = solid(2, 2, [255, 255, 255, 255]);
+    let yuv = YuvFrame::from_screenshot(&frame.bgr_screenshot(), YuvFormat::I420, YuvMatrix::Bt601);
+    assert_eq!(yuv.y_plane(), [235; 4]);
+    assert_eq!(yuv.u_plane(), Some(&[128][..]));
+    assert_eq!(yuv.v_plane(), Some(&[128][..]));
+
+    let mut frame = solid(2, 2, [0, 0, 0, 255]);
+    let yuv = YuvFrame::from_screenshot(&frame.bgr_screenshot(), YuvFormat::I420, YuvMatrix::Bt709);
+    assert_eq!(yuv.y_plane(), [16; 4]);
+    assert_eq!(yuv.u_plane(), Some(&[128][..]));
+    assert_eq!(yuv.data().len(), 6);
+  }
+
+  #[test]
+  fn averages_chroma_over_partial_blocks() {
+    let pixels = [255, 255, 255, 255, 0, 0, 0, 255, 9, 9, 9, 255];
+    assert_eq!(average(&pixels, 3, 1, 0, 0), [128, 128, 128]);
+    assert_eq!(average(&pixels, 3, 1, 2, 0), [9, 9, 9]);
+  }
+}